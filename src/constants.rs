@@ -1,5 +1,6 @@
 use ash::vk;
 use std::ffi::CStr;
+use std::time::Duration;
 
 //Consts for window creation
 pub const WINDOW_TITLE: &str = "Jarmungular Engine";
@@ -12,16 +13,82 @@ pub const DESIRED_PRESENTATION_MODE: vk::PresentModeKHR = vk::PresentModeKHR::MA
 //Can enable/disable validation layers for debug/release
 pub const VALIDATION_ENABLED: bool = true;
 pub const VALIDATION_LAYERS: [&str; 1] = ["VK_LAYER_KHRONOS_validation"];
+//Which validation messages get logged. Info + verbose are mostly the loader talking, add them in when something's really weird
+pub const VALIDATION_LOG_SEVERITY: vk::DebugUtilsMessageSeverityFlagsEXT = vk::DebugUtilsMessageSeverityFlagsEXT::from_raw(vk::DebugUtilsMessageSeverityFlagsEXT::WARNING.as_raw() | vk::DebugUtilsMessageSeverityFlagsEXT::ERROR.as_raw());
+//Panic after a frame if the validation layers reported any errors while it was drawn - so tests fail on them instead of just printing
+pub const PANIC_ON_VALIDATION_ERROR: bool = cfg!(test);
 
 //Required instance and device extensions are here
-pub const INSTANCE_EXTENSIONS: [&CStr; 2] = [
+//The platform specific surface extension (win32/xlib/xcb/wayland) gets added at runtime in "required_extension_names"
+pub const INSTANCE_EXTENSIONS: [&CStr; 1] = [
 	vk::KHR_SURFACE_NAME,
-	vk::KHR_WIN32_SURFACE_NAME, //WINDOWS ONLY for now
 ];
 pub const DEVICE_EXTENSIONS: [&CStr; 1] = [vk::KHR_SWAPCHAIN_NAME];
 
+//Watch the GLSL shader sources and recompile/rebuild the pipeline when they change (see "hot_reload"). Only for debug builds
+pub const SHADER_HOT_RELOAD: bool = cfg!(debug_assertions);
+//How often to check the shader sources for changes
+pub const SHADER_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
 //Mouse sensitivity - measured in degrees per mouse increment
 pub const SENSITIVITY: f32 = 0.04;
 
+//Number of frames the CPU can queue up on the GPU before it has to wait. More = less CPU stalling, but more input lag
+pub const MAX_FRAMES_IN_FLIGHT: usize = 2;
+
+//How big each descriptor pool is - descriptors of each type, and total sets. More pools get made if these fill up
+pub const DESCRIPTOR_POOL_SIZES: [(vk::DescriptorType, u32); 2] = [
+	(vk::DescriptorType::UNIFORM_BUFFER, 64),
+	(vk::DescriptorType::COMBINED_IMAGE_SAMPLER, 64),
+];
+pub const DESCRIPTOR_POOL_MAX_SETS: u32 = 64;
+
+//Size of each big block of GPU memory the allocator splits up. Anything bigger gets its own block. Gets shrunk on small memory heaps
+pub const MEMORY_BLOCK_SIZE: vk::DeviceSize = 64 * 1024 * 1024;
+
+//Room each frame slot gets in the streaming geometry buffer for dynamic objects' vertices + indices. Objects that don't fit in a frame get skipped that frame
+pub const STREAMING_GEOMETRY_REGION_SIZE: vk::DeviceSize = 4 * 1024 * 1024;
+
+//Room each frame slot gets in the streaming instance buffer - 80 bytes per instance, so ~13k objects a frame. Batches that don't fit get skipped that frame
+pub const STREAMING_INSTANCE_REGION_SIZE: vk::DeviceSize = 1024 * 1024;
+
+//Room each frame slot gets for overlay (HUD text) vertices - 32 bytes per vertex, 12 per character with its shadow, so ~2.7k characters a frame
+pub const OVERLAY_REGION_SIZE: vk::DeviceSize = 1024 * 1024;
+
+//Room each frame slot gets for debug line vertices - 24 bytes per vertex, so ~21k lines a frame. Lines past that get dropped that frame
+pub const DEBUG_LINE_REGION_SIZE: vk::DeviceSize = 1024 * 1024;
+
+//Size of the staging ring asset uploads go through. Uploads wait on older ones when it fills up, anything bigger than the whole ring gets its own staging buffer
+pub const STAGING_RING_SIZE: vk::DeviceSize = 32 * 1024 * 1024;
+
 //FPS - will make a setting later
-pub const FPS: f32 = 240.0;
\ No newline at end of file
+pub const FPS: f32 = 240.0;
+
+//HUD - font texels are drawn this many pixels big, this far in from the top left corner of the screen
+pub const HUD_TEXT_SCALE: f32 = 2.0;
+pub const HUD_MARGIN: f32 = 8.0;
+//How many frames the HUD's fps + frame time are averaged over
+pub const HUD_FRAME_SAMPLES: usize = 60;
+//How long HUD messages stay up, in seconds, and how many can be up at once (oldest go first)
+pub const HUD_MESSAGE_LIFETIME: f32 = 4.0;
+pub const HUD_MAX_MESSAGES: usize = 8;
+//How many frames the GPU timings (on the HUD) are averaged over
+pub const GPU_PROFILER_SAMPLES: usize = 60;
+
+//Debug shapes (F5) - the player's velocity arrow is this many timesteps of movement long, so it's actually visible
+pub const DEBUG_VELOCITY_ARROW_SCALE: f32 = 30.0;
+//How long a frozen camera frustum (F6) sticks around, in seconds
+pub const DEBUG_FRUSTUM_LIFETIME: f32 = 10.0;
+
+//Color format of the offscreen image when running headless. Same as the preferred swapchain format, so readbacks come out as srgb rgba bytes
+pub const OFFSCREEN_FORMAT: vk::Format = vk::Format::R8G8B8A8_SRGB;
+
+
+//Folder the pipeline cache gets saved into (relative to wherever the engine is run from)
+pub const PIPELINE_CACHE_DIR: &str = "cache";
+
+//Folder that screenshots get saved into (relative to wherever the engine is run from)
+pub const SCREENSHOT_DIR: &str = "screenshots";
+
+//Folder that frame captures get saved into. Each capture gets its own timestamped folder in here
+pub const CAPTURE_DIR: &str = "captures";
\ No newline at end of file
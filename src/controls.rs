@@ -1,6 +1,8 @@
 use crate::render::pipeline;
 use crate::scene::Scene;
-use crate::utility::debug;
+use crate::scene::debug_draw::GREEN;
+use crate::constants::DEBUG_FRUSTUM_LIFETIME;
+use crate::utility::{debug, capture};
 
 use std::f32::consts::PI;
 use std::collections::HashSet;
@@ -23,6 +25,9 @@ pub struct ControlQueues {
 	held_mouse_buttons: HashSet<MouseButton>, //A set (no duplicate elements) of all the mouse buttons being held down
 
 	knobs: Vec<debug::Knob>, //Holds all midi knob positions. Not really a queue, but this is the best place to put it.
+
+	pub screenshot_requested: bool, //Set when the screenshot key is pressed. The game loop takes the screenshot after the frame is drawn, then clears this
+	pub capture_toggle_requested: Option<capture::CaptureFormat>, //Set when a frame capture key is pressed. The game loop starts a capture in this format (or stops the running one), then clears this
 }
 
 impl ControlQueues {
@@ -36,6 +41,9 @@ impl ControlQueues {
 			held_mouse_buttons: HashSet::new(),
 
 			knobs: vec![debug::Knob::init(); 8],
+
+			screenshot_requested: false,
+			capture_toggle_requested: None,
 		}
 	}
 
@@ -59,7 +67,7 @@ impl ControlQueues {
 
 	//Run through all the controls that happened in a frame, execute them
 	//Key, mouse, and raw mouse queues are separate. The match statement is kinda running twice so all the fields of the enums can be extracted, but this way is much easier for readability
-	pub fn execute_controls(&mut self, vulkan_app: &pipeline::VulkanApp, window: &Window, scene: &mut Scene, event_loop: &ActiveEventLoop) {
+	pub fn execute_controls(&mut self, vulkan_app: &mut pipeline::VulkanApp, window: &Window, scene: &mut Scene, event_loop: &ActiveEventLoop) {
 		self.keyboard_queue_execute(vulkan_app, window, scene, event_loop);
 		self.mouse_queue_execute(vulkan_app, window, scene, event_loop);
 		self.raw_mouse_queue_execute(vulkan_app, window, scene, event_loop);
@@ -68,7 +76,7 @@ impl ControlQueues {
 	}
 
 	//Key press
-	fn keyboard_queue_execute(&mut self, vulkan_app: &pipeline::VulkanApp, window: &Window, scene: &mut Scene, event_loop: &ActiveEventLoop) {
+	fn keyboard_queue_execute(&mut self, vulkan_app: &mut pipeline::VulkanApp, window: &Window, scene: &mut Scene, event_loop: &ActiveEventLoop) {
 		//Loop through keyboard events
 		for event in &self.key_queue {
 			if let WindowEvent::KeyboardInput{device_id, event, is_synthetic} = event {
@@ -91,6 +99,59 @@ impl ControlQueues {
 						event_loop.exit();
 					},
 
+					//F12 takes a screenshot. Can't do it here since the frame hasn't been drawn yet, so just flag it for the game loop
+					(Key::Named(NamedKey::F12), ElementState::Pressed) => {
+						self.screenshot_requested = true;
+					},
+
+					//F1 shows/hides the HUD
+					(Key::Named(NamedKey::F1), ElementState::Pressed) => {
+						scene.hud.visible = !scene.hud.visible;
+					},
+
+					//F2 cycles through the render modes (fill, wireframe, and the debug views)
+					(Key::Named(NamedKey::F2), ElementState::Pressed) => {
+						scene.hud.message(vulkan_app.cycle_render_mode());
+					},
+
+					//F3 shows how much GPU memory is being used, and how fragmented it is
+					(Key::Named(NamedKey::F3), ElementState::Pressed) => {
+						for line in vulkan_app.memory_stats_lines() {
+							scene.hud.message(line);
+						}
+					},
+
+					//F4 shows how many objects got drawn/culled last frame
+					(Key::Named(NamedKey::F4), ElementState::Pressed) => {
+						scene.hud.message(format!("Culling: {}", vulkan_app.cull_stats()));
+					},
+
+					//F7 shows how long each part of the frame is taking on the GPU
+					(Key::Named(NamedKey::F7), ElementState::Pressed) => {
+						for (scope_name, milliseconds) in vulkan_app.gpu_timings() {
+							scene.hud.message(format!("GPU {}: {:.3} ms", scope_name, milliseconds));
+						}
+					},
+
+					//F5 toggles the debug shapes (player velocity, bounding boxes, axes)
+					(Key::Named(NamedKey::F5), ElementState::Pressed) => {
+						scene.show_debug_shapes = !scene.show_debug_shapes;
+						scene.hud.message(format!("Debug shapes: {}", if scene.show_debug_shapes {"on"} else {"off"}));
+					},
+
+					//F6 leaves the camera's current frustum behind for a bit, to look at from the outside (and check culling against)
+					(Key::Named(NamedKey::F6), ElementState::Pressed) => {
+						scene.debug_draw.frustum(scene.camera.get_render_matrix(), GREEN, Some(DEBUG_FRUSTUM_LIFETIME));
+					},
+
+					//F9 starts/stops capturing every frame to a y4m video, F10 does the same as a png sequence
+					(Key::Named(NamedKey::F9), ElementState::Pressed) => {
+						self.capture_toggle_requested = Some(capture::CaptureFormat::Y4m);
+					},
+					(Key::Named(NamedKey::F10), ElementState::Pressed) => {
+						self.capture_toggle_requested = Some(capture::CaptureFormat::PngSequence);
+					},
+
 					//Debug with midi knobs
 					(Key::Character("m"), ElementState::Pressed) => {
 						//Setup value names being changed
@@ -124,7 +185,8 @@ impl ControlQueues {
 						];
 
 						let knob_id = debug::midi_debug_controls(&self.held_keys, &mut self.knobs, &mut to_adjust_vec, bounds_vec);
-						println!("{:?} value adjusted to {:?}", to_adjust_names[knob_id], to_adjust_vec[knob_id]);
+						let adjusted_message = format!("{} adjusted to {:.3}", to_adjust_names[knob_id], to_adjust_vec[knob_id]);
+						scene.hud.message(adjusted_message);
 					}
 
 					_ => (),
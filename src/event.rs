@@ -2,7 +2,7 @@ use crate::constants::*;
 use crate::render::pipeline;
 use crate::scene::Scene;
 use crate::controls;
-use crate::utility::read;
+use crate::utility::{read, capture};
 
 use std::{
     thread::sleep,
@@ -22,6 +22,8 @@ pub struct EventHandler {
 	pub scene: Scene, //The scene containing all the fun stuff
 
 	pub control_queues: controls::ControlQueues,
+
+	pub frame_capture: Option<capture::FrameCapture>, //Running frame capture, if there is one. Game loop runs on a virtual clock while this exists
 }
 
 impl EventHandler {
@@ -35,13 +37,15 @@ impl EventHandler {
 			vulkan_app: None,
 			scene: Scene::init_scene(),
 
-			control_queues
+			control_queues,
+
+			frame_capture: None,
 		}
 	}
 
 	//Game loop - called on redraw request in "window_event" fn
 	fn game_loop(&mut self, event_loop: &ActiveEventLoop) {
-		let vulkan_app = self.vulkan_app.as_ref().unwrap();
+		let vulkan_app = self.vulkan_app.as_mut().unwrap();
 		let window = self.window.as_ref().unwrap();
 		let scene = &mut self.scene;
 
@@ -52,27 +56,97 @@ impl EventHandler {
 
 		//Execute all the controls that happened this frame, then clear the control queue
 		control_queues.execute_controls(vulkan_app, window, scene, event_loop);
-		self.control_queues.clear();
+		control_queues.clear();
 
 		//Update the scene
-		scene.update();
+		//While capturing, game time only moves on once the last timestep's frame has been written. Otherwise the capture would skip timesteps and its clock wouldn't match the frames on disk
+		if self.frame_capture.as_ref().is_none_or(|frame_capture| frame_capture.ready_for_timestep()) {
+			scene.update();
+		}
+
+		//Get any meshes/textures that got added to the scene onto the GPU before drawing
+		vulkan_app.upload_scene_assets(scene);
+		//Pick up any shader edits (debug builds only)
+		vulkan_app.reload_changed_shaders();
+
+		//Screenshots need the swapchain to be copyable - if it isn't, say so instead of waiting forever for a frame that never comes back
+		if control_queues.screenshot_requested && !vulkan_app.swapchain_readback_supported() {
+			scene.hud.message(String::from("Screenshots aren't supported - this surface's swapchain images can't be copied from"));
+			control_queues.screenshot_requested = false;
+		}
+
+		//If a screenshot was asked for (or a capture's running), have this frame copied back as it's drawn
+		if control_queues.screenshot_requested || self.frame_capture.is_some() {
+			vulkan_app.request_swapchain_readback();
+		}
 
 		//Acquire a swapchain image, render to it, then present it from the swapchain
 		vulkan_app.draw_frame(window, scene);
+		//Timings from the GPU's last few frames, for the HUD
+		scene.hud.gpu_timings = vulkan_app.gpu_timings();
+
+		//Frame that just got drawn, if it was copied back
+		let readback = vulkan_app.take_swapchain_readback();
+
+		//Save the screenshot once the frame's actually been drawn. If it wasn't (minimized, out of date swapchain), try again next frame
+		if control_queues.screenshot_requested {
+			if let Some((extent, pixels)) = &readback {
+				capture::save_screenshot(*extent, pixels);
+				control_queues.screenshot_requested = false;
+			}
+		}
+
+		//Start or stop a frame capture if one of the capture keys was pressed
+		if let Some(capture_format) = control_queues.capture_toggle_requested.take() {
+			self.frame_capture = match self.frame_capture.take() {
+				Some(frame_capture) => {
+					frame_capture.finish();
+					None
+				},
+				None if !vulkan_app.swapchain_readback_supported() => {
+					scene.hud.message(String::from("Frame capture isn't supported - this surface's swapchain images can't be copied from"));
+					None
+				},
+				None => Some(capture::FrameCapture::start(capture_format)),
+			};
+		}
+
+		match &mut self.frame_capture {
+			//While capturing, game time comes from the capture's virtual clock instead of the wall clock
+			//Every frame is exactly one timestep, so don't sleep or skip - writing frames can take as long as it wants and the capture still plays back smooth
+			//A frame that didn't come back gets drawn again next loop, without stepping the game
+			Some(frame_capture) => {
+				let result = match &readback {
+					Some((extent, pixels)) => frame_capture.capture_frame(*extent, pixels),
+					None => {
+						frame_capture.miss_frame();
+						Ok(())
+					},
+				};
+				if let Err(message) = result {
+					scene.hud.message(message);
+					self.frame_capture.take().unwrap().finish();
+				}
+			},
 
-		//Target time for one frame
-		let frame_time = Duration::from_secs_f32(1.0 / FPS);
-		//Check the elapsed time
-		let elapsed_time = initial_time.elapsed();
-
-		//Right now, just sleep until the next frame-ish time
-		//If loop was too long, just run the next one ASAP
-		//This is a horrible way to do it, but works for now
-		if elapsed_time < frame_time {
-			let time_to_sleep = frame_time - elapsed_time;
-			sleep(time_to_sleep);
-		} else {
-			println!("Skipped frame at {:?} fps", FPS);
+			//Otherwise, pace to the wall clock
+			None => {
+				//Target time for one frame
+				let frame_time = Duration::from_secs_f32(1.0 / FPS);
+				//Check the elapsed time
+				let elapsed_time = initial_time.elapsed();
+				scene.hud.record_frame(elapsed_time);
+
+				//Right now, just sleep until the next frame-ish time
+				//If loop was too long, just run the next one ASAP
+				//This is a horrible way to do it, but works for now
+				if elapsed_time < frame_time {
+					let time_to_sleep = frame_time - elapsed_time;
+					sleep(time_to_sleep);
+				} else {
+					scene.hud.message(format!("Skipped frame at {:?} fps", FPS));
+				}
+			},
 		}
 
 
@@ -96,7 +170,11 @@ impl EventHandler {
 		window.set_cursor_grab(CursorGrabMode::Confined).expect("Failed to set cursor mode");
 
 		//Then set up the vulkan app
-		let vulkan_app = pipeline::VulkanApp::init_vulkan(&window);
+		let vulkan_app = pipeline::VulkanApp::init_vulkan(&window, &self.scene);
+		self.scene.hud.message(format!("Running on {}", vulkan_app.device_name()));
+		for line in vulkan_app.device_info_lines() {
+			self.scene.hud.message(line);
+		}
 
 		//Set vulkan app handler's fields now
 		self.window = Some(window);
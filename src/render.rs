@@ -1,37 +1,74 @@
 pub mod pipeline;
 pub mod memory;
+pub mod offscreen;
+pub mod mesh;
+pub mod descriptors;
+pub mod texture;
+pub mod streaming;
+pub mod upload;
+pub mod hot_reload;
+pub mod reflection;
+pub mod pipeline_cache;
+pub mod text;
+pub mod debug_utils;
+pub mod profiler;
+#[cfg(test)]
+mod golden;
+
+use crate::render::descriptors::DescriptorBinding;
 
 use ash::vk;
-use glam::f32::{Vec3, Vec4};
+use glam::f32::{Mat4, Vec2, Vec3, Vec4};
 
+#[derive(Debug, Clone, Copy)]
 pub struct Vertex {
 	pub pos: Vec4,
 	pub color: Vec3,
+	pub normal: Vec3, //Model space. Zero means "don't shade this", the color gets used as is
+	pub uv: Vec2, //Texture coordinates, (0, 0) is the top left of the texture
 }
 
 impl Vertex {
 	//Initializes a new vertex
-	pub fn new(pos: Vec4, color: Vec3) -> Vertex {
+	pub fn new(pos: Vec4, color: Vec3, normal: Vec3, uv: Vec2) -> Vertex {
 		Vertex {
 			pos,
 			color,
+			normal,
+			uv,
 		}
 	}
 
 	//Get vertex binding descriptions for vulkan
-	//Specifies how an array of vertices will be passed to the vertex shader
-	pub fn get_binding_descriptions() -> [vk::VertexInputBindingDescription; 1] {
-		//Only need one of these, since the vertex data is all in one array
-		[vk::VertexInputBindingDescription {
-			binding: 0, //Index of this binding in the array of bindings
-			stride: core::mem::size_of::<Vertex>() as u32, //Bytes from one entry to the next (in this case, size of the vertex struct)
-			input_rate: vk::VertexInputRate::VERTEX, //Move to the next data entry after each vertex or instance - "vk::VertexInputRate::VERTEX" would be required for instanced rendering
-		}]
+	//Specifies how the arrays of vertex data will be passed to the vertex shader
+	pub fn get_binding_descriptions() -> [vk::VertexInputBindingDescription; 2] {
+		[
+			//The mesh's vertices
+			vk::VertexInputBindingDescription {
+				binding: 0, //Index of this binding in the array of bindings
+				stride: core::mem::size_of::<Vertex>() as u32, //Bytes from one entry to the next (in this case, size of the vertex struct)
+				input_rate: vk::VertexInputRate::VERTEX, //Move to the next data entry after each vertex or instance
+			},
+			//Per instance data - the shader only moves to the next entry once per instance, so every vertex of a copy gets the same matrix/tint
+			vk::VertexInputBindingDescription {
+				binding: 1,
+				stride: core::mem::size_of::<InstanceData>() as u32,
+				input_rate: vk::VertexInputRate::INSTANCE,
+			},
+		]
 	}
 
 	//Set vertex attributes for vulkan
 	//Specifies how to extract vertex attributes (position, color, etc) originating from a binding description
-	pub fn get_attribute_descriptions() -> [vk::VertexInputAttributeDescription; 2] {
+	pub fn get_attribute_descriptions() -> [vk::VertexInputAttributeDescription; 9] {
+		//The model matrix goes in as 4 vec4 attributes in a row, one location for each column
+		let model_column = |column: u32| vk::VertexInputAttributeDescription {
+			location: 4 + column,
+			binding: 1,
+			format: vk::Format::R32G32B32A32_SFLOAT,
+			offset: core::mem::offset_of!(InstanceData, model) as u32 + column * core::mem::size_of::<Vec4>() as u32,
+		};
+
 		[
 			//Vertex attribute description
 			vk::VertexInputAttributeDescription {
@@ -47,6 +84,178 @@ impl Vertex {
 				format: vk::Format::R32G32B32_SFLOAT, //Here, for color, vec4 would be VK_FORMAT_R32G32B32_SFLOAT
 				offset: core::mem::offset_of!(Vertex, color) as u32,
 			},
+			//Normal attribute description
+			vk::VertexInputAttributeDescription {
+				location: 2,
+				binding: 0,
+				format: vk::Format::R32G32B32_SFLOAT,
+				offset: core::mem::offset_of!(Vertex, normal) as u32,
+			},
+			//Uv attribute description
+			vk::VertexInputAttributeDescription {
+				location: 3,
+				binding: 0,
+				format: vk::Format::R32G32_SFLOAT,
+				offset: core::mem::offset_of!(Vertex, uv) as u32,
+			},
+			//Instance model matrix - locations 4 to 7
+			model_column(0),
+			model_column(1),
+			model_column(2),
+			model_column(3),
+			//Instance tint
+			vk::VertexInputAttributeDescription {
+				location: 8,
+				binding: 1,
+				format: vk::Format::R32G32B32A32_SFLOAT,
+				offset: core::mem::offset_of!(InstanceData, tint) as u32,
+			},
+		]
+	}
+}
+
+//Vertex for the debug line pipeline - just a world space position and a color
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct DebugVertex {
+	pub pos: Vec3,
+	pub color: Vec3,
+}
+
+impl DebugVertex {
+	//Same idea as "Vertex::get_binding_descriptions", but only the one binding - no instancing for lines
+	pub fn get_binding_descriptions() -> [vk::VertexInputBindingDescription; 1] {
+		[vk::VertexInputBindingDescription {
+			binding: 0,
+			stride: core::mem::size_of::<DebugVertex>() as u32,
+			input_rate: vk::VertexInputRate::VERTEX,
+		}]
+	}
+
+	//Has to match the inputs in "debug_line.vert"
+	pub fn get_attribute_descriptions() -> [vk::VertexInputAttributeDescription; 2] {
+		[
+			vk::VertexInputAttributeDescription {
+				location: 0,
+				binding: 0,
+				format: vk::Format::R32G32B32_SFLOAT,
+				offset: core::mem::offset_of!(DebugVertex, pos) as u32,
+			},
+			vk::VertexInputAttributeDescription {
+				location: 1,
+				binding: 0,
+				format: vk::Format::R32G32B32_SFLOAT,
+				offset: core::mem::offset_of!(DebugVertex, color) as u32,
+			},
 		]
 	}
-}
\ No newline at end of file
+}
+
+//Vertex for the overlay pipeline (HUD text) - a screen position in pixels from the top left, a uv into the font atlas, and a color
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct OverlayVertex {
+	pub pos: Vec2,
+	pub uv: Vec2,
+	pub color: Vec4,
+}
+
+impl OverlayVertex {
+	//Just the one binding, same as "DebugVertex"
+	pub fn get_binding_descriptions() -> [vk::VertexInputBindingDescription; 1] {
+		[vk::VertexInputBindingDescription {
+			binding: 0,
+			stride: core::mem::size_of::<OverlayVertex>() as u32,
+			input_rate: vk::VertexInputRate::VERTEX,
+		}]
+	}
+
+	//Has to match the inputs in "overlay.vert"
+	pub fn get_attribute_descriptions() -> [vk::VertexInputAttributeDescription; 3] {
+		[
+			vk::VertexInputAttributeDescription {
+				location: 0,
+				binding: 0,
+				format: vk::Format::R32G32_SFLOAT,
+				offset: core::mem::offset_of!(OverlayVertex, pos) as u32,
+			},
+			vk::VertexInputAttributeDescription {
+				location: 1,
+				binding: 0,
+				format: vk::Format::R32G32_SFLOAT,
+				offset: core::mem::offset_of!(OverlayVertex, uv) as u32,
+			},
+			vk::VertexInputAttributeDescription {
+				location: 2,
+				binding: 0,
+				format: vk::Format::R32G32B32A32_SFLOAT,
+				offset: core::mem::offset_of!(OverlayVertex, color) as u32,
+			},
+		]
+	}
+}
+
+//How the scene gets drawn - the normal way, or one of the debug views for checking geometry
+//Each one is its own pipeline variant (see "RenderPipelines")
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RenderMode {
+	Fill, //Shaded + textured, the normal way
+	Wireframe, //Just the triangle edges. Needs the "fillModeNonSolid" device feature, draws filled without it
+	VertexColor, //Vertex color * tint, no lighting or textures
+	Normals, //World space normals as colors
+	Depth, //Distance from the camera, white up close fading to black
+}
+
+impl RenderMode {
+	//The mode after this one, for cycling through them with a key
+	pub fn next(self) -> RenderMode {
+		match self {
+			RenderMode::Fill => RenderMode::Wireframe,
+			RenderMode::Wireframe => RenderMode::VertexColor,
+			RenderMode::VertexColor => RenderMode::Normals,
+			RenderMode::Normals => RenderMode::Depth,
+			RenderMode::Depth => RenderMode::Fill,
+		}
+	}
+
+	//What "debug.frag" should show, pushed as a push constant. None for the modes that use the normal fragment shader
+	pub fn debug_view(self) -> Option<u32> {
+		match self {
+			RenderMode::Fill | RenderMode::Wireframe => None,
+			RenderMode::VertexColor => Some(0),
+			RenderMode::Normals => Some(1),
+			RenderMode::Depth => Some(2),
+		}
+	}
+}
+
+//Everything that's different between copies of a mesh - one of these for each copy, in vertex binding 1
+//Has to match the instance inputs in "vertex.vert". Streamed every frame, so moving objects around is free
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct InstanceData {
+	pub model: Mat4, //Object's model matrix (model -> world space)
+	pub tint: Vec4, //Multiplied into the vertex color. White leaves it alone (alpha is unused for now)
+}
+
+//Everything that's the same for every draw in a frame - goes in a uniform buffer (set 0, binding 0), rewritten every frame
+//Has to match the "FrameData" block in the shaders, with std140 layout rules - vec3s take up a whole vec4, so everything's a vec4 here
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct FrameUniforms {
+	pub view_projection: Mat4, //Camera's render matrix (world -> clip space)
+	pub light_dir: Vec4, //Direction the light comes from (world space, w unused). Doesn't need to be normalized
+}
+
+//Layout of descriptor set 0 - the per frame set
+pub const FRAME_SET_BINDINGS: [DescriptorBinding; 1] = [
+	DescriptorBinding::new(0, vk::DescriptorType::UNIFORM_BUFFER, vk::ShaderStageFlags::VERTEX), //FrameUniforms
+];
+
+//Layout of descriptor set 1 - one set for each texture, bound per draw
+pub const TEXTURE_SET_BINDINGS: [DescriptorBinding; 1] = [
+	DescriptorBinding::new(0, vk::DescriptorType::COMBINED_IMAGE_SAMPLER, vk::ShaderStageFlags::FRAGMENT), //Base color texture
+];
+
+//Every set the pipeline uses, in set order. The shaders get checked against these when they're loaded
+pub const PIPELINE_SET_BINDINGS: [&[DescriptorBinding]; 2] = [&FRAME_SET_BINDINGS, &TEXTURE_SET_BINDINGS];
\ No newline at end of file
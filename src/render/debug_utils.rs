@@ -0,0 +1,141 @@
+use crate::constants::{VALIDATION_ENABLED, VALIDATION_LOG_SEVERITY};
+
+use ash::{vk, ext};
+use std::ffi::{c_void, CStr, CString};
+use std::ptr;
+use std::sync::Mutex;
+
+//Everything from the "VK_EXT_debug_utils" extension - validation messages coming back from the driver/layers, and names + labels going out to them
+//Names and labels show up in validation messages ("VkBuffer 0x1234[streaming instances]" instead of just a handle) and in captures from tools like RenderDoc
+//Only used with validation on, and only if the extension's there. Everything here does nothing otherwise
+
+
+//Validation errors that came in since the last "take_validation_errors"
+//The callback can't panic itself (it's called from C, and unwinding out of it would abort), so errors get stashed here and checked from rust code instead
+static VALIDATION_ERRORS: Mutex<Vec<String>> = Mutex::new(vec![]);
+
+//True if the debug utils extension should be turned on - validation's on, and the vulkan implementation has it
+pub fn debug_utils_available(entry: &ash::Entry) -> bool {
+	if !VALIDATION_ENABLED {
+		return false
+	}
+	let available_extensions = unsafe { entry.enumerate_instance_extension_properties(None).expect("Failed to enumerate instance extensions") };
+	available_extensions.iter().any(|extension_properties| extension_properties.extension_name_as_c_str() == Ok(ext::debug_utils::NAME))
+}
+
+//Messenger info for "debug_callback", at whatever severities "VALIDATION_LOG_SEVERITY" lets through
+//Also goes in the instance create info's p_next, so messages from making/destroying the instance itself get caught too
+pub fn messenger_create_info() -> vk::DebugUtilsMessengerCreateInfoEXT<'static> {
+	vk::DebugUtilsMessengerCreateInfoEXT {
+		s_type: vk::StructureType::DEBUG_UTILS_MESSENGER_CREATE_INFO_EXT,
+		p_next: ptr::null(),
+		flags: vk::DebugUtilsMessengerCreateFlagsEXT::empty(),
+		message_severity: VALIDATION_LOG_SEVERITY,
+		message_type: vk::DebugUtilsMessageTypeFlagsEXT::GENERAL | vk::DebugUtilsMessageTypeFlagsEXT::VALIDATION | vk::DebugUtilsMessageTypeFlagsEXT::PERFORMANCE,
+		pfn_user_callback: Some(debug_callback),
+		p_user_data: ptr::null_mut(),
+		..Default::default()
+	}
+}
+
+//Gets called by the validation layers with every message. Errors + warnings go to stderr, everything else to stdout
+//Errors also get kept for "take_validation_errors"
+unsafe extern "system" fn debug_callback(message_severity: vk::DebugUtilsMessageSeverityFlagsEXT, message_types: vk::DebugUtilsMessageTypeFlagsEXT, p_callback_data: *const vk::DebugUtilsMessengerCallbackDataEXT<'_>, _p_user_data: *mut c_void) -> vk::Bool32 {
+	let message = match p_callback_data.as_ref().filter(|callback_data| !callback_data.p_message.is_null()) {
+		Some(callback_data) => CStr::from_ptr(callback_data.p_message).to_string_lossy().into_owned(),
+		None => String::from("(no message)"),
+	};
+
+	match message_severity {
+		vk::DebugUtilsMessageSeverityFlagsEXT::ERROR => {
+			eprintln!("[Vulkan error] {:?}: {}", message_types, message);
+			if let Ok(mut validation_errors) = VALIDATION_ERRORS.lock() {
+				validation_errors.push(message);
+			}
+		},
+		vk::DebugUtilsMessageSeverityFlagsEXT::WARNING => eprintln!("[Vulkan warning] {:?}: {}", message_types, message),
+		vk::DebugUtilsMessageSeverityFlagsEXT::INFO => println!("[Vulkan info] {:?}: {}", message_types, message),
+		_ => println!("[Vulkan verbose] {:?}: {}", message_types, message),
+	}
+
+	//Returning true would make the call that caused it fail - that's only meant for testing the layers themselves
+	vk::FALSE
+}
+
+//Takes every validation error that's come in since last time
+pub fn take_validation_errors() -> Vec<String> {
+	VALIDATION_ERRORS.lock().map(|mut validation_errors| std::mem::take(&mut *validation_errors)).unwrap_or_default()
+}
+
+//The messenger that sends validation messages to "debug_callback"
+pub struct DebugMessenger {
+	debug_utils_instance: ext::debug_utils::Instance,
+	messenger: vk::DebugUtilsMessengerEXT,
+}
+
+impl DebugMessenger {
+	//The instance has to have been made with the debug utils extension
+	pub fn new(entry: &ash::Entry, instance: &ash::Instance) -> DebugMessenger {
+		let debug_utils_instance = ext::debug_utils::Instance::new(entry, instance);
+		let messenger = unsafe { debug_utils_instance.create_debug_utils_messenger(&messenger_create_info(), None).expect("Failed to create debug messenger") };
+
+		DebugMessenger {
+			debug_utils_instance,
+			messenger,
+		}
+	}
+
+	//Has to go before the instance does
+	pub fn destroy(&self) {
+		unsafe { self.debug_utils_instance.destroy_debug_utils_messenger(self.messenger, None) };
+	}
+}
+
+//Gives vulkan objects names, and marks regions of command buffers with labels
+pub struct ObjectNamer {
+	debug_utils_device: ext::debug_utils::Device,
+}
+
+impl ObjectNamer {
+	pub fn new(instance: &ash::Instance, device: &ash::Device) -> ObjectNamer {
+		ObjectNamer {
+			debug_utils_device: ext::debug_utils::Device::new(instance, device),
+		}
+	}
+
+	//Names any vulkan handle. Null handles get skipped. A name that doesn't work out isn't worth crashing over, so failures are ignored
+	pub fn name<H: vk::Handle>(&self, handle: H, name: &str) {
+		let object_handle = handle.as_raw();
+		if object_handle == 0 {
+			return
+		}
+		let Ok(name) = CString::new(name) else { return };
+
+		let name_info = vk::DebugUtilsObjectNameInfoEXT {
+			s_type: vk::StructureType::DEBUG_UTILS_OBJECT_NAME_INFO_EXT,
+			p_next: ptr::null(),
+			object_type: H::TYPE,
+			object_handle,
+			p_object_name: name.as_ptr(),
+			..Default::default()
+		};
+		let _ = unsafe { self.debug_utils_device.set_debug_utils_object_name(&name_info) };
+	}
+
+	//Starts a labeled region in a command buffer. Every begin needs an end, in the same command buffer
+	pub fn cmd_begin_label(&self, command_buffer: vk::CommandBuffer, label: &str) {
+		let Ok(label) = CString::new(label) else { return };
+		let label_info = vk::DebugUtilsLabelEXT {
+			s_type: vk::StructureType::DEBUG_UTILS_LABEL_EXT,
+			p_next: ptr::null(),
+			p_label_name: label.as_ptr(),
+			color: [0.0; 4], //All zero means no color
+			..Default::default()
+		};
+		unsafe { self.debug_utils_device.cmd_begin_debug_utils_label(command_buffer, &label_info) };
+	}
+
+	pub fn cmd_end_label(&self, command_buffer: vk::CommandBuffer) {
+		unsafe { self.debug_utils_device.cmd_end_debug_utils_label(command_buffer) };
+	}
+}
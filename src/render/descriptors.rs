@@ -0,0 +1,257 @@
+use crate::render::memory::{GpuAllocator, Allocation, create_buffer};
+
+use ash::vk;
+use std::ptr;
+
+//Descriptor sets are how shaders get at buffers/images that aren't vertex data (uniform buffers, textures, etc)
+//This module has:
+//	Descriptor set layouts made from a simple list of bindings
+//	A descriptor allocator that hands out sets from a bunch of pools, making new pools as they fill up, and can reset them all at once
+//	Uniform buffers - one per frame in flight, persistently mapped so they can be written every frame without mapping/unmapping
+
+
+//One binding in a descriptor set layout - matches a "layout(set = _, binding = _)" declaration in a shader
+#[derive(Debug, Clone, Copy)]
+pub struct DescriptorBinding {
+	pub binding: u32, //Binding number in the shader
+	pub descriptor_type: vk::DescriptorType, //Uniform buffer, combined image sampler, etc
+	pub count: u32, //Array size in the shader, 1 if it's not an array
+	pub stages: vk::ShaderStageFlags, //Shader stages that use it
+}
+
+impl DescriptorBinding {
+	//Single (non array) binding
+	pub const fn new(binding: u32, descriptor_type: vk::DescriptorType, stages: vk::ShaderStageFlags) -> DescriptorBinding {
+		DescriptorBinding {
+			binding,
+			descriptor_type,
+			count: 1,
+			stages,
+		}
+	}
+}
+
+//Makes a descriptor set layout out of a list of bindings
+pub fn create_descriptor_set_layout(device: &ash::Device, bindings: &[DescriptorBinding]) -> vk::DescriptorSetLayout {
+	let layout_bindings: Vec<vk::DescriptorSetLayoutBinding> = bindings
+		.iter()
+		.map(|binding| vk::DescriptorSetLayoutBinding {
+			binding: binding.binding,
+			descriptor_type: binding.descriptor_type,
+			descriptor_count: binding.count,
+			stage_flags: binding.stages,
+			p_immutable_samplers: ptr::null(), //Samplers get written into the set like everything else
+			..Default::default()
+		})
+		.collect();
+
+	let layout_info = vk::DescriptorSetLayoutCreateInfo {
+		s_type: vk::StructureType::DESCRIPTOR_SET_LAYOUT_CREATE_INFO,
+		p_next: ptr::null(),
+		flags: vk::DescriptorSetLayoutCreateFlags::empty(),
+		binding_count: layout_bindings.len() as u32,
+		p_bindings: layout_bindings.as_ptr(),
+		..Default::default()
+	};
+
+	unsafe { device.create_descriptor_set_layout(&layout_info, None).expect("Failed to create descriptor set layout") }
+}
+
+//Points a uniform buffer binding in a descriptor set at a buffer (the whole thing)
+pub fn write_uniform_buffer_descriptor(device: &ash::Device, descriptor_set: vk::DescriptorSet, binding: u32, buffer: vk::Buffer) {
+	let buffer_info = vk::DescriptorBufferInfo {
+		buffer,
+		offset: 0,
+		range: vk::WHOLE_SIZE,
+	};
+
+	let descriptor_writes = [vk::WriteDescriptorSet {
+		s_type: vk::StructureType::WRITE_DESCRIPTOR_SET,
+		p_next: ptr::null(),
+		dst_set: descriptor_set,
+		dst_binding: binding,
+		dst_array_element: 0,
+		descriptor_count: 1,
+		descriptor_type: vk::DescriptorType::UNIFORM_BUFFER,
+		p_image_info: ptr::null(),
+		p_buffer_info: &buffer_info,
+		p_texel_buffer_view: ptr::null(),
+		..Default::default()
+	}];
+
+	unsafe { device.update_descriptor_sets(&descriptor_writes, &[]) };
+}
+
+
+//Points a combined image sampler binding in a descriptor set at an image view + sampler. The image has to be in the shader read only layout
+pub fn write_combined_image_sampler_descriptor(device: &ash::Device, descriptor_set: vk::DescriptorSet, binding: u32, image_view: vk::ImageView, sampler: vk::Sampler) {
+	let image_info = vk::DescriptorImageInfo {
+		sampler,
+		image_view,
+		image_layout: vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+	};
+
+	let descriptor_writes = [vk::WriteDescriptorSet {
+		s_type: vk::StructureType::WRITE_DESCRIPTOR_SET,
+		p_next: ptr::null(),
+		dst_set: descriptor_set,
+		dst_binding: binding,
+		dst_array_element: 0,
+		descriptor_count: 1,
+		descriptor_type: vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
+		p_image_info: &image_info,
+		p_buffer_info: ptr::null(),
+		p_texel_buffer_view: ptr::null(),
+		..Default::default()
+	}];
+
+	unsafe { device.update_descriptor_sets(&descriptor_writes, &[]) };
+}
+
+
+//Hands out descriptor sets from descriptor pools
+//Pools have a fixed size, so when one runs out, it gets set aside and a new one gets made (or an old reset one gets reused)
+//Sets can't be freed one at a time - "reset" frees every set from every pool at once, which is way cheaper anyway
+pub struct DescriptorAllocator {
+	pool_sizes: Vec<vk::DescriptorPoolSize>, //Descriptors of each type that each pool has room for
+	max_sets: u32, //Sets each pool has room for
+
+	current_pool: Option<vk::DescriptorPool>, //Pool sets are getting allocated from right now
+	full_pools: Vec<vk::DescriptorPool>, //Pools that have run out of room (or at least had a failed allocation)
+	free_pools: Vec<vk::DescriptorPool>, //Pools that have been reset and are ready to be reused
+}
+
+impl DescriptorAllocator {
+	//Doesn't make any pools until the first allocation
+	//"pool_sizes" is how many descriptors of each type go in each pool
+	pub fn new(pool_sizes: &[(vk::DescriptorType, u32)], max_sets: u32) -> DescriptorAllocator {
+		DescriptorAllocator {
+			pool_sizes: pool_sizes.iter().map(|&(ty, descriptor_count)| vk::DescriptorPoolSize {ty, descriptor_count}).collect(),
+			max_sets,
+
+			current_pool: None,
+			full_pools: vec![],
+			free_pools: vec![],
+		}
+	}
+
+	//Allocates a descriptor set with the given layout
+	//If the current pool is out of room, moves on to another pool and tries again
+	pub fn allocate(&mut self, device: &ash::Device, layout: vk::DescriptorSetLayout) -> vk::DescriptorSet {
+		let layouts = [layout];
+
+		//Try the current pool, then one more time with a fresh pool. If that doesn't work the set is just too big for the pool sizes
+		for _ in 0..2 {
+			let pool = match self.current_pool {
+				Some(pool) => pool,
+				None => self.grab_pool(device),
+			};
+
+			let allocate_info = vk::DescriptorSetAllocateInfo {
+				s_type: vk::StructureType::DESCRIPTOR_SET_ALLOCATE_INFO,
+				p_next: ptr::null(),
+				descriptor_pool: pool,
+				descriptor_set_count: layouts.len() as u32,
+				p_set_layouts: layouts.as_ptr(),
+				..Default::default()
+			};
+
+			match unsafe { device.allocate_descriptor_sets(&allocate_info) } {
+				Ok(descriptor_sets) => return descriptor_sets[0],
+				//Pool is full - retire it and go around again with a different one
+				Err(vk::Result::ERROR_OUT_OF_POOL_MEMORY) | Err(vk::Result::ERROR_FRAGMENTED_POOL) => {
+					self.full_pools.push(pool);
+					self.current_pool = None;
+				},
+				Err(error) => panic!("Failed to allocate descriptor set: {:?}", error),
+			}
+		}
+		panic!("Failed to allocate descriptor set - too big for an empty pool");
+	}
+
+	//Frees every set allocated so far, so the pools can be reused. Nothing can be using any of the sets
+	pub fn reset(&mut self, device: &ash::Device) {
+		for pool in self.current_pool.take().into_iter().chain(self.full_pools.drain(..)) {
+			unsafe { device.reset_descriptor_pool(pool, vk::DescriptorPoolResetFlags::empty()).expect("Failed to reset descriptor pool") };
+			self.free_pools.push(pool);
+		}
+	}
+
+	//Destroys all the pools (which frees all the sets)
+	pub fn destroy(&self, device: &ash::Device) {
+		for &pool in self.current_pool.iter().chain(&self.full_pools).chain(&self.free_pools) {
+			unsafe { device.destroy_descriptor_pool(pool, None) };
+		}
+	}
+
+	//Makes a pool the current one - reuses a reset pool if there is one, otherwise makes a new one
+	fn grab_pool(&mut self, device: &ash::Device) -> vk::DescriptorPool {
+		let pool = self.free_pools.pop().unwrap_or_else(|| {
+			let pool_info = vk::DescriptorPoolCreateInfo {
+				s_type: vk::StructureType::DESCRIPTOR_POOL_CREATE_INFO,
+				p_next: ptr::null(),
+				flags: vk::DescriptorPoolCreateFlags::empty(), //No freeing individual sets, only resetting the whole pool
+				max_sets: self.max_sets,
+				pool_size_count: self.pool_sizes.len() as u32,
+				p_pool_sizes: self.pool_sizes.as_ptr(),
+				..Default::default()
+			};
+			unsafe { device.create_descriptor_pool(&pool_info, None).expect("Failed to create descriptor pool") }
+		});
+
+		self.current_pool = Some(pool);
+		pool
+	}
+}
+
+
+//Uniform buffers holding one T each, one for each frame in flight
+//They stay mapped the whole time (the allocator keeps host visible memory mapped), and the memory is host coherent, so writing is just a memcpy - no flushing, no mapping/unmapping
+//Only write the buffer for a frame slot once that slot's fence has been waited on, otherwise the GPU might still be reading it
+pub struct UniformBuffers<T> {
+	buffers: Vec<vk::Buffer>,
+	buffer_allocations: Vec<Allocation>,
+	mapped_pointers: Vec<*mut T>, //Where each buffer is mapped into CPU memory
+}
+
+impl<T: Copy> UniformBuffers<T> {
+	//Makes "count" uniform buffers
+	pub fn new(device: &ash::Device, allocator: &mut GpuAllocator, count: usize) -> UniformBuffers<T> {
+		let buffer_size = core::mem::size_of::<T>() as vk::DeviceSize;
+
+		let mut buffers = vec![];
+		let mut buffer_allocations = vec![];
+		let mut mapped_pointers = vec![];
+		for _ in 0..count {
+			let (buffer, buffer_allocation) = create_buffer(device, allocator, buffer_size, vk::BufferUsageFlags::UNIFORM_BUFFER, vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT);
+
+			buffers.push(buffer);
+			buffer_allocations.push(buffer_allocation);
+			mapped_pointers.push(buffer_allocation.mapped_pointer::<T>());
+		}
+
+		UniformBuffers {
+			buffers,
+			buffer_allocations,
+			mapped_pointers,
+		}
+	}
+
+	//Copies the data into the buffer for a frame slot
+	pub fn write(&self, frame: usize, data: &T) {
+		unsafe { ptr::copy_nonoverlapping(data, self.mapped_pointers[frame], 1) };
+	}
+
+	//Buffer for a frame slot, for writing into descriptor sets
+	pub fn buffer(&self, frame: usize) -> vk::Buffer {
+		self.buffers[frame]
+	}
+
+	//Destroys all the buffers and gives their memory back
+	pub fn destroy(&self, device: &ash::Device, allocator: &mut GpuAllocator) {
+		for (&buffer, buffer_allocation) in self.buffers.iter().zip(&self.buffer_allocations) {
+			unsafe { device.destroy_buffer(buffer, None) };
+			allocator.free(device, buffer_allocation);
+		}
+	}
+}
@@ -0,0 +1,185 @@
+//Golden image regression tests for the renderer
+//Renders fixed scenes from fixed camera poses with the headless renderer, then compares against reference pngs in "tests/golden"
+//On a mismatch, the actual render + a diff image get written to "target/golden" so you can see what broke
+//
+//A missing reference image fails the test. With GOLDEN_BLESS=1 set, the render gets saved as the new reference instead and the test passes
+//So for a new test or an intentional change to the output: run once with GOLDEN_BLESS=1, look at the new pngs, commit them
+//References should be generated on the same driver CI uses (lavapipe), since different GPUs rasterize edges a little differently
+//
+//The render tests need a vulkan device, so they're ignored by a plain "cargo test". Run them with "cargo test golden -- --ignored"
+
+use crate::render::pipeline::VulkanApp;
+use crate::scene::Scene;
+use crate::scene::camera::Camera;
+use crate::utility::image::{read_png, write_png};
+use crate::utility::read;
+
+use std::path::{Path, PathBuf};
+use glam::f32::{vec3, Vec3};
+
+//Size of the offscreen image for every golden render
+const GOLDEN_WIDTH: u32 = 320;
+const GOLDEN_HEIGHT: u32 = 180;
+
+//Max difference allowed in any one channel of a pixel before that pixel counts as mismatched
+const CHANNEL_TOLERANCE: u8 = 2;
+
+//Where reference images live (checked in) and where failed renders/diffs get dumped (not checked in)
+const REFERENCE_DIR: &str = "tests/golden";
+const OUTPUT_DIR: &str = "target/golden";
+
+//Result of comparing two images
+struct GoldenComparison {
+	mismatched_pixels: usize,
+	max_channel_difference: u8,
+	diff_image: Vec<u8>, //Mismatched pixels are red, matching pixels are a dimmed grey version of the reference
+}
+
+//Compares two rgba images of the same size pixel by pixel
+fn compare_images(reference: &[u8], actual: &[u8], tolerance: u8) -> GoldenComparison {
+	let mut mismatched_pixels = 0;
+	let mut max_channel_difference = 0;
+	let mut diff_image = Vec::with_capacity(reference.len());
+
+	for (reference_pixel, actual_pixel) in reference.chunks_exact(4).zip(actual.chunks_exact(4)) {
+		//Biggest difference in any one channel for this pixel
+		let pixel_difference = reference_pixel.iter()
+			.zip(actual_pixel)
+			.map(|(r, a)| r.abs_diff(*a))
+			.max()
+			.unwrap();
+		max_channel_difference = max_channel_difference.max(pixel_difference);
+
+		if pixel_difference > tolerance {
+			mismatched_pixels += 1;
+			diff_image.extend_from_slice(&[255, 0, 0, 255]);
+		} else {
+			let grey = ((reference_pixel[0] as u32 + reference_pixel[1] as u32 + reference_pixel[2] as u32) / 12) as u8;
+			diff_image.extend_from_slice(&[grey, grey, grey, 255]);
+		}
+	}
+
+	GoldenComparison {
+		mismatched_pixels,
+		max_channel_difference,
+		diff_image,
+	}
+}
+
+//Renders a scene headless from the given camera pose
+fn render_scene(mut scene: Scene, camera_pos: Vec3, camera_target: Vec3) -> Vec<u8> {
+	//Fixed camera pose, with the aspect ratio of the golden image
+	scene.camera = Camera::new(camera_pos, camera_target);
+	scene.camera.camera_resize(GOLDEN_WIDTH, GOLDEN_HEIGHT);
+	//HUD text changes from run to run (frame times), so keep it out of the images
+	scene.hud.visible = false;
+
+	let mut vulkan_app = VulkanApp::init_headless(GOLDEN_WIDTH, GOLDEN_HEIGHT, &scene);
+	vulkan_app.render_offscreen(&scene)
+}
+
+//Checks a render against its reference image, panics with a useful message if they don't match
+fn check_golden(name: &str, actual: &[u8]) {
+	let reference_path = Path::new(REFERENCE_DIR).join(format!("{name}.png"));
+	let bless = std::env::var("GOLDEN_BLESS").is_ok_and(|value| value == "1");
+
+	//Asked to replace it - this render becomes the reference
+	if bless {
+		write_png(&reference_path, GOLDEN_WIDTH, GOLDEN_HEIGHT, actual);
+		println!("Wrote golden reference {:?}", reference_path);
+		return
+	}
+	//No reference to compare against is a failure, otherwise a fresh checkout would pass everything without checking anything
+	if !reference_path.exists() {
+		let actual_path: PathBuf = Path::new(OUTPUT_DIR).join(format!("{name}.actual.png"));
+		write_png(&actual_path, GOLDEN_WIDTH, GOLDEN_HEIGHT, actual);
+		panic!("Golden reference {:?} is missing. Wrote the render to {:?} - if it looks right, run with GOLDEN_BLESS=1 and commit the reference", reference_path, actual_path);
+	}
+
+	let (reference_width, reference_height, reference) = read_png(&reference_path);
+	assert!(reference_width == GOLDEN_WIDTH && reference_height == GOLDEN_HEIGHT, "Golden reference {:?} is {}x{}, expected {}x{}", reference_path, reference_width, reference_height, GOLDEN_WIDTH, GOLDEN_HEIGHT);
+
+	let comparison = compare_images(&reference, actual, CHANNEL_TOLERANCE);
+
+	//Dump the actual render and the diff so the mismatch can be looked at
+	if comparison.mismatched_pixels > 0 {
+		let actual_path: PathBuf = Path::new(OUTPUT_DIR).join(format!("{name}.actual.png"));
+		let diff_path: PathBuf = Path::new(OUTPUT_DIR).join(format!("{name}.diff.png"));
+		write_png(&actual_path, GOLDEN_WIDTH, GOLDEN_HEIGHT, actual);
+		write_png(&diff_path, GOLDEN_WIDTH, GOLDEN_HEIGHT, &comparison.diff_image);
+
+		panic!(
+			"Golden image {:?} mismatch: {} pixels off by more than {} (max channel difference {}). Wrote {:?} and {:?}",
+			name, comparison.mismatched_pixels, CHANNEL_TOLERANCE, comparison.max_channel_difference, actual_path, diff_path
+		);
+	}
+}
+
+//Scene with just the test triangles in it
+fn test_triangles_scene() -> Scene {
+	let mut scene = Scene::empty();
+	scene.add_test_triangles();
+	scene
+}
+
+//Scene with just the sample level from samplescene.glb in it
+fn sample_level_scene() -> Scene {
+	let mut scene = Scene::empty();
+	scene.load_gltf(&read::sample_scene_asset());
+	scene
+}
+
+//Test triangles straight on, far enough back to see all of them
+#[test]
+#[ignore = "needs a vulkan device (lavapipe works), run with --ignored"]
+fn golden_test_triangles_front() {
+	let pixels = render_scene(test_triangles_scene(), vec3(-100.0, 40.0, 4.0), vec3(20.0, 40.0, 4.0));
+	check_golden("test_triangles_front", &pixels);
+}
+
+//Test triangles from off to the side, so they're at an angle
+#[test]
+#[ignore = "needs a vulkan device (lavapipe works), run with --ignored"]
+fn golden_test_triangles_angled() {
+	let pixels = render_scene(test_triangles_scene(), vec3(-40.0, 20.0, 60.0), vec3(20.0, 20.0, 4.0));
+	check_golden("test_triangles_angled", &pixels);
+}
+
+//Sample level on its own, looking down at it from a corner
+#[test]
+#[ignore = "needs a vulkan device (lavapipe works), run with --ignored"]
+fn golden_sample_scene() {
+	let pixels = render_scene(sample_level_scene(), vec3(12.0, 10.0, 12.0), vec3(-2.0, 0.0, -3.0));
+	check_golden("sample_scene", &pixels);
+}
+
+//The whole starting scene (level + triangles) from the default spawn pose, looking up at the big triangle
+#[test]
+#[ignore = "needs a vulkan device (lavapipe works), run with --ignored"]
+fn golden_full_scene_spawn() {
+	let pixels = render_scene(Scene::init_scene(), vec3(0.0, 0.0, 10.0), vec3(20.0, 100.0, 0.0));
+	check_golden("full_scene_spawn", &pixels);
+}
+
+//Identical images match, with a grey diff image
+#[test]
+fn compare_identical_images() {
+	let image = [10, 20, 30, 255, 200, 100, 50, 255];
+	let comparison = compare_images(&image, &image, CHANNEL_TOLERANCE);
+	assert_eq!(comparison.mismatched_pixels, 0);
+	assert_eq!(comparison.max_channel_difference, 0);
+	//Average of rgb, dimmed to a quarter
+	assert_eq!(comparison.diff_image, [5, 5, 5, 255, 29, 29, 29, 255]);
+}
+
+//A difference right at the tolerance still matches, one past it doesn't
+#[test]
+fn compare_images_tolerance_boundary() {
+	let reference = [100, 100, 100, 255, 100, 100, 100, 255, 100, 100, 100, 255];
+	let actual = [100, 100, 100 + CHANNEL_TOLERANCE, 255, 100 - CHANNEL_TOLERANCE - 1, 100, 100, 255, 100, 100, 100, 255 - CHANNEL_TOLERANCE - 1];
+	let comparison = compare_images(&reference, &actual, CHANNEL_TOLERANCE);
+	assert_eq!(comparison.mismatched_pixels, 2);
+	assert_eq!(comparison.max_channel_difference, CHANNEL_TOLERANCE + 1);
+	//First pixel is within tolerance, the other two get marked red
+	assert_eq!(comparison.diff_image, [25, 25, 25, 255, 255, 0, 0, 255, 255, 0, 0, 255]);
+}
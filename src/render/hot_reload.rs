@@ -0,0 +1,98 @@
+use crate::constants::SHADER_POLL_INTERVAL;
+
+use naga::{front::glsl, back::spv, valid::{Validator, ValidationFlags, Capabilities}, ShaderStage};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Instant, SystemTime};
+
+//Where the GLSL lives. Baked in at compile time, so it points at the source tree no matter where the engine gets run from
+pub const SHADER_SOURCE_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/src/render/shaders");
+
+//Keeps an eye on the shader sources, for reloading them while the engine is running
+//Just checks modified times every so often - no need for a file watching library for a handful of files
+pub struct ShaderWatcher {
+	shader_dir: PathBuf,
+	modified_times: HashMap<PathBuf, SystemTime>, //Last modified time seen for each .vert/.frag file
+	last_poll: Instant,
+}
+
+impl ShaderWatcher {
+	//Starts watching a folder. Whatever's in there right now counts as already loaded
+	pub fn new(shader_dir: &Path) -> ShaderWatcher {
+		let mut shader_watcher = ShaderWatcher {
+			shader_dir: shader_dir.to_path_buf(),
+			modified_times: HashMap::new(),
+			last_poll: Instant::now(),
+		};
+		shader_watcher.modified_times = shader_watcher.scan();
+		shader_watcher
+	}
+
+	//Returns every shader source that's been changed (or added) since the last poll
+	//Only actually looks at the disk once every "SHADER_POLL_INTERVAL", so it's fine to call every frame
+	pub fn poll(&mut self) -> Vec<PathBuf> {
+		if self.last_poll.elapsed() < SHADER_POLL_INTERVAL {
+			return vec![]
+		}
+		self.last_poll = Instant::now();
+
+		let modified_times = self.scan();
+		let mut changed: Vec<PathBuf> = modified_times
+			.iter()
+			.filter(|(path, modified_time)| self.modified_times.get(*path) != Some(modified_time))
+			.map(|(path, _)| path.clone())
+			.collect();
+		changed.sort(); //Same order every time, so the log is easier to follow
+		self.modified_times = modified_times;
+		changed
+	}
+
+	//Modified time of every shader source in the folder. Files that can't be read right now (mid-save, etc) get skipped, and get picked up on a later poll
+	fn scan(&self) -> HashMap<PathBuf, SystemTime> {
+		let Ok(entries) = fs::read_dir(&self.shader_dir) else { return HashMap::new() };
+		entries
+			.filter_map(|entry| entry.ok())
+			.map(|entry| entry.path())
+			.filter(|path| shader_stage(path).is_some())
+			.filter_map(|path| {
+				let modified_time = fs::metadata(&path).and_then(|metadata| metadata.modified()).ok()?;
+				Some((path, modified_time))
+			})
+			.collect()
+	}
+}
+
+//Which stage a shader source is for, from its extension
+pub fn shader_stage(path: &Path) -> Option<ShaderStage> {
+	match path.extension()?.to_str()? {
+		"vert" => Some(ShaderStage::Vertex),
+		"frag" => Some(ShaderStage::Fragment),
+		_ => None,
+	}
+}
+
+//Compiles a GLSL file to SPIR-V bytes, ready for "create_shader_module"
+//Returns the compiler's error message (with the offending line pointed out) if it doesn't compile
+pub fn compile_glsl(path: &Path) -> Result<Vec<u8>, String> {
+	let stage = shader_stage(path).ok_or(format!("{:?} isn't a .vert or .frag file", path))?;
+	let source = fs::read_to_string(path).map_err(|error| format!("Couldn't read {:?}: {}", path, error))?;
+
+	let module = glsl::Frontend::default().parse(&glsl::Options::from(stage), &source).map_err(|error| error.emit_to_string(&source))?;
+
+	//Binding validation is off because the fragment shader declares its texture + sampler at the same binding (one combined image sampler descriptor). Vulkan's fine with that, naga's validator isn't
+	let module_info = Validator::new(ValidationFlags::all() - ValidationFlags::BINDINGS, Capabilities::all())
+		.validate(&module)
+		.map_err(|error| error.emit_to_string(&source))?;
+
+	//SPIR-V 1.0 works with any vulkan version. Debug info just makes the files bigger
+	//Naga flips gl_Position's y by default (its IR uses wgsl's y up clip space), but the GLSL is already written for vulkan's y down clip space, so that has to go too
+	let options = spv::Options {
+		lang_version: (1, 0),
+		flags: spv::Options::default().flags - spv::WriterFlags::DEBUG - spv::WriterFlags::ADJUST_COORDINATE_SPACE,
+		..Default::default()
+	};
+	let words = spv::write_vec(&module, &module_info, &options, None).map_err(|error| error.to_string())?;
+
+	Ok(words.iter().flat_map(|word| word.to_le_bytes()).collect())
+}
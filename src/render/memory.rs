@@ -1,10 +1,364 @@
+use crate::constants::MEMORY_BLOCK_SIZE;
+
 use ash::vk;
+use std::fmt;
 use std::ptr;
 
-//Creates a buffer
-//As a note - vulkan doesn't like allocating lots of memory separately, it prefers to do it in a big chunk - there's a limit to the number of times memory can be allocated
-//Generally, a few big chunks of memory will be allocated and everything will be copied over together. This makes fragmentation an issue to think about
-pub fn create_buffer(instance: &ash::Instance, device: &ash::Device, physical_device: vk::PhysicalDevice, size: vk::DeviceSize, usage: vk::BufferUsageFlags, required_memory_properties:  vk::MemoryPropertyFlags) -> (vk::Buffer, vk::DeviceMemory) {
+//What kind of resource is sitting in a chunk of memory
+//Linear resources (buffers) and optimal tiled images can't share a "bufferImageGranularity" sized page of memory, so the allocator needs to know which is which
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ResourceKind {
+	Linear, //Buffers (and linear tiled images, if those ever get used)
+	Optimal, //Optimal tiled images - every image "create_image" makes
+}
+
+//A chunk of a memory block handed out by the allocator
+//Bind with "memory" + "offset", give it back with "GpuAllocator::free" once the buffer/image is destroyed
+#[derive(Debug, Clone, Copy)]
+pub struct Allocation {
+	pub memory: vk::DeviceMemory, //The block this lives in - shared with other allocations, so don't free/map it directly
+	pub offset: vk::DeviceSize, //Where the allocation starts in the block
+	pub size: vk::DeviceSize,
+	memory_type_index: u32,
+	mapped_pointer: *mut u8, //Where the allocation is mapped into CPU memory. Null if it's not host visible
+}
+
+impl Allocation {
+	//Where the allocation is mapped into CPU memory
+	//Host visible blocks get mapped once when they're made and stay mapped (memory can't be mapped twice), so this is always valid until it's freed
+	pub fn mapped_pointer<T>(&self) -> *mut T {
+		assert!(!self.mapped_pointer.is_null(), "Tried to map an allocation that isn't host visible");
+		self.mapped_pointer as *mut T
+	}
+}
+
+//Part of a block that's free
+#[derive(Debug, Clone, Copy)]
+struct FreeRange {
+	offset: vk::DeviceSize,
+	size: vk::DeviceSize,
+}
+
+//Part of a block that's handed out
+#[derive(Debug, Clone, Copy)]
+struct UsedRange {
+	offset: vk::DeviceSize,
+	size: vk::DeviceSize,
+	kind: ResourceKind,
+}
+
+//One big "vkAllocateMemory" allocation that gets split up
+//Free + used ranges cover the whole block with no gaps (alignment padding just stays on the free list)
+struct MemoryBlock {
+	memory: vk::DeviceMemory,
+	size: vk::DeviceSize,
+	mapped_pointer: *mut u8, //The whole block is mapped if it's host visible, null otherwise
+	free_ranges: Vec<FreeRange>, //Sorted by offset. Neighbors always get merged, so two free ranges never touch
+	used_ranges: Vec<UsedRange>, //Sorted by offset
+}
+
+impl MemoryBlock {
+	//Finds a spot for an allocation and takes it off the free list. None if it doesn't fit anywhere
+	//Best fit - uses the smallest free range that works, so the big ranges are left for big resources
+	fn allocate(&mut self, size: vk::DeviceSize, alignment: vk::DeviceSize, kind: ResourceKind, granularity: vk::DeviceSize) -> Option<vk::DeviceSize> {
+		let mut best_fit: Option<(usize, vk::DeviceSize)> = None; //Free range index, offset
+		for (i, free_range) in self.free_ranges.iter().enumerate() {
+			if let Some(offset) = self.fit_in_free_range(free_range, size, alignment, kind, granularity) {
+				if best_fit.is_none_or(|(best_i, _)| free_range.size < self.free_ranges[best_i].size) {
+					best_fit = Some((i, offset));
+				}
+			}
+		}
+		let (free_index, offset) = best_fit?;
+
+		//Cut the allocation out of the free range - whatever's left over on either side stays free
+		let free_range = self.free_ranges.remove(free_index);
+		let free_end = free_range.offset + free_range.size;
+		let end = offset + size;
+		if end < free_end {
+			self.free_ranges.insert(free_index, FreeRange {offset: end, size: free_end - end});
+		}
+		if offset > free_range.offset {
+			self.free_ranges.insert(free_index, FreeRange {offset: free_range.offset, size: offset - free_range.offset});
+		}
+
+		let used_index = self.used_ranges.partition_point(|used_range| used_range.offset < offset);
+		self.used_ranges.insert(used_index, UsedRange {offset, size, kind});
+
+		Some(offset)
+	}
+
+	//Where an allocation would start in a free range, if it fits at all
+	//Aligns the start, then checks the used ranges on either side for bufferImageGranularity conflicts
+	fn fit_in_free_range(&self, free_range: &FreeRange, size: vk::DeviceSize, alignment: vk::DeviceSize, kind: ResourceKind, granularity: vk::DeviceSize) -> Option<vk::DeviceSize> {
+		let free_end = free_range.offset + free_range.size;
+		let mut offset = align_up(free_range.offset, alignment);
+
+		//Since there's no gaps, the used range right after the free range is the first one past its start, and the one before it is right before that
+		let next_used_index = self.used_ranges.partition_point(|used_range| used_range.offset < free_range.offset);
+
+		//If whatever ends right before is the other kind of resource and shares a page with the start, bump the start up to the next page
+		if let Some(previous) = next_used_index.checked_sub(1).map(|i| self.used_ranges[i]) {
+			if previous.kind != kind && on_same_page(previous.offset + previous.size - 1, offset, granularity) {
+				offset = align_up(offset, granularity);
+			}
+		}
+
+		let end = offset.checked_add(size)?;
+		if end > free_end {
+			return None
+		}
+
+		//Same deal for whatever starts right after, except that can't be moved - just doesn't fit here
+		if let Some(next) = self.used_ranges.get(next_used_index) {
+			if next.kind != kind && on_same_page(end - 1, next.offset, granularity) {
+				return None
+			}
+		}
+
+		Some(offset)
+	}
+
+	//Puts an allocation back on the free list, merged with the free ranges on either side of it
+	fn free(&mut self, offset: vk::DeviceSize) {
+		let used_index = self.used_ranges.iter().position(|used_range| used_range.offset == offset).expect("Tried to free memory that isn't allocated");
+		let used_range = self.used_ranges.remove(used_index);
+
+		let free_index = self.free_ranges.partition_point(|free_range| free_range.offset < offset);
+		let mut freed_range = FreeRange {offset, size: used_range.size};
+
+		//Merge with the next free range
+		if let Some(next) = self.free_ranges.get(free_index) {
+			if freed_range.offset + freed_range.size == next.offset {
+				freed_range.size += next.size;
+				self.free_ranges.remove(free_index);
+			}
+		}
+		//Merge with the previous free range
+		if let Some(previous) = free_index.checked_sub(1).map(|i| &mut self.free_ranges[i]) {
+			if previous.offset + previous.size == freed_range.offset {
+				previous.size += freed_range.size;
+				return
+			}
+		}
+
+		self.free_ranges.insert(free_index, freed_range);
+	}
+
+	fn is_empty(&self) -> bool {
+		self.used_ranges.is_empty()
+	}
+
+	//Adds this block's numbers onto some stats
+	fn add_stats(&self, stats: &mut MemoryStats) {
+		stats.block_count += 1;
+		stats.allocation_count += self.used_ranges.len();
+		stats.bytes_reserved += self.size;
+		stats.bytes_used += self.used_ranges.iter().map(|used_range| used_range.size).sum::<vk::DeviceSize>();
+		stats.free_range_count += self.free_ranges.len();
+		stats.largest_free_range = self.free_ranges.iter().map(|free_range| free_range.size).fold(stats.largest_free_range, vk::DeviceSize::max);
+	}
+}
+
+
+//Usage/fragmentation numbers for the allocator (or just one memory type)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MemoryStats {
+	pub block_count: usize, //Number of actual "vkAllocateMemory" allocations
+	pub allocation_count: usize, //Number of buffers/images living in those blocks
+	pub bytes_reserved: vk::DeviceSize, //Total size of the blocks
+	pub bytes_used: vk::DeviceSize, //Total size handed out (alignment padding doesn't count)
+	pub free_range_count: usize,
+	pub largest_free_range: vk::DeviceSize, //Biggest single thing that'd fit without making a new block (ignoring alignment)
+}
+
+impl MemoryStats {
+	//How broken up the free memory is - 0 when it's all in one range, heading towards 1 as it gets split into lots of little ranges
+	pub fn fragmentation(&self) -> f32 {
+		let bytes_free = self.bytes_reserved - self.bytes_used;
+		match bytes_free {
+			0 => 0.0,
+			_ => 1.0 - self.largest_free_range as f32 / bytes_free as f32,
+		}
+	}
+}
+
+impl fmt::Display for MemoryStats {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		let mib = |bytes: vk::DeviceSize| bytes as f32 / (1024.0 * 1024.0);
+		write!(f, "{} blocks, {} allocations, {:.2}/{:.2} MiB used, {} free ranges (largest {:.2} MiB), {:.1}% fragmented",
+			self.block_count, self.allocation_count, mib(self.bytes_used), mib(self.bytes_reserved), self.free_range_count, mib(self.largest_free_range), self.fragmentation() * 100.0)
+	}
+}
+
+
+//Sub-allocating GPU memory allocator
+//Vulkan has a limit on the number of separate allocations (maxMemoryAllocationCount - can be as low as 4096), and each one is slow, so it prefers a few big chunks
+//So memory gets allocated in big blocks, one list of blocks for each memory type, and buffers/images get placed inside them at an offset
+//Freed ranges go back on their block's free list and get reused. Fragmentation is a thing to keep an eye on ("stats")
+pub struct GpuAllocator {
+	memory_properties: vk::PhysicalDeviceMemoryProperties, //Memory types + heaps of the physical device
+	buffer_image_granularity: vk::DeviceSize, //Page size that linear + optimal resources can't share
+	blocks: Vec<Vec<MemoryBlock>>, //Blocks for each memory type, indexed by memory type index
+}
+
+impl GpuAllocator {
+	pub fn new(instance: &ash::Instance, physical_device: vk::PhysicalDevice) -> GpuAllocator {
+		let memory_properties = unsafe { instance.get_physical_device_memory_properties(physical_device) };
+		let buffer_image_granularity = unsafe { instance.get_physical_device_properties(physical_device) }.limits.buffer_image_granularity;
+
+		GpuAllocator {
+			memory_properties,
+			buffer_image_granularity,
+			blocks: (0..memory_properties.memory_type_count).map(|_| vec![]).collect(),
+		}
+	}
+
+	//Finds memory for something with these requirements (from "get_buffer/image_memory_requirements")
+	//Tries every block of the right memory type, then makes a new block if nothing fits
+	pub fn allocate(&mut self, device: &ash::Device, requirements: vk::MemoryRequirements, required_memory_properties: vk::MemoryPropertyFlags, kind: ResourceKind) -> Allocation {
+		let memory_type_index = find_memory_type_index(&self.memory_properties, requirements.memory_type_bits, required_memory_properties);
+		let granularity = self.buffer_image_granularity;
+
+		//Existing blocks first
+		let blocks = &mut self.blocks[memory_type_index as usize];
+		let existing = blocks.iter_mut().find_map(|block| {
+			block.allocate(requirements.size, requirements.alignment, kind, granularity).map(|offset| (block.memory, block.mapped_pointer, offset))
+		});
+
+		let (memory, block_mapped_pointer, offset) = match existing {
+			Some(existing) => existing,
+			None => {
+				//Nothing fits - make a new block. Anything bigger than a normal block gets a block sized just for it
+				let block_size = self.block_size(memory_type_index).max(requirements.size);
+				let mut block = self.create_block(device, memory_type_index, block_size);
+				let offset = block.allocate(requirements.size, requirements.alignment, kind, granularity).expect("New memory block is too small for the allocation");
+				let new = (block.memory, block.mapped_pointer, offset);
+				self.blocks[memory_type_index as usize].push(block);
+				new
+			},
+		};
+
+		let mapped_pointer = match block_mapped_pointer.is_null() {
+			true => ptr::null_mut(),
+			false => unsafe { block_mapped_pointer.add(offset as usize) },
+		};
+
+		Allocation {
+			memory,
+			offset,
+			size: requirements.size,
+			memory_type_index,
+			mapped_pointer,
+		}
+	}
+
+	//Gives an allocation back. Destroy whatever was bound to it first (and make sure the GPU is done with it)
+	//Empty blocks get freed, except for one spare per memory type so things like staging buffers don't make + free a block every time
+	pub fn free(&mut self, device: &ash::Device, allocation: &Allocation) {
+		let blocks = &mut self.blocks[allocation.memory_type_index as usize];
+		let block_index = blocks.iter().position(|block| block.memory == allocation.memory).expect("Tried to free an allocation from a block that doesn't exist");
+		blocks[block_index].free(allocation.offset);
+
+		if blocks[block_index].is_empty() && blocks.iter().filter(|block| block.is_empty()).count() > 1 {
+			let block = blocks.remove(block_index);
+			GpuAllocator::destroy_block(device, &block);
+		}
+	}
+
+	//Stats for everything the allocator has
+	pub fn stats(&self) -> MemoryStats {
+		let mut stats = MemoryStats::default();
+		for block in self.blocks.iter().flatten() {
+			block.add_stats(&mut stats);
+		}
+		stats
+	}
+
+	//Stats for each memory type that has any blocks, with the memory type's index and property flags
+	pub fn memory_type_stats(&self) -> Vec<(u32, vk::MemoryPropertyFlags, MemoryStats)> {
+		self.blocks.iter()
+			.enumerate()
+			.filter(|(_, blocks)| !blocks.is_empty())
+			.map(|(memory_type_index, blocks)| {
+				let mut stats = MemoryStats::default();
+				for block in blocks {
+					block.add_stats(&mut stats);
+				}
+				(memory_type_index as u32, self.memory_properties.memory_types[memory_type_index].property_flags, stats)
+			})
+			.collect()
+	}
+
+	//Frees every block. Everything allocated from them has to be destroyed already - anything that isn't gets reported as a leak
+	pub fn destroy(&mut self, device: &ash::Device) {
+		let leaked = self.stats().allocation_count;
+		if leaked > 0 {
+			println!("GPU allocator destroyed with {:?} allocation(s) still alive", leaked);
+		}
+
+		for block in self.blocks.iter().flatten() {
+			GpuAllocator::destroy_block(device, block);
+		}
+		self.blocks.iter_mut().for_each(Vec::clear);
+	}
+
+	//Normal block size for a memory type - MEMORY_BLOCK_SIZE, unless the heap is small enough that that'd hog a big chunk of it
+	fn block_size(&self, memory_type_index: u32) -> vk::DeviceSize {
+		let heap_index = self.memory_properties.memory_types[memory_type_index as usize].heap_index;
+		let heap_size = self.memory_properties.memory_heaps[heap_index as usize].size;
+		MEMORY_BLOCK_SIZE.min(heap_size / 8)
+	}
+
+	//Allocates a new block, and maps the whole thing if it's host visible
+	fn create_block(&self, device: &ash::Device, memory_type_index: u32, size: vk::DeviceSize) -> MemoryBlock {
+		let memory_allocate_info = vk::MemoryAllocateInfo {
+			s_type: vk::StructureType::MEMORY_ALLOCATE_INFO,
+			p_next: ptr::null(),
+			allocation_size: size,
+			memory_type_index,
+			..Default::default()
+		};
+		let memory = unsafe { device.allocate_memory(&memory_allocate_info, None).expect("Failed to allocate device memory") };
+
+		let property_flags = self.memory_properties.memory_types[memory_type_index as usize].property_flags;
+		let mapped_pointer = match property_flags.contains(vk::MemoryPropertyFlags::HOST_VISIBLE) {
+			true => unsafe { device.map_memory(memory, 0, vk::WHOLE_SIZE, vk::MemoryMapFlags::empty()).expect("Failed to map device memory") as *mut u8 },
+			false => ptr::null_mut(),
+		};
+
+		MemoryBlock {
+			memory,
+			size,
+			mapped_pointer,
+			free_ranges: vec![FreeRange {offset: 0, size}],
+			used_ranges: vec![],
+		}
+	}
+
+	fn destroy_block(device: &ash::Device, block: &MemoryBlock) {
+		unsafe {
+			if !block.mapped_pointer.is_null() {
+				device.unmap_memory(block.memory);
+			}
+			device.free_memory(block.memory, None);
+		}
+	}
+}
+
+//Rounds up to a multiple of the alignment (vulkan alignments are always powers of 2)
+fn align_up(value: vk::DeviceSize, alignment: vk::DeviceSize) -> vk::DeviceSize {
+	(value + alignment - 1) & !(alignment - 1)
+}
+
+//Whether two byte offsets land in the same page, for bufferImageGranularity
+fn on_same_page(a: vk::DeviceSize, b: vk::DeviceSize, page_size: vk::DeviceSize) -> bool {
+	a & !(page_size - 1) == b & !(page_size - 1)
+}
+
+//Creates a buffer, with its memory sub-allocated from the allocator
+//Destroy the buffer, then give the allocation back with "GpuAllocator::free"
+pub fn create_buffer(device: &ash::Device, allocator: &mut GpuAllocator, size: vk::DeviceSize, usage: vk::BufferUsageFlags, required_memory_properties: vk::MemoryPropertyFlags) -> (vk::Buffer, Allocation) {
 	//Buffer creation info
 	let buffer_info = vk::BufferCreateInfo {
 		s_type: vk::StructureType::BUFFER_CREATE_INFO,
@@ -19,64 +373,78 @@ pub fn create_buffer(instance: &ash::Instance, device: &ash::Device, physical_de
 	};
 
 	//Create the buffer
-	let buffer = unsafe { device.create_buffer(&buffer_info, None).expect("Failed to create vertex buffer") };
+	let buffer = unsafe { device.create_buffer(&buffer_info, None).expect("Failed to create buffer") };
 
 	//Get that buffer's memory requirements - required size may differ from the size specified during buffer creation
 	let buffer_memory_requirements = unsafe { device.get_buffer_memory_requirements(buffer) };
 
-	//Need to find the right type of GPU memory to use query the GPU using "find_memory_type_index"
-	let memory_type_index = find_memory_type_index(instance, physical_device, buffer_memory_requirements.memory_type_bits, required_memory_properties);
+	//Get a chunk of a memory block with the right memory type from the allocator
+	let buffer_allocation = allocator.allocate(device, buffer_memory_requirements, required_memory_properties, ResourceKind::Linear);
+
+	//Associate the memory with the buffer by binding it, at wherever the allocation is in its block
+	unsafe { device.bind_buffer_memory(buffer, buffer_allocation.memory, buffer_allocation.offset).expect("Failed to bind buffer memory") };
 
-	//After getting the appropriate memory type index, can fill out memory allocation info
-	let memory_allocate_info = vk::MemoryAllocateInfo {
-		s_type: vk::StructureType::MEMORY_ALLOCATE_INFO,
+	(buffer, buffer_allocation)
+}
+
+//Creates a 2d image and binds memory to it - same deal as "create_buffer" but for images
+//Tiling is always optimal (GPU decides the texel layout), so the image can't be read directly by the CPU. Copy it to a buffer for that
+//"mip_levels" is 1 for anything that isn't a texture
+pub fn create_image(device: &ash::Device, allocator: &mut GpuAllocator, extent: vk::Extent2D, mip_levels: u32, format: vk::Format, usage: vk::ImageUsageFlags, required_memory_properties: vk::MemoryPropertyFlags) -> (vk::Image, Allocation) {
+	//Image creation info
+	let image_info = vk::ImageCreateInfo {
+		s_type: vk::StructureType::IMAGE_CREATE_INFO,
 		p_next: ptr::null(),
-		allocation_size: buffer_memory_requirements.size,
-		memory_type_index,
+		flags: vk::ImageCreateFlags::empty(),
+		image_type: vk::ImageType::TYPE_2D,
+		format,
+		extent: vk::Extent3D {width: extent.width, height: extent.height, depth: 1},
+		mip_levels, //Number of mip levels, the full size image counts as one
+		array_layers: 1,
+		samples: vk::SampleCountFlags::TYPE_1, //No msaa
+		tiling: vk::ImageTiling::OPTIMAL,
+		usage,
+		sharing_mode: vk::SharingMode::EXCLUSIVE, //Only used by the graphics queue
+		queue_family_index_count: 0,
+		p_queue_family_indices: ptr::null(),
+		initial_layout: vk::ImageLayout::UNDEFINED, //Contents don't matter, will get transitioned/cleared before use
 		..Default::default()
 	};
 
-	//Allocate the memory
-	let buffer_memory = unsafe { device.allocate_memory(&memory_allocate_info, None).expect("Failed to allocate device memory") };
+	//Create the image
+	let image = unsafe { device.create_image(&image_info, None).expect("Failed to create image") };
+
+	//Get the image's memory requirements and allocate (same as buffers, but it's an optimal tiled resource for the granularity rules)
+	let image_memory_requirements = unsafe { device.get_image_memory_requirements(image) };
+	let image_allocation = allocator.allocate(device, image_memory_requirements, required_memory_properties, ResourceKind::Optimal);
 
-	//Associate the allocated memory to the buffer by binding it - no offset, since the memory is specifically allocated for this buffer
-	unsafe { device.bind_buffer_memory(buffer, buffer_memory, 0).expect("Failed to bind buffer memory") };
+	//Bind the memory to the image
+	unsafe { device.bind_image_memory(image, image_allocation.memory, image_allocation.offset).expect("Failed to bind image memory") };
 
-	(buffer, buffer_memory)
+	(image, image_allocation)
 }
 
-//Can copy a buffer in host visible memory to a buffer in device local memory
-//This function won't check if the supplied queue has transfer capabilities, but that should be ensured first
-pub fn copy_buffer(device: &ash::Device, command_pool: vk::CommandPool, submit_queue: vk::Queue, src_buffer: vk::Buffer, dst_buffer: vk::Buffer, size: vk::DeviceSize) {
+//Allocates a short lived command buffer and starts recording into it
+//Pair with "end_single_time_commands" to submit it + wait for it to finish
+pub fn begin_single_time_commands(device: &ash::Device, command_pool: vk::CommandPool) -> vk::CommandBuffer {
 	//Create a short lived command buffer
 	let command_buffer_info = vk::CommandBufferAllocateInfo {
 		s_type: vk::StructureType::COMMAND_BUFFER_ALLOCATE_INFO,
 		p_next: ptr::null(),
 		command_pool, //Command pool from which command buffer is allocated
 		level: vk::CommandBufferLevel::PRIMARY, //Primary or secondary. Primary command buffers can execute secondary command buffers, kinda like executing a function
-		command_buffer_count: 1, //Number of command buffers to allocate. If doing multiple frames in flight, must have one for each framebuffer (one for each swapchain image)
+		command_buffer_count: 1, //Number of command buffers to allocate. Just the one, it gets thrown away after
 		..Default::default()
 	};
 
 	//Allocate the command buffer
 	let command_buffers = unsafe { device.allocate_command_buffers(&command_buffer_info).expect("Failed to allocate command buffers") };
 
-	//Need to set up a fence that will go off when the copy is done
-	let fence_info = vk::FenceCreateInfo {
-		s_type: vk::StructureType::FENCE_CREATE_INFO,
-		p_next: ptr::null(),
-		flags: vk::FenceCreateFlags::empty(),
-		..Default::default()
-	};
-
-	//Create the fence
-	let copy_fence = unsafe { device.create_fence(&fence_info, None).expect("Failed to create fence") };
-
 	//Start with the command buffer begin info
 	let command_buffer_begin_info = vk::CommandBufferBeginInfo {
 		s_type: vk::StructureType::COMMAND_BUFFER_BEGIN_INFO,
 		p_next: ptr::null(),
-		flags: vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT, //This command buffer will only be used once (for the copy)
+		flags: vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT, //This command buffer will only be used once
 		p_inheritance_info: ptr::null(),
 		..Default::default()
 	};
@@ -84,18 +452,25 @@ pub fn copy_buffer(device: &ash::Device, command_pool: vk::CommandPool, submit_q
 	//Begin recording to the command buffer
 	unsafe { device.begin_command_buffer(command_buffers[0], &command_buffer_begin_info).expect("Failed to begin recording to command buffer") };
 
-	//Define the regions to copy - want to copy the entire buffer
-	let copy_infos = [vk::BufferCopy {
-		src_offset: 0,
-		dst_offset: 0,
-		size
-	}];
-
-	//Copy the src buffer into the dst buffer
-	unsafe { device.cmd_copy_buffer(command_buffers[0], src_buffer, dst_buffer, &copy_infos) };
+	command_buffers[0]
+}
 
+//Ends recording a command buffer from "begin_single_time_commands", submits it, and blocks until it's done
+//Frees the command buffer after
+pub fn end_single_time_commands(device: &ash::Device, command_pool: vk::CommandPool, submit_queue: vk::Queue, command_buffer: vk::CommandBuffer) {
 	//End recording to the command buffer
-	unsafe { device.end_command_buffer(command_buffers[0]).expect("Failed to record to command buffer") };
+	unsafe { device.end_command_buffer(command_buffer).expect("Failed to record to command buffer") };
+
+	//Need to set up a fence that will go off when the commands are done
+	let fence_info = vk::FenceCreateInfo {
+		s_type: vk::StructureType::FENCE_CREATE_INFO,
+		p_next: ptr::null(),
+		flags: vk::FenceCreateFlags::empty(),
+		..Default::default()
+	};
+
+	//Create the fence
+	let fence = unsafe { device.create_fence(&fence_info, None).expect("Failed to create fence") };
 
 	//Execute the command buffer right away
 	let submit_infos = [vk::SubmitInfo {
@@ -105,32 +480,169 @@ pub fn copy_buffer(device: &ash::Device, command_pool: vk::CommandPool, submit_q
 		p_wait_semaphores: ptr::null(),
 		p_wait_dst_stage_mask: ptr::null(),
 		command_buffer_count: 1,
-		p_command_buffers: &command_buffers[0],
+		p_command_buffers: &command_buffer,
 		signal_semaphore_count: 0,
 		p_signal_semaphores: ptr::null(),
 		..Default::default()
 	}];
 
 	//Submits command buffer to queue, signals fence when complete
-	unsafe {device.queue_submit(submit_queue, &submit_infos, copy_fence).expect("Failed to submit command buffer to queue") };
+	unsafe {device.queue_submit(submit_queue, &submit_infos, fence).expect("Failed to submit command buffer to queue") };
 	//Wait for the fence right away
-	unsafe { device.wait_for_fences(&[copy_fence], true, std::u64::MAX).expect("Failed to wait for fence") };
+	unsafe { device.wait_for_fences(&[fence], true, u64::MAX).expect("Failed to wait for fence") };
 
 	//Clean up the command buffer and the fence
-	unsafe { device.free_command_buffers(command_pool, &command_buffers) };
-	unsafe { device.destroy_fence(copy_fence, None) };
+	unsafe { device.free_command_buffers(command_pool, &[command_buffer]) };
+	unsafe { device.destroy_fence(fence, None) };
+}
+
+//Can copy a buffer in host visible memory to a buffer in device local memory
+//This function won't check if the supplied queue has transfer capabilities, but that should be ensured first
+pub fn copy_buffer(device: &ash::Device, command_pool: vk::CommandPool, submit_queue: vk::Queue, src_buffer: vk::Buffer, dst_buffer: vk::Buffer, size: vk::DeviceSize) {
+	//Record into a short lived command buffer
+	let command_buffer = begin_single_time_commands(device, command_pool);
+
+	//Define the regions to copy - want to copy the entire buffer
+	let copy_infos = [vk::BufferCopy {
+		src_offset: 0,
+		dst_offset: 0,
+		size
+	}];
+
+	//Copy the src buffer into the dst buffer
+	unsafe { device.cmd_copy_buffer(command_buffer, src_buffer, dst_buffer, &copy_infos) };
+
+	//Submit and wait for the copy to finish
+	end_single_time_commands(device, command_pool, submit_queue, command_buffer);
+}
+
+//Creates a buffer in device local memory and fills it with data (staging path)
+//Copies the data into a host visible staging buffer first, then has the GPU copy that into the device local buffer (faster to read from than host visible memory)
+//"usage" is what the buffer gets used for (vertex buffer, index buffer, etc) - transfer dst gets added on. Blocks until the copy is done
+pub fn create_device_local_buffer<T: Copy>(device: &ash::Device, allocator: &mut GpuAllocator, command_pool: vk::CommandPool, submit_queue: vk::Queue, data: &[T], usage: vk::BufferUsageFlags) -> (vk::Buffer, Allocation) {
+	let buffer_size = core::mem::size_of_val(data) as vk::DeviceSize;
+
+	//If host coherent, "vkFlushMappedMemoryRanges" and "vkInvalidateMappedMemoryRanges" aren't needed during memory mapping, but it's slower
+	let (staging_buffer, staging_buffer_allocation) = create_buffer(device, allocator, buffer_size, vk::BufferUsageFlags::TRANSFER_SRC, vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT);
+
+	//Staging buffer is already mapped into CPU accessible memory, just copy the data in - rust equivalent of "memcpy"
+	unsafe { ptr::copy_nonoverlapping(data.as_ptr(), staging_buffer_allocation.mapped_pointer::<T>(), data.len()) };
+
+	//Now create the actual buffer, and copy the staging buffer into it
+	let (buffer, buffer_allocation) = create_buffer(device, allocator, buffer_size, vk::BufferUsageFlags::TRANSFER_DST | usage, vk::MemoryPropertyFlags::DEVICE_LOCAL);
+	copy_buffer(device, command_pool, submit_queue, staging_buffer, buffer, buffer_size);
+
+	//Can get rid of the staging buffer now
+	unsafe { device.destroy_buffer(staging_buffer, None) };
+	allocator.free(device, &staging_buffer_allocation);
+
+	(buffer, buffer_allocation)
+}
+
+//Reads a color image back to the CPU (readback path)
+//Copies the image into a host visible buffer, then copies that buffer into a vec. Assumes 4 bytes per pixel (rgba8/bgra8 formats)
+//The image has to be in "image_layout" when this is called, and gets put back in that layout after the copy
+//Blocks until the copy is done. Returns the pixels tightly packed, row by row from the top left
+pub fn read_image(device: &ash::Device, allocator: &mut GpuAllocator, command_pool: vk::CommandPool, submit_queue: vk::Queue, image: vk::Image, image_layout: vk::ImageLayout, extent: vk::Extent2D) -> Vec<u8> {
+	let (readback_buffer, readback_buffer_allocation) = create_readback_buffer(device, allocator, extent);
+
+	//Submit and wait - buffer is ready to read after this
+	let command_buffer = begin_single_time_commands(device, command_pool);
+	cmd_copy_image_to_readback_buffer(device, command_buffer, image, image_layout, extent, readback_buffer);
+	end_single_time_commands(device, command_pool, submit_queue, command_buffer);
+
+	let pixels = read_readback_buffer(&readback_buffer_allocation, extent);
+
+	//Don't need the buffer anymore
+	unsafe { device.destroy_buffer(readback_buffer, None) };
+	allocator.free(device, &readback_buffer_allocation);
+
+	pixels
+}
+
+//Host visible + coherent buffer big enough for a 4 bytes per pixel image, to copy into. Can be mapped without any flushing/invalidating
+pub fn create_readback_buffer(device: &ash::Device, allocator: &mut GpuAllocator, extent: vk::Extent2D) -> (vk::Buffer, Allocation) {
+	let buffer_size = (extent.width * extent.height * 4) as vk::DeviceSize;
+	create_buffer(device, allocator, buffer_size, vk::BufferUsageFlags::TRANSFER_DST, vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT)
+}
+
+//Records copying a color image into a readback buffer. Doesn't wait for anything - the buffer's ready once the command buffer has finished
+//The image has to be in "image_layout" when the commands run, and gets put back in that layout after the copy
+pub fn cmd_copy_image_to_readback_buffer(device: &ash::Device, command_buffer: vk::CommandBuffer, image: vk::Image, image_layout: vk::ImageLayout, extent: vk::Extent2D, readback_buffer: vk::Buffer) {
+	//Only care about the color of the first mip/layer
+	let subresource_range = vk::ImageSubresourceRange {
+		aspect_mask: vk::ImageAspectFlags::COLOR,
+		base_mip_level: 0,
+		level_count: 1,
+		base_array_layer: 0,
+		layer_count: 1,
+	};
+
+	//Transition to transfer src - also makes sure any writes to the image (rendering) are done before the copy reads it
+	let to_transfer_barrier = vk::ImageMemoryBarrier {
+		s_type: vk::StructureType::IMAGE_MEMORY_BARRIER,
+		p_next: ptr::null(),
+		src_access_mask: vk::AccessFlags::MEMORY_WRITE,
+		dst_access_mask: vk::AccessFlags::TRANSFER_READ,
+		old_layout: image_layout,
+		new_layout: vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+		src_queue_family_index: vk::QUEUE_FAMILY_IGNORED, //Not transferring queue family ownership
+		dst_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
+		image,
+		subresource_range,
+		..Default::default()
+	};
+	unsafe { device.cmd_pipeline_barrier(command_buffer, vk::PipelineStageFlags::ALL_COMMANDS, vk::PipelineStageFlags::TRANSFER, vk::DependencyFlags::empty(), &[], &[], &[to_transfer_barrier]) };
+
+	//Copy the whole image into the buffer. Row length/image height of 0 means tightly packed
+	let copy_regions = [vk::BufferImageCopy {
+		buffer_offset: 0,
+		buffer_row_length: 0,
+		buffer_image_height: 0,
+		image_subresource: vk::ImageSubresourceLayers {
+			aspect_mask: vk::ImageAspectFlags::COLOR,
+			mip_level: 0,
+			base_array_layer: 0,
+			layer_count: 1,
+		},
+		image_offset: vk::Offset3D {x: 0, y: 0, z: 0},
+		image_extent: vk::Extent3D {width: extent.width, height: extent.height, depth: 1},
+	}];
+	unsafe { device.cmd_copy_image_to_buffer(command_buffer, image, vk::ImageLayout::TRANSFER_SRC_OPTIMAL, readback_buffer, &copy_regions) };
+
+	//Put the image back the way it was
+	let from_transfer_barrier = vk::ImageMemoryBarrier {
+		s_type: vk::StructureType::IMAGE_MEMORY_BARRIER,
+		p_next: ptr::null(),
+		src_access_mask: vk::AccessFlags::TRANSFER_READ,
+		dst_access_mask: vk::AccessFlags::MEMORY_READ,
+		old_layout: vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+		new_layout: image_layout,
+		src_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
+		dst_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
+		image,
+		subresource_range,
+		..Default::default()
+	};
+	unsafe { device.cmd_pipeline_barrier(command_buffer, vk::PipelineStageFlags::TRANSFER, vk::PipelineStageFlags::BOTTOM_OF_PIPE, vk::DependencyFlags::empty(), &[], &[], &[from_transfer_barrier]) };
+}
+
+//Copies a readback buffer's pixels out into a vec. The copy into it has to have finished already
+pub fn read_readback_buffer(readback_buffer_allocation: &Allocation, extent: vk::Extent2D) -> Vec<u8> {
+	//Buffer's already mapped, copy everything out
+	let buffer_size = (extent.width * extent.height * 4) as usize;
+	let mut pixels = vec![0u8; buffer_size];
+	unsafe { ptr::copy_nonoverlapping(readback_buffer_allocation.mapped_pointer::<u8>(), pixels.as_mut_ptr(), buffer_size) };
+	pixels
 }
 
 
 //Finds the right type of GPU memory for whatever we want to do, return the index for that memory type
 //Chooses based on memory types. Doesn't worry about the specific memory heap at the moment
-fn find_memory_type_index(instance: &ash::Instance, physical_device: vk::PhysicalDevice, type_filter: u32, required_memory_properties: vk::MemoryPropertyFlags) -> u32 {
-	//Query for available types of memory
-	//This will give a "VkPhysicalDeviceMemoryProperties" with the available memory types and heaps
-	let memory_properties = unsafe { instance.get_physical_device_memory_properties(physical_device) };
-
+//"memory_properties" has the available memory types and heaps - the allocator queries it once up front
+fn find_memory_type_index(memory_properties: &vk::PhysicalDeviceMemoryProperties, type_filter: u32, required_memory_properties: vk::MemoryPropertyFlags) -> u32 {
 	//Loop through the memory types, check against the type filter, also check that it's suitable
-	for (memory_index, memory_type) in memory_properties.memory_types.iter().enumerate() {
+	for (memory_index, memory_type) in memory_properties.memory_types_as_slice().iter().enumerate() {
 		let memory_type_bits = 1 << memory_index; //Memory type bits contains a bit set for every supported memory type for the resource, corresponding to the memory index i
 		
 		//Check to make sure the memory type bits are the desired ones from the type filter, and also it has at least the required properties
@@ -140,4 +652,131 @@ fn find_memory_type_index(instance: &ash::Instance, physical_device: vk::Physica
 		}
 	}
 	panic!("Failed to find suitable memory type");
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	//Pretend bufferImageGranularity for the tests - big enough to see pages in small blocks
+	const GRANULARITY: vk::DeviceSize = 1024;
+
+	//Empty block that isn't backed by any real memory. Fine for everything that doesn't touch the GPU
+	fn test_block(size: vk::DeviceSize) -> MemoryBlock {
+		MemoryBlock {
+			memory: vk::DeviceMemory::null(),
+			size,
+			mapped_pointer: ptr::null_mut(),
+			free_ranges: vec![FreeRange {offset: 0, size}],
+			used_ranges: vec![],
+		}
+	}
+
+	//Free ranges as (offset, size), to compare against
+	fn free_ranges(block: &MemoryBlock) -> Vec<(vk::DeviceSize, vk::DeviceSize)> {
+		block.free_ranges.iter().map(|free_range| (free_range.offset, free_range.size)).collect()
+	}
+
+	//Aligned allocations skip ahead, and the padding they skip stays free
+	#[test]
+	fn alignment_padding() {
+		let mut block = test_block(4096);
+		assert_eq!(block.allocate(100, 1, ResourceKind::Linear, GRANULARITY), Some(0));
+		assert_eq!(block.allocate(64, 256, ResourceKind::Linear, GRANULARITY), Some(256));
+		assert_eq!(free_ranges(&block), [(100, 156), (320, 3776)]);
+
+		//Small enough to go in the padding, so it does (best fit)
+		assert_eq!(block.allocate(32, 4, ResourceKind::Linear, GRANULARITY), Some(100));
+	}
+
+	//Linear and optimal resources never share a page, in either direction
+	#[test]
+	fn linear_next_to_optimal() {
+		let mut block = test_block(4096);
+
+		//Same kind can pack right up against each other, the other kind gets bumped to the next page
+		assert_eq!(block.allocate(100, 16, ResourceKind::Linear, GRANULARITY), Some(0));
+		assert_eq!(block.allocate(100, 16, ResourceKind::Linear, GRANULARITY), Some(112));
+		assert_eq!(block.allocate(100, 16, ResourceKind::Optimal, GRANULARITY), Some(1024));
+
+		//Optimal at 0 and 512, then free the first one - leaves a free range that ends on the same page as the optimal at 512
+		let mut block = test_block(4096);
+		assert_eq!(block.allocate(300, 1, ResourceKind::Optimal, GRANULARITY), Some(0));
+		assert_eq!(block.allocate(256, 256, ResourceKind::Optimal, GRANULARITY), Some(512));
+		block.free(0);
+		assert_eq!(free_ranges(&block), [(0, 512), (768, 3328)]);
+
+		//A linear resource can't go before it (would end on its page) or right after it (would start on its page), so it goes on the next page
+		assert_eq!(block.allocate(100, 1, ResourceKind::Linear, GRANULARITY), Some(1024));
+		//Another optimal one can go right next to it though - in the gap after it, since that's the best fit
+		assert_eq!(block.allocate(100, 1, ResourceKind::Optimal, GRANULARITY), Some(768));
+		assert_eq!(block.allocate(400, 1, ResourceKind::Optimal, GRANULARITY), Some(0));
+	}
+
+	//Freeing merges with free neighbors on both sides, until the block is one free range again
+	#[test]
+	fn free_merges_neighbors() {
+		let mut block = test_block(4096);
+		for expected_offset in [0, 100, 200] {
+			assert_eq!(block.allocate(100, 1, ResourceKind::Linear, GRANULARITY), Some(expected_offset));
+		}
+
+		block.free(0);
+		assert_eq!(free_ranges(&block), [(0, 100), (300, 3796)]);
+		//Merges with the range after it
+		block.free(200);
+		assert_eq!(free_ranges(&block), [(0, 100), (200, 3896)]);
+		//Merges with both
+		block.free(100);
+		assert_eq!(free_ranges(&block), [(0, 4096)]);
+		assert!(block.is_empty());
+	}
+
+	//Nothing fits in a full block, or in a block that's too small
+	#[test]
+	fn full_block() {
+		let mut block = test_block(1024);
+		assert_eq!(block.allocate(1024, 1, ResourceKind::Linear, GRANULARITY), Some(0));
+		assert_eq!(block.allocate(1, 1, ResourceKind::Linear, GRANULARITY), None);
+		assert!(block.free_ranges.is_empty());
+
+		let mut block = test_block(1024);
+		assert_eq!(block.allocate(2048, 1, ResourceKind::Linear, GRANULARITY), None);
+		//Fits without the alignment, doesn't with it
+		assert_eq!(block.allocate(1000, 1, ResourceKind::Linear, GRANULARITY), Some(0));
+		assert_eq!(block.allocate(16, 32, ResourceKind::Linear, GRANULARITY), None);
+	}
+
+	//Freeing every other allocation leaves lots of little free ranges, and best fit fills the small ones first
+	#[test]
+	fn fragmentation_stats() {
+		let mut block = test_block(4096);
+		for i in 0..8 {
+			assert_eq!(block.allocate(512, 1, ResourceKind::Linear, GRANULARITY), Some(i * 512));
+		}
+		for i in [0, 2, 4, 6] {
+			block.free(i * 512);
+		}
+
+		let mut stats = MemoryStats::default();
+		block.add_stats(&mut stats);
+		assert_eq!(stats.block_count, 1);
+		assert_eq!(stats.allocation_count, 4);
+		assert_eq!(stats.bytes_reserved, 4096);
+		assert_eq!(stats.bytes_used, 2048);
+		assert_eq!(stats.free_range_count, 4);
+		assert_eq!(stats.largest_free_range, 512);
+		assert_eq!(stats.fragmentation(), 0.75);
+
+		//Freeing the last one makes one bigger range at the end, but a 512 byte allocation still goes in the first exact fit
+		block.free(7 * 512);
+		assert_eq!(free_ranges(&block), [(0, 512), (1024, 512), (2048, 512), (3072, 1024)]);
+		assert_eq!(block.allocate(512, 1, ResourceKind::Linear, GRANULARITY), Some(0));
+
+		let mut stats = MemoryStats::default();
+		block.add_stats(&mut stats);
+		assert_eq!(stats.free_range_count, 3);
+		assert_eq!(stats.largest_free_range, 1024);
+		assert_eq!(stats.fragmentation(), 0.5);
+	}
+}
@@ -0,0 +1,76 @@
+use crate::render::Vertex;
+use crate::render::memory::{GpuAllocator, Allocation, create_buffer};
+use crate::render::upload::Uploader;
+use crate::scene::bounds::Aabb;
+
+use ash::vk;
+
+//Geometry on the CPU side - what gets loaded from files/made in code, before it's uploaded
+//Vertices are in model space, indices are a triangle list into the vertices
+#[derive(Clone)]
+pub struct MeshData {
+	pub vertices: Vec<Vertex>,
+	pub indices: Vec<u32>,
+}
+
+impl MeshData {
+	//Model space box around all the vertices, for culling
+	pub fn bounds(&self) -> Aabb {
+		Aabb::from_points(self.vertices.iter().map(|vertex| vertex.pos.truncate()))
+	}
+}
+
+//Mesh that lives on the GPU - owns its own vertex and index buffers in device local memory
+//Made from a "MeshData" with "Mesh::upload", drawn with "cmd_draw_indexed" once its upload batch has been collected
+pub struct Mesh {
+	pub vertex_buffer: vk::Buffer,
+	pub vertex_buffer_allocation: Allocation,
+	pub index_buffer: vk::Buffer,
+	pub index_buffer_allocation: Allocation,
+	pub index_count: u32, //Number of indices to draw
+	pub upload_batch: u64, //Uploader batch the vertices/indices are in. Can't draw it until the uploader has collected this batch
+}
+
+impl Mesh {
+	//Makes the device local buffers and queues up the mesh data to be copied into them on the transfer queue. Doesn't wait for the copies
+	pub fn upload(device: &ash::Device, allocator: &mut GpuAllocator, uploader: &mut Uploader, mesh_data: &MeshData) -> Mesh {
+		let vertex_buffer_size = core::mem::size_of_val(mesh_data.vertices.as_slice()) as vk::DeviceSize;
+		let index_buffer_size = core::mem::size_of_val(mesh_data.indices.as_slice()) as vk::DeviceSize;
+		let (vertex_buffer, vertex_buffer_allocation) = create_buffer(device, allocator, vertex_buffer_size, vk::BufferUsageFlags::TRANSFER_DST | vk::BufferUsageFlags::VERTEX_BUFFER, vk::MemoryPropertyFlags::DEVICE_LOCAL);
+		let (index_buffer, index_buffer_allocation) = create_buffer(device, allocator, index_buffer_size, vk::BufferUsageFlags::TRANSFER_DST | vk::BufferUsageFlags::INDEX_BUFFER, vk::MemoryPropertyFlags::DEVICE_LOCAL);
+
+		//These can end up in different batches - staging submits the open batch if the staging ring fills up in between
+		//The indices go second, so their batch is the later one. Batches get collected in order, so once it's collected the vertices are there too
+		uploader.upload_buffer(device, allocator, &mesh_data.vertices, vertex_buffer, vk::AccessFlags::VERTEX_ATTRIBUTE_READ, vk::PipelineStageFlags::VERTEX_INPUT);
+		let upload_batch = uploader.upload_buffer(device, allocator, &mesh_data.indices, index_buffer, vk::AccessFlags::INDEX_READ, vk::PipelineStageFlags::VERTEX_INPUT);
+
+		Mesh {
+			vertex_buffer,
+			vertex_buffer_allocation,
+			index_buffer,
+			index_buffer_allocation,
+			index_count: mesh_data.indices.len() as u32,
+			upload_batch,
+		}
+	}
+
+	//Binds the vertex/index buffers and draws "instance_count" copies of the whole mesh
+	//Pipeline, descriptor sets, and the instance buffer (binding 1) need to be set up already
+	pub fn cmd_draw(&self, device: &ash::Device, command_buffer: vk::CommandBuffer, instance_count: u32) {
+		unsafe {
+			device.cmd_bind_vertex_buffers(command_buffer, 0, &[self.vertex_buffer], &[0]);
+			device.cmd_bind_index_buffer(command_buffer, self.index_buffer, 0, vk::IndexType::UINT32); //Indices are always u32, gltf u8/u16 indices get widened when loaded
+			device.cmd_draw_indexed(command_buffer, self.index_count, instance_count, 0, 0, 0); //Index count, instance count, first index, vertex offset, first instance
+		}
+	}
+
+	//Destroys the buffers and frees their memory. Make sure the GPU is done with them first
+	pub fn destroy(&self, device: &ash::Device, allocator: &mut GpuAllocator) {
+		unsafe {
+			device.destroy_buffer(self.vertex_buffer, None);
+			device.destroy_buffer(self.index_buffer, None);
+		}
+		allocator.free(device, &self.vertex_buffer_allocation);
+		allocator.free(device, &self.index_buffer_allocation);
+	}
+}
@@ -0,0 +1,37 @@
+use crate::constants::OFFSCREEN_FORMAT;
+use crate::render::memory::{GpuAllocator, Allocation, create_image};
+
+use ash::vk;
+
+//Color image that gets rendered to instead of a swapchain image when running headless (no window/surface)
+//Rendering leaves it in the transfer src layout so it can be copied straight out with "memory::read_image"
+pub struct OffscreenTarget {
+	pub image: vk::Image,
+	pub image_allocation: Allocation,
+	pub format: vk::Format,
+	pub extent: vk::Extent2D,
+}
+
+impl OffscreenTarget {
+	//Creates the offscreen image in device local memory
+	//Needs to be usable as a color attachment (rendered to) and a transfer src (copied out of)
+	pub fn new(device: &ash::Device, allocator: &mut GpuAllocator, width: u32, height: u32) -> OffscreenTarget {
+		let extent = vk::Extent2D {width, height};
+		let usage = vk::ImageUsageFlags::COLOR_ATTACHMENT | vk::ImageUsageFlags::TRANSFER_SRC;
+
+		let (image, image_allocation) = create_image(device, allocator, extent, 1, OFFSCREEN_FORMAT, usage, vk::MemoryPropertyFlags::DEVICE_LOCAL);
+
+		OffscreenTarget {
+			image,
+			image_allocation,
+			format: OFFSCREEN_FORMAT,
+			extent,
+		}
+	}
+
+	//Destroys the image and frees its memory. Image view/framebuffer are owned by VulkanApp and get destroyed there
+	pub fn destroy(&self, device: &ash::Device, allocator: &mut GpuAllocator) {
+		unsafe { device.destroy_image(self.image, None) };
+		allocator.free(device, &self.image_allocation);
+	}
+}
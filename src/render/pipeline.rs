@@ -1,15 +1,29 @@
 use crate::constants::*;
-use crate::render::memory::{create_buffer, copy_buffer};
-use crate::render::Vertex;
-use crate::scene::{Scene, TEST_TRIANGLE_VERTICES};
-use crate::utility::read::{fragment_shader, vertex_shader};
+use crate::render::memory::{GpuAllocator, Allocation, MemoryStats, create_image, read_image, create_readback_buffer, cmd_copy_image_to_readback_buffer, read_readback_buffer};
+use crate::render::mesh::Mesh;
+use crate::render::offscreen::OffscreenTarget;
+use crate::render::{Vertex, DebugVertex, OverlayVertex, FrameUniforms, RenderMode, FRAME_SET_BINDINGS, TEXTURE_SET_BINDINGS, PIPELINE_SET_BINDINGS};
+use crate::render::descriptors::{DescriptorAllocator, UniformBuffers, create_descriptor_set_layout, write_uniform_buffer_descriptor, write_combined_image_sampler_descriptor};
+use crate::render::texture::Texture;
+use crate::render::text::{GLYPH_SIZE, font_atlas, text_vertices};
+use crate::render::streaming::StreamingBuffer;
+use crate::render::upload::Uploader;
+use crate::render::debug_utils::{DebugMessenger, ObjectNamer, debug_utils_available, messenger_create_info, take_validation_errors};
+use crate::render::profiler::{GpuProfiler, GpuScope};
+use crate::render::hot_reload::{ShaderWatcher, SHADER_SOURCE_DIR, compile_glsl};
+use crate::render::pipeline_cache::{load_pipeline_cache, save_pipeline_cache};
+use crate::render::reflection::{ShaderReflection, check_pipeline_interface, push_constant_ranges};
+use crate::scene::{Scene, CullStats, WHITE_TEXTURE};
+use crate::utility::read::{fragment_shader, debug_fragment_shader, debug_line_vertex_shader, debug_line_fragment_shader, overlay_vertex_shader, overlay_fragment_shader, vertex_shader};
 
 use std::ptr;
+use std::path::Path;
 use std::ffi::{CString, CStr};
 use ash::{vk, khr, Entry};
+use glam::f32::{vec2, Vec4};
 use winit::{
 	window::{Window},
-	raw_window_handle::{HasWindowHandle, RawWindowHandle},
+	raw_window_handle::{HasWindowHandle, HasDisplayHandle, RawWindowHandle, RawDisplayHandle},
 };
 
 
@@ -17,8 +31,10 @@ use winit::{
 //vkEnumerateInstanceExtensionProperties checks which extensions are available in the vulkan implementation (on my computer)
 //Can add more extensions here
 //This isn't a const because it may be different on different devices
+//The platform surface extension is picked at runtime from the display handle, so the same code works on Windows/X11/Wayland
+//If there's no display handle (headless), no surface extensions are needed at all
 //Returns the extension names to pass to instance creation
-fn required_extension_names(entry: &ash::Entry) -> Vec<*const i8> {
+fn required_extension_names(entry: &ash::Entry, display_handle: Option<RawDisplayHandle>) -> Vec<*const i8> {
 	//Check for available instance extensions and convert to a vec of &CStr
 	let available_extensions: Vec<vk::ExtensionProperties> = unsafe {entry.enumerate_instance_extension_properties(None).expect("Failed to enumerate instance extensions")};
 	let available_extension_names :Vec<&CStr> = available_extensions
@@ -26,20 +42,43 @@ fn required_extension_names(entry: &ash::Entry) -> Vec<*const i8> {
 		.map(|extension_properties| extension_properties.extension_name_as_c_str().unwrap())
 		.collect();
 
+	//Tack the surface extension for whatever platform we're on onto the constant ones
+	let mut extensions_to_enable = vec![];
+	if let Some(display_handle) = display_handle {
+		extensions_to_enable.extend_from_slice(&INSTANCE_EXTENSIONS);
+		extensions_to_enable.push(platform_surface_extension_name(display_handle));
+	}
+	//Debug utils for validation messages + object names, if validation's on. Not having it isn't a problem, there just won't be any messages
+	if debug_utils_available(entry) {
+		extensions_to_enable.push(ash::ext::debug_utils::NAME);
+	}
+
 	//Check the listed extensions against the instance, panic if one isn't available. Then returns the vec.
 	//Exclamation point is just a "not"
-	for extension_to_check in &INSTANCE_EXTENSIONS {
+	for extension_to_check in &extensions_to_enable {
 		if !available_extension_names.contains(extension_to_check) {
-			panic!("Instance extension not available")
+			panic!("Instance extension {:?} not available", extension_to_check)
 		}
 	}
 
-	INSTANCE_EXTENSIONS
+	extensions_to_enable
 		.iter()
 		.map(|extension_properties_cstr| extension_properties_cstr.as_ptr())
 		.collect()
 }
 
+//Gets the name of the surface extension that goes with the windowing system winit is running on
+//Winit on linux will give either an xlib or a wayland handle (xcb is here in case the window comes from somewhere else)
+fn platform_surface_extension_name(display_handle: RawDisplayHandle) -> &'static CStr {
+	match display_handle {
+		RawDisplayHandle::Windows(_) => vk::KHR_WIN32_SURFACE_NAME,
+		RawDisplayHandle::Xlib(_) => vk::KHR_XLIB_SURFACE_NAME,
+		RawDisplayHandle::Xcb(_) => vk::KHR_XCB_SURFACE_NAME,
+		RawDisplayHandle::Wayland(_) => vk::KHR_WAYLAND_SURFACE_NAME,
+		_ => panic!("Unsupported windowing system: {:?}", display_handle)
+	}
+}
+
 //Validation Layers
 //Can add more validation layers here
 struct ValidationInfo<'a> { //I'm doing mine in a func, not as a const, so lifetime is <'a> instead of static - I think this just means that rust will deallocate the &str when the struct is deallocated
@@ -73,10 +112,12 @@ fn required_layer_names<'a>(entry: &ash::Entry) -> ValidationInfo<'a> { //I thin
 struct QueueFamilyIndices {
 	graphics_family: Option<u32>,
 	present_family: Option<u32>,
+	transfer_family: Option<u32>, //For async uploads. A transfer only family if there is one, otherwise the graphics family
 }
 
 impl QueueFamilyIndices {
-	fn find_queue_families(instance: &ash::Instance, physical_device: vk::PhysicalDevice, surface_req: &SurfaceReq) -> QueueFamilyIndices{
+	//If there's no surface (headless), nothing gets presented - the present family is just set to the graphics family so everything downstream still works
+	fn find_queue_families(instance: &ash::Instance, physical_device: vk::PhysicalDevice, surface_req: Option<&SurfaceReq>) -> QueueFamilyIndices{
 		//Get the queues of the physical device (same way it's done in "physical_device_suitability_score")
 		//There's some default structs happening here - vulkan overwrites these structs. I think ash is doing something kinda quirky here to deal with rust/vulkan??
 		let queue_family_count_len = unsafe { instance.get_physical_device_queue_family_properties2_len(physical_device) }; //Finds the length (# of available queue families)
@@ -87,6 +128,7 @@ impl QueueFamilyIndices {
 		let mut queue_family_index = QueueFamilyIndices {
 			graphics_family: None,
 			present_family: None,
+			transfer_family: None,
 		};
 
 
@@ -100,7 +142,10 @@ impl QueueFamilyIndices {
 			}
 
 			//Get a bool for if the queue family supports presentation
-			let presentation_support_bool = unsafe { surface_req.surface_loader.get_physical_device_surface_support(physical_device, i as u32, surface_req.surface).unwrap() };
+			let presentation_support_bool = match surface_req {
+				Some(surface_req) => unsafe { surface_req.surface_loader.get_physical_device_surface_support(physical_device, i as u32, surface_req.surface).unwrap() },
+				None => false,
+			};
 
 			//Do the same for the present queue. Prefer it to be the same as the graphics queue if possible
 			//If the current queue family is the first one that supports presentation, set it
@@ -110,6 +155,22 @@ impl QueueFamilyIndices {
 			}
 		}
 
+		//Headless - "present" on the graphics family
+		if surface_req.is_none() {
+			queue_family_index.present_family = queue_family_index.graphics_family;
+		}
+
+		//Look for a family that can transfer but can't do graphics - usually separate DMA hardware, so uploads can run alongside rendering
+		//Prefer one that can't do compute either (that's the real dedicated copy engine). Graphics/compute families can always transfer, even without the flag, so they're the fallback
+		let transfer_only = |family: &&vk::QueueFamilyProperties2, exclude: vk::QueueFlags| {
+			let flags = family.queue_family_properties.queue_flags;
+			flags.contains(vk::QueueFlags::TRANSFER) && !flags.intersects(exclude)
+		};
+		queue_family_index.transfer_family = queue_families.iter().position(|family| transfer_only(&family, vk::QueueFlags::GRAPHICS | vk::QueueFlags::COMPUTE))
+			.or_else(|| queue_families.iter().position(|family| transfer_only(&family, vk::QueueFlags::GRAPHICS)))
+			.map(|i| i as u32)
+			.or(queue_family_index.graphics_family);
+
 		//Return the updated indices
 		queue_family_index
 	}
@@ -128,7 +189,17 @@ struct SwapchainReq {
 	swapchain: vk::SwapchainKHR,
 	swapchain_format: vk::SurfaceFormatKHR,
 	swapchain_extent: vk::Extent2D,
-	swapchain_images: Vec<vk::Image>
+	swapchain_images: Vec<vk::Image>,
+	readback_supported: bool, //Whether the swapchain images can be copied out of (TRANSFER_SRC) - not every surface supports it
+}
+
+//A copy of a swapchain image on its way back to the CPU (screenshots, frame captures)
+//The copy gets recorded into the frame's command buffer before the image is presented, since after presenting it belongs to the presentation engine until it's acquired again
+struct SwapchainReadback {
+	buffer: vk::Buffer, //Host visible buffer the image gets copied into
+	allocation: Allocation,
+	extent: vk::Extent2D,
+	fence: vk::Fence, //Fence of the frame slot the copy was recorded in - the buffer's ready once it's signaled
 }
 
 //Need to check: surface capabilities, surface formats, and presentation modes
@@ -156,6 +227,157 @@ impl SwapchainSupportDetails {
 	}
 }
 
+//Spirv for every shader the pipelines use
+//Kept around so hot reloading one shader can rebuild the pipelines with the rest as is
+#[derive(Clone)]
+struct ShaderCode {
+	vertex: Vec<u8>,
+	fragment: Vec<u8>,
+	debug_fragment: Vec<u8>,
+	debug_line_vertex: Vec<u8>,
+	debug_line_fragment: Vec<u8>,
+	overlay_vertex: Vec<u8>,
+	overlay_fragment: Vec<u8>,
+}
+
+impl ShaderCode {
+	//The spirv baked into the exe
+	fn embedded() -> ShaderCode {
+		ShaderCode {
+			vertex: vertex_shader(),
+			fragment: fragment_shader(),
+			debug_fragment: debug_fragment_shader(),
+			debug_line_vertex: debug_line_vertex_shader(),
+			debug_line_fragment: debug_line_fragment_shader(),
+			overlay_vertex: overlay_vertex_shader(),
+			overlay_fragment: overlay_fragment_shader(),
+		}
+	}
+
+	//Swaps in freshly compiled spirv for a shader source file. Returns false if it's not a file any pipeline uses
+	fn replace(&mut self, file_name: &str, shader_code: Vec<u8>) -> bool {
+		let slot = match file_name {
+			"vertex.vert" => &mut self.vertex,
+			"fragment.frag" => &mut self.fragment,
+			"debug.frag" => &mut self.debug_fragment,
+			"debug_line.vert" => &mut self.debug_line_vertex,
+			"debug_line.frag" => &mut self.debug_line_fragment,
+			"overlay.vert" => &mut self.overlay_vertex,
+			"overlay.frag" => &mut self.overlay_fragment,
+			_ => return false,
+		};
+		*slot = shader_code;
+		true
+	}
+
+	//Checks each pipeline's shaders against the vertex attributes + descriptor set layouts it gets made with
+	fn check(&self) -> Result<(), String> {
+		VulkanApp::check_shaders(&self.vertex, &self.fragment, &Vertex::get_attribute_descriptions())
+			.and(VulkanApp::check_shaders(&self.vertex, &self.debug_fragment, &Vertex::get_attribute_descriptions()))
+			.and(VulkanApp::check_shaders(&self.debug_line_vertex, &self.debug_line_fragment, &DebugVertex::get_attribute_descriptions()))
+			.and(VulkanApp::check_shaders(&self.overlay_vertex, &self.overlay_fragment, &OverlayVertex::get_attribute_descriptions()))
+	}
+}
+
+//Everything that changes from one pipeline "create_pipeline" makes to the next - the rest of the pipeline state is the same for all of them
+struct PipelineDescription<'a> {
+	vertex_shader_code: &'a [u8],
+	fragment_shader_code: &'a [u8],
+	binding_descriptions: &'a [vk::VertexInputBindingDescription],
+	attribute_descriptions: &'a [vk::VertexInputAttributeDescription],
+	topology: vk::PrimitiveTopology, //Triangle list for meshes, line list for debug lines
+	polygon_mode: vk::PolygonMode, //Fill for normal triangles, line for wireframe (line needs the "fillModeNonSolid" feature)
+	cull_mode: vk::CullModeFlags,
+	depth_test: bool, //Without it, everything gets drawn on top of what's already there (and doesn't write depth)
+	alpha_blend: bool, //Blends by the fragment's alpha instead of just replacing what's there
+}
+
+//Every pipeline the renderer draws with, and the layouts they were made with
+//There's a pipeline variant for each render mode - fill + wireframe use the normal shaders, the debug views all share one pipeline with "debug.frag" (which view it shows gets pushed as a push constant)
+//Then the debug line pipeline, which draws on top of all of them, and the overlay pipeline (HUD text) on top of that
+struct RenderPipelines {
+	fill: (vk::Pipeline, vk::PipelineLayout),
+	wireframe: Option<(vk::Pipeline, vk::PipelineLayout)>, //None if the device can't draw lines as polygons ("fillModeNonSolid")
+	debug: (vk::Pipeline, vk::PipelineLayout),
+	debug_lines: (vk::Pipeline, vk::PipelineLayout),
+	overlay: (vk::Pipeline, vk::PipelineLayout),
+}
+
+impl RenderPipelines {
+	fn new(device: &ash::Device, pipeline_cache: vk::PipelineCache, render_pass: vk::RenderPass, extent: vk::Extent2D, set_layouts: &[vk::DescriptorSetLayout], shader_code: &ShaderCode, wireframe_supported: bool) -> RenderPipelines {
+		let (mesh_bindings, mesh_attributes) = (Vertex::get_binding_descriptions(), Vertex::get_attribute_descriptions());
+		let (line_bindings, line_attributes) = (DebugVertex::get_binding_descriptions(), DebugVertex::get_attribute_descriptions());
+		let (overlay_bindings, overlay_attributes) = (OverlayVertex::get_binding_descriptions(), OverlayVertex::get_attribute_descriptions());
+		let mesh_description = |fragment_shader_code, polygon_mode| PipelineDescription {
+			vertex_shader_code: &shader_code.vertex,
+			fragment_shader_code,
+			binding_descriptions: &mesh_bindings,
+			attribute_descriptions: &mesh_attributes,
+			topology: vk::PrimitiveTopology::TRIANGLE_LIST,
+			polygon_mode,
+			cull_mode: vk::CullModeFlags::BACK,
+			depth_test: true,
+			alpha_blend: false,
+		};
+		let debug_lines_description = PipelineDescription {
+			vertex_shader_code: &shader_code.debug_line_vertex,
+			fragment_shader_code: &shader_code.debug_line_fragment,
+			binding_descriptions: &line_bindings,
+			attribute_descriptions: &line_attributes,
+			topology: vk::PrimitiveTopology::LINE_LIST,
+			polygon_mode: vk::PolygonMode::FILL, //Lines are lines already, no need for the line polygon mode
+			cull_mode: vk::CullModeFlags::NONE,
+			depth_test: false, //On top of everything
+			alpha_blend: false,
+		};
+		let overlay_description = PipelineDescription {
+			vertex_shader_code: &shader_code.overlay_vertex,
+			fragment_shader_code: &shader_code.overlay_fragment,
+			binding_descriptions: &overlay_bindings,
+			attribute_descriptions: &overlay_attributes,
+			topology: vk::PrimitiveTopology::TRIANGLE_LIST,
+			polygon_mode: vk::PolygonMode::FILL,
+			cull_mode: vk::CullModeFlags::NONE,
+			depth_test: false,
+			alpha_blend: true, //Glyphs are see through around the edges
+		};
+
+		RenderPipelines {
+			fill: VulkanApp::create_pipeline(device, pipeline_cache, render_pass, extent, set_layouts, &mesh_description(&shader_code.fragment, vk::PolygonMode::FILL)),
+			wireframe: wireframe_supported.then(|| VulkanApp::create_pipeline(device, pipeline_cache, render_pass, extent, set_layouts, &mesh_description(&shader_code.fragment, vk::PolygonMode::LINE))),
+			debug: VulkanApp::create_pipeline(device, pipeline_cache, render_pass, extent, set_layouts, &mesh_description(&shader_code.debug_fragment, vk::PolygonMode::FILL)),
+			debug_lines: VulkanApp::create_pipeline(device, pipeline_cache, render_pass, extent, set_layouts, &debug_lines_description),
+			overlay: VulkanApp::create_pipeline(device, pipeline_cache, render_pass, extent, set_layouts, &overlay_description),
+		}
+	}
+
+	//Pipeline + layout to draw with in a render mode. Wireframe falls back to fill if it isn't supported
+	fn for_mode(&self, render_mode: RenderMode) -> (vk::Pipeline, vk::PipelineLayout) {
+		match render_mode {
+			RenderMode::Fill => self.fill,
+			RenderMode::Wireframe => self.wireframe.unwrap_or(self.fill),
+			RenderMode::VertexColor | RenderMode::Normals | RenderMode::Depth => self.debug,
+		}
+	}
+
+	//Every pipeline + layout there is, with a name for each
+	fn all(&self) -> Vec<(&'static str, (vk::Pipeline, vk::PipelineLayout))> {
+		[("fill", Some(self.fill)), ("wireframe", self.wireframe), ("debug view", Some(self.debug)), ("debug lines", Some(self.debug_lines)), ("overlay", Some(self.overlay))]
+			.into_iter()
+			.filter_map(|(name, pipeline)| Some((name, pipeline?)))
+			.collect()
+	}
+
+	fn destroy(&self, device: &ash::Device) {
+		for (_, (pipeline, pipeline_layout)) in self.all() {
+			unsafe {
+				device.destroy_pipeline(pipeline, None);
+				device.destroy_pipeline_layout(pipeline_layout, None);
+			}
+		}
+	}
+}
+
 
 
 
@@ -168,49 +390,108 @@ impl SwapchainSupportDetails {
 pub struct VulkanApp {
 	entry: ash::Entry, //I think the entry just lets you use all the functions without needing an instance
 	instance: ash::Instance, //The instance of vulkan - does uhhhhh everything
+	debug_messenger: Option<DebugMessenger>, //Sends validation messages to the log. None without validation/debug utils
+	object_namer: Option<ObjectNamer>, //Names vulkan objects + labels command buffer regions, for validation messages and captures. None without validation/debug utils
 	
-	surface: vk::SurfaceKHR, //Vulkan surface that gets rendered to
-	surface_loader: khr::surface::Instance,
+	surface_req: Option<SurfaceReq>, //Vulkan surface that gets rendered to + its loader. None when headless
 	
 	physical_device: vk::PhysicalDevice, //Physical device - the GPU
+	max_sampler_anisotropy: f32, //Highest anisotropy samplers can use, 0 if the device can't do anisotropic filtering
 	device: ash::Device, //Logical device - one instance of vulkan run on the physical device
+	allocator: GpuAllocator, //Hands out GPU memory for every buffer/image, sub-allocated from big blocks
+	uploader: Uploader, //Copies meshes/textures onto the GPU on the transfer queue without stalling rendering
 
 	graphics_queue: vk::Queue, //Queue - where graphics operations are run
 	present_queue: vk::Queue, //Queue that has presentation support (likely the same as the graphics queue, but not necessarily)
 
-	swapchain:vk::SwapchainKHR, //Swapchain - handles screen display + vsync/buffering
-	swapchain_loader: khr::swapchain::Device,
-	swapchain_image_views: Vec<vk::ImageView>, //Image views that describe image access for all the images on the swapchain
-	swapchain_framebuffers: Vec<vk::Framebuffer>, //Framebuffers define the attachments to be written to (image views)
-	swapchain_extent: vk::Extent2D, //The size of the swapchain images
+	swapchain_req: Option<SwapchainReq>, //Swapchain (handles screen display + vsync/buffering), its loader, and its images. None when headless
+	offscreen_target: Option<OffscreenTarget>, //Color image that gets rendered to instead of the swapchain images. Only exists when headless
+	swapchain_readback_requested: bool, //Set by "request_swapchain_readback" - the next frame drawn gets copied back
+	swapchain_readback: Option<SwapchainReadback>, //Copy of the last drawn frame, waiting for "take_swapchain_readback"
 
-	render_pass: vk::RenderPass, //Describes framebuffer attachments and subpasses for the pipeline
-	pipeline: vk::Pipeline, //A graphics pipeline with all the shaders + fixed functions in there
-	pipeline_layout: vk::PipelineLayout, //Deals with descriptor sets and push constants for pipeline to access
+	render_image_views: Vec<vk::ImageView>, //Image views that describe image access for all the images being rendered to (swapchain images, or the one offscreen image)
+	render_framebuffers: Vec<vk::Framebuffer>, //Framebuffers define the attachments to be written to (image views)
+	render_extent: vk::Extent2D, //The size of the images being rendered to
 
-	vertex_buffer: vk::Buffer, //Buffer used to hold all the juicy vertex data
-	vertex_buffer_memory: vk::DeviceMemory, //The memory the vertex buffer is allocated to
+	depth_format: vk::Format, //Format of the depth image, picked from what the physical device supports
+	depth_image: vk::Image, //Depth buffer - one shared by all the framebuffers, same size as the images being rendered to
+	depth_image_allocation: Allocation,
+	depth_image_view: vk::ImageView,
+
+	render_pass: vk::RenderPass, //Describes framebuffer attachments and subpasses for the pipeline
+	render_pipelines: RenderPipelines, //A graphics pipeline (+ layout) for each render mode, with all the shaders + fixed functions in there
+	render_mode: RenderMode, //Which of those gets drawn with
+	pipeline_cache: vk::PipelineCache, //Driver's compiled pipelines, saved to disk on shutdown so the next startup is faster
+	shader_code: ShaderCode, //Spirv the pipelines were last built with
+	shader_watcher: Option<ShaderWatcher>, //Only there with hot reloading on
+
+	descriptor_allocator: DescriptorAllocator, //Hands out descriptor sets
+	frame_set_layout: vk::DescriptorSetLayout, //Layout of the per frame descriptor set (set 0)
+	texture_set_layout: vk::DescriptorSetLayout, //Layout of the per texture descriptor set (set 1)
+	frame_uniform_buffers: UniformBuffers<FrameUniforms>, //Per frame uniforms, one buffer for each frame in flight
+	frame_descriptor_sets: Vec<vk::DescriptorSet>, //Per frame descriptor sets pointing at those buffers, one for each frame in flight
+
+	meshes: Vec<Mesh>, //Every mesh uploaded to the GPU, same indices as the scene's mesh list
+	textures: Vec<Texture>, //Every texture uploaded to the GPU, same indices as the scene's texture list
+	streaming_geometry: StreamingBuffer, //Vertices + indices of the scene's dynamic objects, rewritten every frame. One region for each frame in flight
+	streaming_instances: StreamingBuffer, //Instance data (model matrix + tint) for every draw, rewritten every frame
+	streaming_debug_lines: StreamingBuffer, //The scene's debug lines, rewritten every frame
+	streaming_overlay: StreamingBuffer, //HUD text quads, rewritten every frame
+	font_texture: Texture, //Atlas of the embedded bitmap font, for the overlay. Not one of the scene's textures
+	font_descriptor_set: vk::DescriptorSet, //Descriptor set (set 1) pointing at the font atlas
+	cull_stats: CullStats, //How many objects the last recorded frame drew/culled
+	gpu_profiler: Option<GpuProfiler>, //Times each part of the frame on the GPU. None if the graphics queue can't do timestamps
+	texture_descriptor_sets: Vec<vk::DescriptorSet>, //Descriptor set (set 1) for each texture, same indices as the textures
 
 	command_pool: vk::CommandPool, //Deals with memory stuff for the command buffers
 	command_pool_short: vk::CommandPool, //Command buffers created from this pool will be short lived
-	command_buffers: Vec<vk::CommandBuffer>, //Records commands which are then submitted to a queue
+	command_buffers: Vec<vk::CommandBuffer>, //Records commands which are then submitted to a queue. One for each frame in flight
 
 	//Synchronization objects
 	//Semaphore - used for GPU-GPU synchronization
 	//Fence - used for CPU-GPU synchronization
-	image_available_semaphore: vk::Semaphore, //Signals that an image has been acquired from the swapchain and is ready for rendering
-	render_finished_semaphore: vk::Semaphore, //Signals that rendering is finished, presentation can happen
-	in_flight_fence: vk::Fence, //Signals that a frame is in flight
+	//There are MAX_FRAMES_IN_FLIGHT frame slots. Each slot gets its own command buffer, image available semaphore and fence, and the slots get cycled through frame by frame
+	image_available_semaphores: Vec<vk::Semaphore>, //Signals that an image has been acquired from the swapchain and is ready for rendering. One per frame slot
+	in_flight_fences: Vec<vk::Fence>, //Signals that a frame slot's commands are done executing. One per frame slot
+	render_finished_semaphores: Vec<vk::Semaphore>, //Signals that rendering is finished, presentation can happen. One per image being rendered to (see "draw_frame" for why)
+	images_in_flight: Vec<vk::Fence>, //Fence of the frame slot that last rendered into each image (null if none yet) - a frame has to wait on it before touching that image
+	current_frame: usize, //Index of the frame slot to use for the next frame
 
 }
 
 //OpenGLcels seething over Vulkanchads
 impl VulkanApp {
-	//Initializes VulkanApp with an instance
-	pub fn init_vulkan(window: &Window) -> VulkanApp {
+	//Initializes VulkanApp with an instance, rendering to the window's swapchain
+	//The scene's geometry gets uploaded to the GPU here
+	pub fn init_vulkan(window: &Window, scene: &Scene) -> VulkanApp {
 		//Make an entry. Seems like this is just ash's thing to call functions before an instance is created.
 		let entry = Entry::linked();
 
+		//The display handle decides which surface extension gets enabled
+		let display_handle = window.display_handle().expect("Couldn't get display handle").as_raw();
+		//Create the instance
+		let instance = VulkanApp::create_instance(&entry, Some(display_handle));
+		//Setup the surface
+		let surface_req = VulkanApp::create_surface(&entry, &instance, window);
+
+		//Do everything else
+		VulkanApp::init_render_target(entry, instance, Some(surface_req), WINDOW_WIDTH, WINDOW_HEIGHT, scene)
+	}
+
+	//Initializes VulkanApp without a window - renders into an offscreen color image of the given size instead of swapchain images
+	//Use "render_offscreen" to draw a frame and read it back. Good for running on a software driver (lavapipe) in CI
+	pub fn init_headless(width: u32, height: u32, scene: &Scene) -> VulkanApp {
+		let entry = Entry::linked();
+
+		//No display handle, so no surface extensions
+		let instance = VulkanApp::create_instance(&entry, None);
+
+		//Do everything else with no surface
+		VulkanApp::init_render_target(entry, instance, None, width, height, scene)
+	}
+
+	//Creates the vulkan instance, with the surface extensions for the given display (or none if headless)
+	fn create_instance(entry: &ash::Entry, display_handle: Option<RawDisplayHandle>) -> ash::Instance {
 		//Need to do the strings as cstrings. Yay!
 		let app_name = CString::new(WINDOW_TITLE).unwrap(); //Just use window title for the app name
 		let engine_name = CString::new("jarmungular_ engine").unwrap();
@@ -229,10 +510,10 @@ impl VulkanApp {
 
 		
 		//Get extensions from khr (defined up at the top, might move it into a new file later)
-		let extension_names = required_extension_names(&entry);
+		let extension_names = required_extension_names(entry, display_handle);
 
 		//Set up validation layers. If is_enabled is false, these will get zorped later. I tried to put this whole thing in an if statement and it was a pain so whatev
-		let validation_layer_raw_names: Vec<CString> = required_layer_names(&entry)
+		let validation_layer_raw_names: Vec<CString> = required_layer_names(entry)
 			.validation_layers
 			.iter()
 			.map(|layer_name| CString::new(*layer_name).unwrap())
@@ -243,10 +524,13 @@ impl VulkanApp {
 			.collect();
 
 
+		//Messenger info chained onto the instance info, so validation messages from creating/destroying the instance get caught too (the real messenger doesn't exist yet/anymore then)
+		let instance_messenger_info = messenger_create_info();
+
 		//Instance creation info to feed the create_instance function
 		let create_info = vk::InstanceCreateInfo {
 			s_type: vk::StructureType::INSTANCE_CREATE_INFO,
-			p_next: ptr::null(), //This is to extend the struct, used for extensions/debug callbacks I think
+			p_next: if debug_utils_available(entry) {&instance_messenger_info as *const _ as *const std::ffi::c_void} else {ptr::null()}, //This is to extend the struct, used for extensions/debug callbacks
 			flags: vk::InstanceCreateFlags::empty(), //Don't need to deal with any flags (which is good because idk what they'd be used for)
 			p_application_info: &app_info,
 			pp_enabled_layer_names: if VALIDATION_ENABLED{enable_layer_names.as_ptr()} else {ptr::null()}, //Global validation layers. Only use if enabled, otherwise ignore
@@ -258,73 +542,165 @@ impl VulkanApp {
 		};
 
 		//Create the instance
-		let instance = unsafe { entry.create_instance(&create_info, None).expect("Failed to create the instance") };
-		//Setup the surface
-		let surface_req = VulkanApp::create_surface(&entry, &instance, window);
+		unsafe { entry.create_instance(&create_info, None).expect("Failed to create the instance") }
+	}
+
+	//Sets up everything after the instance/surface - the device, whatever is being rendered to (swapchain or offscreen image), the pipeline, and all the buffers
+	//If there's no surface, it's headless and an offscreen target of the given size is made instead of a swapchain
+	fn init_render_target(entry: ash::Entry, instance: ash::Instance, surface_req: Option<SurfaceReq>, width: u32, height: u32, scene: &Scene) -> VulkanApp {
+		//Start listening for validation messages before anything else gets made
+		let debug_utils_enabled = debug_utils_available(&entry);
+		let debug_messenger = debug_utils_enabled.then(|| DebugMessenger::new(&entry, &instance));
+
 		//Create the physical device
-		let physical_device = VulkanApp::select_physical_device(&instance, &surface_req);
+		let physical_device = VulkanApp::select_physical_device(&instance, surface_req.as_ref());
 		//Find the queue family indices from the physicl device. These will be used for the logical device creation (and a couple other things after that)
-		let queue_family_indices = QueueFamilyIndices::find_queue_families(&instance, physical_device, &surface_req);
+		let queue_family_indices = QueueFamilyIndices::find_queue_families(&instance, physical_device, surface_req.as_ref());
 		//Create logical device and graphics/present queue
-		let (device, graphics_queue, present_queue) = VulkanApp::create_logical_device(&instance, physical_device, &queue_family_indices, &surface_req, enable_layer_names);
-		//Create swapchain (and all the fun stuff that comes with it)
-		let swapchain_req = VulkanApp::create_swapchain(&instance, &device, physical_device, &surface_req, &queue_family_indices, WINDOW_WIDTH, WINDOW_HEIGHT);
-		//Create image views for all the swapchain images
-		let swapchain_image_views = VulkanApp::create_image_views(&device, swapchain_req.swapchain_format, &swapchain_req.swapchain_images);
+		let (device, graphics_queue, present_queue, transfer_queue, max_sampler_anisotropy, wireframe_supported) = VulkanApp::create_logical_device(&instance, physical_device, &queue_family_indices);
+		let object_namer = debug_utils_enabled.then(|| ObjectNamer::new(&instance, &device));
+		//Create the memory allocator - everything after this gets its memory from it
+		let mut allocator = GpuAllocator::new(&instance, physical_device);
+		//Create the uploader that streams assets in on the transfer queue
+		let mut uploader = Uploader::new(&device, &mut allocator, transfer_queue, queue_family_indices.transfer_family.unwrap(), queue_family_indices.graphics_family.unwrap());
+
+		//Create whatever is being rendered to
+		//With a surface: create swapchain (and all the fun stuff that comes with it). Rendered images get presented, so they end up in the present layout
+		//Headless: create the offscreen image. Rendered image gets copied out, so it ends up in the transfer src layout
+		let (swapchain_req, offscreen_target) = match &surface_req {
+			Some(surface_req) => (Some(VulkanApp::create_swapchain(&instance, &device, physical_device, surface_req, &queue_family_indices, width, height)), None),
+			None => (None, Some(OffscreenTarget::new(&device, &mut allocator, width, height))),
+		};
+		let (render_images, render_format, render_extent, final_layout) = match (&swapchain_req, &offscreen_target) {
+			(Some(swapchain_req), _) => (swapchain_req.swapchain_images.clone(), swapchain_req.swapchain_format.format, swapchain_req.swapchain_extent, vk::ImageLayout::PRESENT_SRC_KHR),
+			(_, Some(offscreen_target)) => (vec![offscreen_target.image], offscreen_target.format, offscreen_target.extent, vk::ImageLayout::TRANSFER_SRC_OPTIMAL),
+			_ => unreachable!(),
+		};
+
+		//Create image views for all the images being rendered to
+		let render_image_views = VulkanApp::create_image_views(&device, render_format, &render_images);
+		//Pick a depth format and create the depth image to go with the render images
+		let depth_format = VulkanApp::choose_depth_format(&instance, physical_device);
+		let (depth_image, depth_image_allocation, depth_image_view) = VulkanApp::create_depth_resources(&device, &mut allocator, depth_format, render_extent);
 		//Create the render pass
-		let render_pass = VulkanApp::create_render_pass(&device, swapchain_req.swapchain_format.format);
-		//Create a pipeline including the vertex/fragment shaders
-		let (pipeline, pipeline_layout) = VulkanApp::create_pipeline(&device, render_pass, swapchain_req.swapchain_extent);
-		//Create the framebuffers that contain the image views for the swapchain images as attachments
-		let swapchain_framebuffers = VulkanApp::create_framebuffers(&device, render_pass, &swapchain_image_views, swapchain_req.swapchain_extent);
+		let render_pass = VulkanApp::create_render_pass(&device, render_format, depth_format, final_layout);
+		//Create the descriptor set layouts the pipeline uses
+		let frame_set_layout = create_descriptor_set_layout(&device, &FRAME_SET_BINDINGS);
+		let texture_set_layout = create_descriptor_set_layout(&device, &TEXTURE_SET_BINDINGS);
+		//Create a pipeline for each render mode including the vertex/fragment shaders (the spirv baked into the exe - hot reloading swaps these out later)
+		//Make sure they match the vertex structs/set layouts first - much easier to track down here than as garbage on screen
+		let shader_code = ShaderCode::embedded();
+		shader_code.check().unwrap_or_else(|problems| panic!("Shaders don't match the pipeline:\n{}", problems));
+		let pipeline_cache = load_pipeline_cache(&instance, &device, physical_device);
+		let render_pipelines = RenderPipelines::new(&device, pipeline_cache, render_pass, render_extent, &[frame_set_layout, texture_set_layout], &shader_code, wireframe_supported);
+		//Watch the shader sources for changes, if hot reloading is on. Headless runs (tests) always use the baked in shaders
+		let shader_watcher = match SHADER_HOT_RELOAD && surface_req.is_some() {
+			true => Some(ShaderWatcher::new(Path::new(SHADER_SOURCE_DIR))),
+			false => None,
+		};
+		//Create the framebuffers that contain the image views for the render images (+ the depth image) as attachments
+		let render_framebuffers = VulkanApp::create_framebuffers(&device, render_pass, &render_image_views, depth_image_view, render_extent);
 		//Create the command pool for the graphics family. Also create a short lived command pool on the graphics family for one time operations
 		let (command_pool, command_pool_short) = VulkanApp::create_command_pools(&device, &queue_family_indices);
-		//Create the command buffer with all the recorded commands
-		let command_buffers = VulkanApp::create_command_buffer(&device, command_pool);
-		//Create the vertex buffer
-		let (vertex_buffer, vertex_buffer_memory) = VulkanApp::create_vertex_buffer(&instance, &device, physical_device, command_pool_short, graphics_queue);
+		//Create the command buffers - one for each frame in flight
+		let command_buffers = VulkanApp::create_command_buffers(&device, command_pool, MAX_FRAMES_IN_FLIGHT as u32);
 		//Create all the stuff needed to synchronize the draw
-		let (image_available_semaphore, render_finished_semaphore, in_flight_fence) = VulkanApp::create_sync_objects(&device);
-
-		//Now stick those into the VulkanApp fields to initiate everything (returns this struct)
-		VulkanApp {
+		let (image_available_semaphores, in_flight_fences) = VulkanApp::create_sync_objects(&device);
+		let render_finished_semaphores = VulkanApp::create_semaphores(&device, render_image_views.len());
+		let images_in_flight = vec![vk::Fence::null(); render_image_views.len()];
+		//Create the per frame uniform buffers + descriptor sets
+		let mut descriptor_allocator = DescriptorAllocator::new(&DESCRIPTOR_POOL_SIZES, DESCRIPTOR_POOL_MAX_SETS);
+		let (frame_uniform_buffers, frame_descriptor_sets) = VulkanApp::create_frame_descriptors(&device, &mut allocator, &mut descriptor_allocator, frame_set_layout);
+		//Create the ring buffer that dynamic geometry gets streamed through
+		let streaming_geometry = StreamingBuffer::new(&device, &mut allocator, STREAMING_GEOMETRY_REGION_SIZE, MAX_FRAMES_IN_FLIGHT, vk::BufferUsageFlags::VERTEX_BUFFER | vk::BufferUsageFlags::INDEX_BUFFER);
+		let streaming_instances = StreamingBuffer::new(&device, &mut allocator, STREAMING_INSTANCE_REGION_SIZE, MAX_FRAMES_IN_FLIGHT, vk::BufferUsageFlags::VERTEX_BUFFER);
+		let streaming_debug_lines = StreamingBuffer::new(&device, &mut allocator, DEBUG_LINE_REGION_SIZE, MAX_FRAMES_IN_FLIGHT, vk::BufferUsageFlags::VERTEX_BUFFER);
+		let streaming_overlay = StreamingBuffer::new(&device, &mut allocator, OVERLAY_REGION_SIZE, MAX_FRAMES_IN_FLIGHT, vk::BufferUsageFlags::VERTEX_BUFFER);
+		//Queue up the font atlas for the overlay - it goes out with the scene's assets below
+		let font_texture = Texture::upload(&instance, &device, &mut allocator, &mut uploader, physical_device, &font_atlas(), max_sampler_anisotropy);
+		let font_descriptor_set = descriptor_allocator.allocate(&device, texture_set_layout);
+		write_combined_image_sampler_descriptor(&device, font_descriptor_set, 0, font_texture.image_view, font_texture.sampler);
+		//Create the timestamp queries for GPU timing
+		let gpu_profiler = GpuProfiler::new(&instance, &device, physical_device, queue_family_indices.graphics_family.unwrap());
+
+		//Now stick those into the VulkanApp fields to initiate everything
+		let mut vulkan_app = VulkanApp {
 			entry,
 			instance,
+			debug_messenger,
+			object_namer,
 
-			surface: surface_req.surface,
-			surface_loader: surface_req.surface_loader,
+			surface_req,
 
 			physical_device,
+			max_sampler_anisotropy,
 			device,
+			allocator,
+			uploader,
 
 			graphics_queue,
 			present_queue,
 
-			swapchain: swapchain_req.swapchain,
-			swapchain_loader: swapchain_req.swapchain_loader,
-			swapchain_image_views,
-			swapchain_framebuffers,
-			swapchain_extent: swapchain_req.swapchain_extent,
+			swapchain_req,
+			offscreen_target,
+			swapchain_readback_requested: false,
+			swapchain_readback: None,
+
+			render_image_views,
+			render_framebuffers,
+			render_extent,
+
+			depth_format,
+			depth_image,
+			depth_image_allocation,
+			depth_image_view,
 
 			render_pass,
-			pipeline,
-			pipeline_layout,
+			render_pipelines,
+			render_mode: RenderMode::Fill,
+			pipeline_cache,
+			shader_code,
+			shader_watcher,
+
+			descriptor_allocator,
+			frame_set_layout,
+			texture_set_layout,
+			frame_uniform_buffers,
+			frame_descriptor_sets,
 
 			command_pool,
 			command_pool_short,
 			command_buffers,
 
-			vertex_buffer,
-			vertex_buffer_memory,
+			meshes: vec![],
+			textures: vec![],
+			streaming_geometry,
+			streaming_instances,
+			streaming_debug_lines,
+			streaming_overlay,
+			font_texture,
+			font_descriptor_set,
+			cull_stats: CullStats::default(),
+			gpu_profiler,
+			texture_descriptor_sets: vec![],
+
+			image_available_semaphores,
+			in_flight_fences,
+			render_finished_semaphores,
+			images_in_flight,
+			current_frame: 0,
+		};
 
-			image_available_semaphore,
-			render_finished_semaphore,
-			in_flight_fence,
-		}
+		//Upload all the meshes/textures the scene starts with, then return the app
+		//Wait for those to finish, so the first frame has everything (the first frame's command buffer still acquires them)
+		vulkan_app.upload_scene_assets(scene);
+		vulkan_app.uploader.wait_idle(&vulkan_app.device, &mut vulkan_app.allocator);
+		vulkan_app.name_objects();
+		vulkan_app
 	}
 
 	//Selects the physical device (the GPU) that vulkan uses 
-	fn select_physical_device(instance: &ash::Instance, surface_req: &SurfaceReq) -> vk::PhysicalDevice {
+	fn select_physical_device(instance: &ash::Instance, surface_req: Option<&SurfaceReq>) -> vk::PhysicalDevice {
 		let physical_devices = unsafe { instance.enumerate_physical_devices().expect("Failed to enumerate physical devices") };
 		println!("{:?} device(s) with vulkan support", physical_devices.len());
 
@@ -343,7 +719,8 @@ impl VulkanApp {
 	}
 
 	//This just checks each physical device and gives a score based on properties/features
-	fn physical_device_suitability_score(instance: &ash::Instance, physical_device: vk::PhysicalDevice, surface_req: &SurfaceReq) -> u32 {
+	//If headless (no surface), swapchain support isn't checked
+	fn physical_device_suitability_score(instance: &ash::Instance, physical_device: vk::PhysicalDevice, surface_req: Option<&SurfaceReq>) -> u32 {
 		//These "2"s are all from an update that added pnext to the functions. I really don't need them, but I'm using them anyway.
 		//They all take the old v1 versions of the structs before doing their thing - that's what's behind the "defaults" above, and needs to be expressly stated for the queue families (to get the amount of queue families)
 		//The "get_physical_device_properties" function will modify this, so just set it default for now
@@ -383,23 +760,9 @@ impl VulkanApp {
 		if !VulkanApp::check_device_extension_support(instance, physical_device) {score = 0}
 
 		//Check swapchain support stuff. If there isn't at least one supported image format/presentation mode, it's over
-		let swapchain_support_details = SwapchainSupportDetails::query_swapchain_support_details(physical_device, surface_req);
-		if swapchain_support_details.formats.is_empty() || swapchain_support_details.present_modes.is_empty() {score = 0}
-
-		//Print some info (this is all debug style stuff, probably useful though)
-		let device_name = device_properties.properties.device_name_as_c_str().unwrap();
-		let device_id = device_properties.properties.device_id;
-		let device_type = device_properties.properties.device_type;
-		let api_version_major = vk::api_version_major(device_properties.properties.api_version);
-		let api_version_minor = vk::api_version_minor(device_properties.properties.api_version);
-		let api_version_patch = vk::api_version_patch(device_properties.properties.api_version);
-
-		println!("Device: {:?}, ID: {:?}, Type: {:?}, JAKESCORE: {:?}", device_name, device_id, device_type, score);
-		println!("API version: {:?}.{:?}.{:?}", api_version_major, api_version_minor, api_version_patch);
-
-		println!("Queue Families:");
-		for family in &queue_families {
-			println!("\tQueue count: {:?}, Flags: {:?}", family.queue_family_properties.queue_count, family.queue_family_properties.queue_flags);
+		if let Some(surface_req) = surface_req {
+			let swapchain_support_details = SwapchainSupportDetails::query_swapchain_support_details(physical_device, surface_req);
+			if swapchain_support_details.formats.is_empty() || swapchain_support_details.present_modes.is_empty() {score = 0}
 		}
 
 		//Return the calculated score
@@ -437,11 +800,13 @@ impl VulkanApp {
 	}
 
 	//Find a graphics queue family, create the logical device, create queue
-	fn create_logical_device(instance: &ash::Instance, physical_device: vk::PhysicalDevice, queue_family_indices: &QueueFamilyIndices, surface_req: &SurfaceReq, validation: Vec<*const i8>) -> (ash::Device, vk::Queue, vk::Queue) {
+	//Returns the graphics, present, and transfer queues (which might all be the same queue)
+	//Also returns the max sampler anisotropy (0 if unsupported) and whether wireframe ("fillModeNonSolid") is supported
+	fn create_logical_device(instance: &ash::Instance, physical_device: vk::PhysicalDevice, queue_family_indices: &QueueFamilyIndices) -> (ash::Device, vk::Queue, vk::Queue, vk::Queue, f32, bool) {
 		//Passing the queue family indices into this function, since they're used for a few other things as well
 		//Get UNIQUE queue family indices
 		//This would be more efficient with a hashset, but this should only deal with a few familiy indices so doesn't really matter
-		let mut unique_queue_family_indices = vec![queue_family_indices.graphics_family, queue_family_indices.present_family];
+		let mut unique_queue_family_indices = vec![queue_family_indices.graphics_family, queue_family_indices.present_family, queue_family_indices.transfer_family];
 		unique_queue_family_indices.sort();
 		unique_queue_family_indices.dedup(); //Zorps duplicates (needed to be sorted first)
 		
@@ -467,14 +832,21 @@ impl VulkanApp {
 			queue_create_info_vec.push(queue_create_info);
 		}
 
-		//Physical device features will also feed into the device info - not using any for now
+		//Physical device features will also feed into the device info
+		//Turn on anisotropic filtering for textures, and line polygon mode for wireframe, if the device has them
+		let supported_features = unsafe { instance.get_physical_device_features(physical_device) };
 		let physical_device_features = vk::PhysicalDeviceFeatures {
+			sampler_anisotropy: supported_features.sampler_anisotropy,
+			fill_mode_non_solid: supported_features.fill_mode_non_solid,
 			..Default::default()
 		};
+		let max_sampler_anisotropy = match supported_features.sampler_anisotropy {
+			vk::TRUE => unsafe { instance.get_physical_device_properties(physical_device) }.limits.max_sampler_anisotropy,
+			_ => 0.0,
+		};
 
-		//Set up device specific validation layers as well. Validation layers are deprecated for logical devices, but gotta stick em in anyway
-		//Just take them pre-baked from the "init_vulkan" function as "validation" in the function call
-		//(I commented them out teehee, so "validation" in the fn call is dead code right now)
+		//Device specific validation layers are deprecated for logical devices, so they're not set here
+		//(I commented them out teehee - the instance layers from "create_instance" cover everything, and their messages come through the debug messenger from "debug_utils")
 		//No device specific extensions for now
 
 		//Now do the device creation info (logical device)
@@ -484,8 +856,8 @@ impl VulkanApp {
 			flags: vk::DeviceCreateFlags::empty(),
 			queue_create_info_count: queue_create_info_vec.len() as u32, //Length of the vector with all the queue create infos
 			p_queue_create_infos: queue_create_info_vec.as_ptr(),
-			//enabled_layer_count: if VALIDATION_ENABLED{validation.len() as u32} else {0}, //DEPRECATED
-			//pp_enabled_layer_names: if VALIDATION_ENABLED{validation.as_ptr()} else {ptr::null()}, //DEPRECATED
+			//enabled_layer_count: DEPRECATED
+			//pp_enabled_layer_names: DEPRECATED
 			enabled_extension_count: DEVICE_EXTENSIONS.len() as u32, //Enable extensions from the const "DEVICE EXTENSIONS"
 			pp_enabled_extension_names: &DEVICE_EXTENSIONS[0].as_ptr(),
 			p_enabled_features: &physical_device_features,
@@ -515,40 +887,96 @@ impl VulkanApp {
 			..Default::default()
 		};
 
+		//And the transfer queue. If there's no transfer only family, this is the graphics queue again
+		let transfer_queue_info = vk::DeviceQueueInfo2 {
+			s_type: vk::StructureType::DEVICE_QUEUE_INFO_2,
+			p_next: ptr::null(),
+			flags: vk::DeviceQueueCreateFlags::empty(),
+			queue_family_index: queue_family_indices.transfer_family.unwrap(),
+			queue_index: 0,
+			..Default::default()
+		};
+
 		//Get info of the device's queue at the specified index/length above
 		let graphics_queue = unsafe { device.get_device_queue2(&graphics_queue_info) };
 		let present_queue = unsafe { device.get_device_queue2(&present_queue_info) };
+		let transfer_queue = unsafe { device.get_device_queue2(&transfer_queue_info) };
 
-		//Return the device and graphics queue in a tuple
-		(device, graphics_queue, present_queue)
+		//Return the device and queues in a tuple
+		(device, graphics_queue, present_queue, transfer_queue, max_sampler_anisotropy, supported_features.fill_mode_non_solid == vk::TRUE)
 	}
 
-	//Create the surface to display to (WINDOWS ONLY AT THE MOMENT)
+	//Create the surface to display to
+	//Dispatches on the raw window/display handles from winit - each windowing system has its own surface extension + create info
 	//This will be called in the application handler when the vulkan app is "resumed"
 	fn create_surface(entry: &ash::Entry, instance: &ash::Instance, window: &Window) -> SurfaceReq {
-		//First get the hinstance and hwnd from winit window
+		//Get the raw handles from the winit window
 		let raw_window_handle = window.window_handle().expect("Couldn't get window handle").as_raw();
-		let (hinstance, hwnd) = match window.window_handle().unwrap().as_raw() {
-			RawWindowHandle::Win32(handle) => (handle.hinstance.expect("Couldn't get hinstance").get(), handle.hwnd.get()),
-			_ => panic!("WINDOWS ONLY, FOOL")
-		};
+		let raw_display_handle = window.display_handle().expect("Couldn't get display handle").as_raw();
+
+		//Create the surface. Every platform's surface loader is an ash thing, where the extension has to have its own separate instance.
+		let surface = match (raw_window_handle, raw_display_handle) {
+			//Windows - needs the hinstance and hwnd
+			(RawWindowHandle::Win32(handle), _) => {
+				let surface_info = vk::Win32SurfaceCreateInfoKHR {
+					s_type: vk::StructureType::WIN32_SURFACE_CREATE_INFO_KHR,
+					p_next: ptr::null(),
+					flags: vk::Win32SurfaceCreateFlagsKHR::empty(),
+					hinstance: handle.hinstance.expect("Couldn't get hinstance").get(),
+					hwnd: handle.hwnd.get(),
+					..Default::default()
+				};
+				let surface_loader_win32 = khr::win32_surface::Instance::new(entry, instance);
+				unsafe { surface_loader_win32.create_win32_surface(&surface_info, None).expect("Failed to create win32 surface") }
+			},
 
-		//Surface creation info:
-		let surface_info = vk::Win32SurfaceCreateInfoKHR {
-			s_type: vk::StructureType::WIN32_SURFACE_CREATE_INFO_KHR,
-			p_next: ptr::null(),
-			flags: vk::Win32SurfaceCreateFlagsKHR::empty(),
-			hinstance,
-			hwnd,
-			..Default::default()
+			//X11 through xlib - needs the display connection and the window id. This is what winit gives on X11
+			(RawWindowHandle::Xlib(window_handle), RawDisplayHandle::Xlib(display_handle)) => {
+				let surface_info = vk::XlibSurfaceCreateInfoKHR {
+					s_type: vk::StructureType::XLIB_SURFACE_CREATE_INFO_KHR,
+					p_next: ptr::null(),
+					flags: vk::XlibSurfaceCreateFlagsKHR::empty(),
+					dpy: display_handle.display.expect("Couldn't get xlib display").as_ptr(),
+					window: window_handle.window,
+					..Default::default()
+				};
+				let surface_loader_xlib = khr::xlib_surface::Instance::new(entry, instance);
+				unsafe { surface_loader_xlib.create_xlib_surface(&surface_info, None).expect("Failed to create xlib surface") }
+			},
+
+			//X11 through xcb - needs the xcb connection and the window id
+			(RawWindowHandle::Xcb(window_handle), RawDisplayHandle::Xcb(display_handle)) => {
+				let surface_info = vk::XcbSurfaceCreateInfoKHR {
+					s_type: vk::StructureType::XCB_SURFACE_CREATE_INFO_KHR,
+					p_next: ptr::null(),
+					flags: vk::XcbSurfaceCreateFlagsKHR::empty(),
+					connection: display_handle.connection.expect("Couldn't get xcb connection").as_ptr(),
+					window: window_handle.window.get(),
+					..Default::default()
+				};
+				let surface_loader_xcb = khr::xcb_surface::Instance::new(entry, instance);
+				unsafe { surface_loader_xcb.create_xcb_surface(&surface_info, None).expect("Failed to create xcb surface") }
+			},
+
+			//Wayland - needs the wl_display and the wl_surface
+			(RawWindowHandle::Wayland(window_handle), RawDisplayHandle::Wayland(display_handle)) => {
+				let surface_info = vk::WaylandSurfaceCreateInfoKHR {
+					s_type: vk::StructureType::WAYLAND_SURFACE_CREATE_INFO_KHR,
+					p_next: ptr::null(),
+					flags: vk::WaylandSurfaceCreateFlagsKHR::empty(),
+					display: display_handle.display.as_ptr(),
+					surface: window_handle.surface.as_ptr(),
+					..Default::default()
+				};
+				let surface_loader_wayland = khr::wayland_surface::Instance::new(entry, instance);
+				unsafe { surface_loader_wayland.create_wayland_surface(&surface_info, None).expect("Failed to create wayland surface") }
+			},
+
+			_ => panic!("Unsupported window/display handle combo: {:?}, {:?}", raw_window_handle, raw_display_handle)
 		};
 
-		//Creates the loader instance for the win32 window. I think this is an ash thing, where the extension has to have its own separate instance.
-		let surface_loader_win32 = khr::win32_surface::Instance::new(entry, instance);
 		//Also need the more general surface loader to destroy shit later
 		let surface_loader = khr::surface::Instance::new(entry, instance);
-		//Create surface
-		let surface = unsafe { surface_loader_win32.create_win32_surface(&surface_info, None).expect("Failed to create surface") };
 		
 		//Return the surface and its instance
 		SurfaceReq {
@@ -583,6 +1011,14 @@ impl VulkanApp {
 			swapchain_support_details.capabilities.min_image_count
 		};
 
+		//Copying swapchain images out (screenshots, frame captures) needs TRANSFER_SRC, which surfaces don't have to support. Only ask for it if it's there
+		let readback_supported = swapchain_support_details.capabilities.supported_usage_flags.contains(vk::ImageUsageFlags::TRANSFER_SRC);
+		let image_usage = if readback_supported {
+			vk::ImageUsageFlags::COLOR_ATTACHMENT | vk::ImageUsageFlags::TRANSFER_SRC
+		} else {
+			vk::ImageUsageFlags::COLOR_ATTACHMENT
+		};
+
 		//Need to handle the case of graphics queue family being different than presentation queue family
 		//In that case, the swapchain will need to be shared between the two families
 		//Queue family indices are passed into this function
@@ -602,7 +1038,7 @@ impl VulkanApp {
 			image_color_space: surface_format.color_space,
 			image_extent: extent,
 			image_array_layers: 1, //Used for stereoscopic 3d, not doing that so it's just 1
-			image_usage: vk::ImageUsageFlags::COLOR_ATTACHMENT, //This defines what the swapchain images will be used for. This one allows the image view created from this image to be used as the color attachment of a framebuffer
+			image_usage, //This defines what the swapchain images will be used for. Color attachment allows the image view created from this image to be used as the color attachment of a framebuffer, transfer src (when supported) lets it get copied out for screenshots
 			image_sharing_mode,
 			queue_family_index_count,
 			p_queue_family_indices: queue_family_indices.as_ptr(),
@@ -629,7 +1065,8 @@ impl VulkanApp {
 			swapchain,
 			swapchain_format: surface_format,
 			swapchain_extent: extent,
-			swapchain_images
+			swapchain_images,
+			readback_supported,
 		}
 	}
 
@@ -703,9 +1140,9 @@ impl VulkanApp {
 		}
 	}
 
-	//Create image views for the swapchain images
-	//Input a vec of images from swapchain_req.swapchain_images
-	fn create_image_views(device: &ash::Device, surface_format: vk::SurfaceFormatKHR, images: &Vec<vk::Image>) -> Vec<vk::ImageView> {
+	//Create image views for the images being rendered to
+	//Input a vec of images from swapchain_req.swapchain_images (or the offscreen image)
+	fn create_image_views(device: &ash::Device, format: vk::Format, images: &Vec<vk::Image>) -> Vec<vk::ImageView> {
 		let mut swapchain_image_views = vec![];
 		//Loop over all the images and get image views
 		for &image in images {
@@ -716,7 +1153,7 @@ impl VulkanApp {
 				flags: vk::ImageViewCreateFlags::empty(),
 				image,
 				view_type: vk::ImageViewType::TYPE_2D_ARRAY, //Can treat as a 1d texture, 2d texture, or 3d texture/cube map
-				format: surface_format.format, //Get from the surface format passed into the function
+				format, //Get from the surface format (or offscreen format) passed into the function
 				components: vk::ComponentMapping { //No swizzle wanted right now, so just map components as-is
 					r: vk::ComponentSwizzle::R,
 					g: vk::ComponentSwizzle::G,
@@ -741,10 +1178,63 @@ impl VulkanApp {
 		swapchain_image_views
 	}
 
+	//Picks a depth format the physical device can use as a depth attachment with optimal tiling
+	//Go in priority order - pure 32 bit float depth is the most precise, then the combined depth/stencil formats
+	fn choose_depth_format(instance: &ash::Instance, physical_device: vk::PhysicalDevice) -> vk::Format {
+		let candidate_formats = [vk::Format::D32_SFLOAT, vk::Format::D32_SFLOAT_S8_UINT, vk::Format::D24_UNORM_S8_UINT];
+
+		for format in candidate_formats {
+			let format_properties = unsafe { instance.get_physical_device_format_properties(physical_device, format) };
+			if format_properties.optimal_tiling_features.contains(vk::FormatFeatureFlags::DEPTH_STENCIL_ATTACHMENT) {
+				return format
+			}
+		}
+		panic!("Failed to find a supported depth format");
+	}
+
+	//Creates the depth image, its memory, and its image view
+	//Needs to be recreated whenever the render images change size (swapchain recreation)
+	fn create_depth_resources(device: &ash::Device, allocator: &mut GpuAllocator, depth_format: vk::Format, extent: vk::Extent2D) -> (vk::Image, Allocation, vk::ImageView) {
+		//Only ever used as a depth attachment, never read by the CPU, so device local
+		let (depth_image, depth_image_allocation) = create_image(device, allocator, extent, 1, depth_format, vk::ImageUsageFlags::DEPTH_STENCIL_ATTACHMENT, vk::MemoryPropertyFlags::DEVICE_LOCAL);
+
+		//Image view for the depth image - same deal as the color image views, but only looking at the depth aspect
+		let image_view_info = vk::ImageViewCreateInfo {
+			s_type: vk::StructureType::IMAGE_VIEW_CREATE_INFO,
+			p_next: ptr::null(),
+			flags: vk::ImageViewCreateFlags::empty(),
+			image: depth_image,
+			view_type: vk::ImageViewType::TYPE_2D,
+			format: depth_format,
+			components: vk::ComponentMapping::default(), //Identity swizzle
+			subresource_range: vk::ImageSubresourceRange {
+				aspect_mask: vk::ImageAspectFlags::DEPTH, //Stencil aspect isn't used even if the format has one
+				base_mip_level: 0,
+				level_count: 1,
+				base_array_layer: 0,
+				layer_count: 1
+			},
+			..Default::default()
+		};
+		let depth_image_view = unsafe { device.create_image_view(&image_view_info, None).expect("Failed to create depth image view") };
+
+		(depth_image, depth_image_allocation, depth_image_view)
+	}
+
+	//Destroys the depth image, view, and memory - for swapchain recreation/cleanup
+	fn destroy_depth_resources(&mut self) {
+		unsafe {
+			self.device.destroy_image_view(self.depth_image_view, None);
+			self.device.destroy_image(self.depth_image, None);
+		}
+		self.allocator.free(&self.device, &self.depth_image_allocation);
+	}
+
 	//Create a render pass for the pipeline
 	//Decribes framebuffer attachments to be used when rendering
 	//Dynamic rendering ("VK_KHR_dynamic_rendering") would make it so this isn't really necessary (makes each render pass just one subpass). Subpasses are really only important for phone GPUs (tiled GPUs)
-	fn create_render_pass(device: &ash::Device, surface_format: vk::Format) -> vk::RenderPass {
+	//Final layout is where the color attachment ends up - present layout for the swapchain, transfer src for an offscreen image that gets read back
+	fn create_render_pass(device: &ash::Device, surface_format: vk::Format, depth_format: vk::Format, final_layout: vk::ImageLayout) -> vk::RenderPass {
 		//First create attachment description
 		//There's also an AttachmentDescription2, but it only really adds s_type and p_next
 		let color_attachment = vk::AttachmentDescription {
@@ -756,7 +1246,20 @@ impl VulkanApp {
 			stencil_load_op: vk::AttachmentLoadOp::DONT_CARE, //and load behavior for the stencil component
 			stencil_store_op: vk::AttachmentStoreOp::DONT_CARE, //and store behavior for the stencil component
 			initial_layout: vk::ImageLayout::UNDEFINED, //Input image layout - "UNDEFINED" usually means the image was just created
-			final_layout: vk::ImageLayout::PRESENT_SRC_KHR //Output image layout - we want it to go straight to the swapchain
+			final_layout //Output image layout - either going straight to the swapchain or getting copied out
+		};
+
+		//Depth attachment - cleared at the start, and nothing needs it after the render pass so it doesn't get stored
+		let depth_attachment = vk::AttachmentDescription {
+			flags: vk::AttachmentDescriptionFlags::empty(),
+			format: depth_format,
+			samples: vk::SampleCountFlags::TYPE_1,
+			load_op: vk::AttachmentLoadOp::CLEAR,
+			store_op: vk::AttachmentStoreOp::DONT_CARE,
+			stencil_load_op: vk::AttachmentLoadOp::DONT_CARE,
+			stencil_store_op: vk::AttachmentStoreOp::DONT_CARE,
+			initial_layout: vk::ImageLayout::UNDEFINED, //Don't care what was in there before, it's getting cleared
+			final_layout: vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL
 		};
 
 		//Subpasses will reference the attachments, need to set up the attachment references
@@ -764,6 +1267,10 @@ impl VulkanApp {
 			attachment: 0, //Index of attachment to use in RenderPassCreateInfo
 			layout: vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL, //Image layout to use during the subpass
 		};
+		let depth_attachment_ref = vk::AttachmentReference {
+			attachment: 1,
+			layout: vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL,
+		};
 
 		//Subpass description
 		//There's also a "SubpassDescription2," which adds a view mask for multiview - don't really need it
@@ -775,7 +1282,7 @@ impl VulkanApp {
 			color_attachment_count: 1,
 			p_color_attachments: &color_attachment_ref,
 			p_resolve_attachments: ptr::null(), //Attachments used for multisampling
-			p_depth_stencil_attachment: ptr::null(), //Attachment for depth/stencil data
+			p_depth_stencil_attachment: &depth_attachment_ref, //Attachment for depth/stencil data
 			preserve_attachment_count: 0, //Attachments that aren't used by this subpass, but need to be preserved
 			p_preserve_attachments: ptr::null(),
 			..Default::default()
@@ -785,24 +1292,28 @@ impl VulkanApp {
 		//The first implicit subpass has an implicit subpass dependency already, but that dependency is at the top of the pipe
 		//Need to make sure render passes don't begin until the image is available, but without this, there's nothing stopping a subpass from executing at the top of the pipe
 		//So this has the color output stage of subpass 0 (the dependent subpass) wait until the color output + write from the dependency (the first implicit subpass), which won't happen while the semaphore is a thing
+		//The depth image is shared between frames, so the depth tests also have to wait for the last render pass's depth writes to finish before clearing it
 		let subpass_dependencies = [vk::SubpassDependency {
 			src_subpass: vk::SUBPASS_EXTERNAL, //Dependency - "SUBPASS_EXTERNAL" refers to operations that happen before the render pass
 			dst_subpass: 0, //Subpass index of the dependent subpass
-			src_stage_mask: vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT, //Stage of the src subpass for the dst subpass to wait for - once the src subpass gets here, the dst subpass is allowed to go ahead
-			src_access_mask: vk::AccessFlags::empty(), //We're not waiting on any memory dependency, we just need to know that the color output stage (and thus the semaphore + swapchain image acquisition) has executed
-			dst_stage_mask: vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT, //Operations that should wait (writing of the color attachment) - so the render pass is allowed to execute up to this point
-			dst_access_mask: vk::AccessFlags::COLOR_ATTACHMENT_WRITE, //Dst subpass waits on writing to the color attachment
+			src_stage_mask: vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT | vk::PipelineStageFlags::LATE_FRAGMENT_TESTS, //Stage of the src subpass for the dst subpass to wait for - once the src subpass gets here, the dst subpass is allowed to go ahead
+			src_access_mask: vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE, //No memory dependency for color, we just need to know that the color output stage (and thus the semaphore + swapchain image acquisition) has executed. Depth writes need to be done though
+			dst_stage_mask: vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT | vk::PipelineStageFlags::EARLY_FRAGMENT_TESTS, //Operations that should wait (writing of the color attachment, depth clear/tests) - so the render pass is allowed to execute up to this point
+			dst_access_mask: vk::AccessFlags::COLOR_ATTACHMENT_WRITE | vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE, //Dst subpass waits on writing to the color + depth attachments
 			dependency_flags: vk::DependencyFlags::empty(),
 		}];
 
+		//Attachment indices here match the attachment references above
+		let attachments = [color_attachment, depth_attachment];
+
 		//Render pass creation info
 		//There's also a "RenderPassCreationInfo2" that adds a mask suggesting views that should be rendered concurrently, not necessary
 		let render_pass_info = vk::RenderPassCreateInfo {
 			s_type: vk::StructureType::RENDER_PASS_CREATE_INFO,
 			p_next: ptr::null(),
 			flags: vk::RenderPassCreateFlags::empty(),
-			attachment_count: 1,
-			p_attachments: &color_attachment,
+			attachment_count: attachments.len() as u32,
+			p_attachments: attachments.as_ptr(),
 			subpass_count: 1,
 			p_subpasses: &subpass,
 			dependency_count: subpass_dependencies.len() as u32, //Memory dependencies between subpasses
@@ -817,14 +1328,14 @@ impl VulkanApp {
 	//Create the pipeline
 	//Most of the pipeline must be baked. Can configure certain things to be dynamic with "PipelineDynamicStateCreateInfo"
 	//Dynamic states will be recorded into the command buffer after the pipeline is bound
-	fn create_pipeline(device: &ash::Device, render_pass: vk::RenderPass, swapchain_extent: vk::Extent2D) -> (vk::Pipeline, vk::PipelineLayout) {
+	//Descriptor set layouts go in order - set 0 first, then set 1, etc
+	//Shaders, vertex format, topology, etc come from the description
+	fn create_pipeline(device: &ash::Device, pipeline_cache: vk::PipelineCache, render_pass: vk::RenderPass, swapchain_extent: vk::Extent2D, set_layouts: &[vk::DescriptorSetLayout], description: &PipelineDescription) -> (vk::Pipeline, vk::PipelineLayout) {
+		let (vertex_shader_code, fragment_shader_code) = (description.vertex_shader_code, description.fragment_shader_code);
 		//Start with the programmable pipeline stages
-		//Read the spirv files for the vertex/fragment shaders
 		//Shader modules should be destroyed after pipeline creation
-		let fragment_shader_code = fragment_shader();
-		let vertex_shader_code = vertex_shader();
 
-		//Create the shader modules from those files
+		//Create the shader modules from the spirv code
 		let fragment_shader_module = VulkanApp::create_shader_module(device, fragment_shader_code);
 		let vertex_shader_module = VulkanApp::create_shader_module(device, vertex_shader_code);
 		
@@ -864,8 +1375,8 @@ impl VulkanApp {
 		let mut dynamic_states = vec![];
 
 		//Get binding + attribute descriptions for the vertex input stage
-		let binding_descriptions = Vertex::get_binding_descriptions();
-		let attribute_descriptions = Vertex::get_attribute_descriptions();
+		let binding_descriptions = description.binding_descriptions;
+		let attribute_descriptions = description.attribute_descriptions;
 
 		//Vertex input create info. This has to do with has vertices are passed to the vertex shader
 		let vertex_input_state_info = vk::PipelineVertexInputStateCreateInfo {
@@ -884,7 +1395,7 @@ impl VulkanApp {
 			s_type: vk::StructureType::PIPELINE_INPUT_ASSEMBLY_STATE_CREATE_INFO,
 			p_next: ptr::null(),
 			flags: vk::PipelineInputAssemblyStateCreateFlags::empty(),
-			topology: vk::PrimitiveTopology::TRIANGLE_LIST, //Defines every 3 vertices as a triangle primitive, no overlap
+			topology: description.topology, //Triangle list defines every 3 vertices as a triangle primitive, no overlap. Line list is every 2 as a line
 			primitive_restart_enable: vk::FALSE, //Will allow a "restart" vertex index value that can break shit up. Irrelevant for triangle list mode
 			..Default::default()
 		};
@@ -913,9 +1424,9 @@ impl VulkanApp {
 			flags: vk::PipelineRasterizationStateCreateFlags::empty(),
 			depth_clamp_enable: vk::FALSE, //Clamps to z_near and z_far planes instead of discarding values past them if true (requires an extension)
 			rasterizer_discard_enable: vk::FALSE, //Discards triangles before rasterizing. Disables output to framebuffer basically
-			polygon_mode: vk::PolygonMode::FILL, //Can do wireframe or whatever
+			polygon_mode: description.polygon_mode, //Fill, or line for wireframe
 			line_width: 1.0, //Any line thicker than 1.0 will require GPU feature
-			cull_mode: vk::CullModeFlags::BACK, //Cull the back facing triangles only
+			cull_mode: description.cull_mode, //Usually cull the back facing triangles only
 			front_face: vk::FrontFace::COUNTER_CLOCKWISE, //Defines triangle winding convention used for face culling
 			depth_bias_enable: vk::FALSE, //Bias on all the depth values. I guess it can be used for shadow maps or something
 			depth_bias_constant_factor: 0.0,
@@ -940,7 +1451,7 @@ impl VulkanApp {
 		};
 
 		//Configures depth/stencil tests if using depth/stencil buffer
-		//Not using for now
+		//Depth is on, stencil is not
 		//First have to comfigure stencil state - right now, not using the stencil buffer - will always keep everything
 		//Can setup dynamic enabling/disabling of the stencil test if need be
 		let stencil_state = vk::StencilOpState {
@@ -957,8 +1468,8 @@ impl VulkanApp {
 			s_type: vk::StructureType::PIPELINE_DEPTH_STENCIL_STATE_CREATE_INFO,
 			p_next: ptr::null(),
 			flags: vk::PipelineDepthStencilStateCreateFlags::empty(),
-			depth_test_enable: vk::FALSE, //Enables depth testing - compares new fragments to depth buffer
-			depth_write_enable: vk::FALSE, //Enables whether depth attachment is written to if the comparison comes back as "true" during depth test (sets to sample's depth if so)
+			depth_test_enable: description.depth_test as vk::Bool32, //Enables depth testing - compares new fragments to depth buffer
+			depth_write_enable: description.depth_test as vk::Bool32, //Enables whether depth attachment is written to if the comparison comes back as "true" during depth test (sets to sample's depth if so)
 			depth_compare_op: vk::CompareOp::LESS_OR_EQUAL, //What operator to use for depth comparison (lower depth is closer by convention)
 			depth_bounds_test_enable: vk::FALSE, //This and the two bounds let you discard things in a certain depth range. Don't really need it
 			min_depth_bounds: 0.0,
@@ -971,11 +1482,11 @@ impl VulkanApp {
 
 		//Color blending - controls how fragment shader's returned color mixes with the color already in the framebuffer
 		//Need the attachment states first
-		//Disable blending for now - framebuffer will just take new color
+		//Blending's off unless the description asks for it - then it's regular alpha blending (new * alpha + old * (1 - alpha)). Otherwise the framebuffer just takes the new color
 		let color_blend_attachments = [vk::PipelineColorBlendAttachmentState {
-			blend_enable: vk::FALSE,
-			src_color_blend_factor: vk::BlendFactor::ONE, //What the source color (new color from fragment buffer) is multiplied by for blending
-			dst_color_blend_factor: vk::BlendFactor::ONE, //What the destination color (old color in framebuffer) is multiplied by before blending
+			blend_enable: description.alpha_blend as vk::Bool32,
+			src_color_blend_factor: vk::BlendFactor::SRC_ALPHA, //What the source color (new color from fragment buffer) is multiplied by for blending
+			dst_color_blend_factor: vk::BlendFactor::ONE_MINUS_SRC_ALPHA, //What the destination color (old color in framebuffer) is multiplied by before blending
 			color_blend_op: vk::BlendOp::ADD, //The operation used to blend the two colors
 			src_alpha_blend_factor: vk::BlendFactor::ONE, //Same thing is done again for alpha
 			dst_alpha_blend_factor: vk::BlendFactor::ONE,
@@ -1005,24 +1516,19 @@ impl VulkanApp {
 			..Default::default()
 		};
 		
-		//Setup shader push constants to be used in pipeline layouts
-		//Push constants are mega-small (~128 bytes at minimum, so 2 glam::f32::Mat4s), but are very fast, and are updated via commands rather than memory/copy commands
-		//I'm using them over a uniform buffer because I'm recording commands each frame anyway, so these will slot in nicely
-		//Just want to push view + projection matrix for now - will only be used in the vertex bit
-		let push_constant_ranges = [vk::PushConstantRange {		
-			stage_flags: vk::ShaderStageFlags::VERTEX,
-			offset: 0,
-			size: core::mem::size_of::<glam::f32::Mat4>() as u32,
-		}];
-
-		//Pipeline layout - describes resources that can be accessed by a pipeline (descriptor sets)
-		//Use push constants for transformation matrices rather than uniform buffers - recording 
+		//Pipeline layout - describes resources that can be accessed by a pipeline (descriptor sets + push constants)
+		//Push constant ranges come straight from whatever push constant blocks the shaders declare (see "reflection")
+		//There aren't any right now - per object matrices come in through the instance vertex binding, so one draw can cover lots of objects
+		//Everything that's the same for the whole frame goes in the frame uniform buffer
+		let vertex_reflection = ShaderReflection::parse(vertex_shader_code).expect("Failed to reflect vertex shader");
+		let fragment_reflection = ShaderReflection::parse(fragment_shader_code).expect("Failed to reflect fragment shader");
+		let push_constant_ranges = push_constant_ranges(&[&vertex_reflection, &fragment_reflection]);
 		let pipeline_layout_info = vk::PipelineLayoutCreateInfo {
 			s_type: vk::StructureType::PIPELINE_LAYOUT_CREATE_INFO,
 			p_next: ptr::null(),
 			flags: vk::PipelineLayoutCreateFlags::empty(),
-			set_layout_count: 0, //Number of descriptor sets in pipeline layout
-			p_set_layouts: ptr::null(), //Pointer to descriptor set layouts
+			set_layout_count: set_layouts.len() as u32, //Number of descriptor sets in pipeline layout
+			p_set_layouts: set_layouts.as_ptr(), //Pointer to descriptor set layouts
 			push_constant_range_count: push_constant_ranges.len() as u32, //Number of push constants in pipeline layout
 			p_push_constant_ranges: push_constant_ranges.as_ptr(), //Pointer to push constants layouts
 			..Default::default()
@@ -1060,8 +1566,8 @@ impl VulkanApp {
 		let pipeline_infos = [pipeline_info];
 
 		//Create the pipeline
-		//Pipeline cache allows for reuse of pipeline creation details, can speed creation of pipelines later. "Leave as vk::PipelineCache::null()" to not use it
-		let pipelines = unsafe { device.create_graphics_pipelines(vk::PipelineCache::null(), &pipeline_infos, None).expect("Failed to create graphics pipeline(s)") };
+		//Pipeline cache allows for reuse of pipeline creation details, so the driver doesn't have to compile the shaders again if it's seen them before (see "pipeline_cache")
+		let pipelines = unsafe { device.create_graphics_pipelines(pipeline_cache, &pipeline_infos, None).expect("Failed to create graphics pipeline(s)") };
 
 		//Destroy the shader modules now, since they won't be needed after the pipeline gets created
 		unsafe {
@@ -1074,8 +1580,16 @@ impl VulkanApp {
 		(pipelines[0], pipeline_layout)
 	}
 
+	//Checks the shaders' inputs/descriptors against the vertex attributes + descriptor set layouts the pipeline gets made with
+	//Returns every mismatch found, one per line
+	fn check_shaders(vertex_shader_code: &[u8], fragment_shader_code: &[u8], attributes: &[vk::VertexInputAttributeDescription]) -> Result<(), String> {
+		let vertex_reflection = ShaderReflection::parse(vertex_shader_code).map_err(|error| format!("Vertex shader: {}", error))?;
+		let fragment_reflection = ShaderReflection::parse(fragment_shader_code).map_err(|error| format!("Fragment shader: {}", error))?;
+		check_pipeline_interface(&vertex_reflection, &fragment_reflection, attributes, &PIPELINE_SET_BINDINGS)
+	}
+
 	//Create shader modules to be used in pipeline
-	fn create_shader_module(device: &ash::Device, shader_code: Vec<u8>) -> vk::ShaderModule {
+	fn create_shader_module(device: &ash::Device, shader_code: &[u8]) -> vk::ShaderModule {
 		//Shader module creation info
 		let shader_module_info = vk::ShaderModuleCreateInfo {
 			s_type: vk::StructureType::SHADER_MODULE_CREATE_INFO,
@@ -1092,19 +1606,20 @@ impl VulkanApp {
 
 	//Creates framebuffers to hold attachments needed for the render pass
 	//Iterate through image views, create framebuffer for each one
-	fn create_framebuffers(device: &ash::Device, render_pass: vk::RenderPass, image_views: &Vec<vk::ImageView>, swapchain_extent: vk::Extent2D) -> Vec<vk::Framebuffer> {
+	//All the framebuffers share the one depth image view
+	fn create_framebuffers(device: &ash::Device, render_pass: vk::RenderPass, image_views: &Vec<vk::ImageView>, depth_image_view: vk::ImageView, swapchain_extent: vk::Extent2D) -> Vec<vk::Framebuffer> {
 		let mut framebuffers = vec![];
 		
 		//Loop through the swapchain image views, get a framebuffer for each one
 		//Need a framebuffer for each image view to write to whenever the swapchain does its whole swap thing
 		for &image_view in image_views {
-			let attachments = [image_view];
+			let attachments = [image_view, depth_image_view]; //Same order as the attachments in the render pass
 			let framebuffer_info = vk::FramebufferCreateInfo {
 				s_type: vk::StructureType::FRAMEBUFFER_CREATE_INFO,
 				p_next: ptr::null(),
 				flags: vk::FramebufferCreateFlags::empty(),
 				render_pass, //Render pass used for framebuffer compatibility
-				attachment_count: 1, //Just the color attachment for now
+				attachment_count: attachments.len() as u32, //Color + depth
 				p_attachments: attachments.as_ptr(),
 				width: swapchain_extent.width, //Framebuffer will have the same extent as the swap chain image view (which has the same extent as the swap chain images)
 				height: swapchain_extent.height,
@@ -1151,65 +1666,61 @@ impl VulkanApp {
 	}
 
 	//Allocates and creates command buffers for commands to be submitted to
-	//Currently creating one command buffer for the one frame in flight
+	//One command buffer for each frame in flight
 	//Then, command buffers can be reused + rerecorded during frame draw
-	fn create_command_buffer(device: &ash::Device, command_pool: vk::CommandPool) -> Vec<vk::CommandBuffer> {
-		//As long as CPU/GPU are going fast enough, don't need multiple frames in flight
-		//Frames in flight are only there to give CPU something to do while the GPU chugs away, but the CPU will get farther ahead (more input lag)
+	//Frames in flight are there to give CPU something to do while the GPU chugs away, but the CPU will get farther ahead (more input lag)
+	fn create_command_buffers(device: &ash::Device, command_pool: vk::CommandPool, command_buffer_count: u32) -> Vec<vk::CommandBuffer> {
 		let command_buffer_info = vk::CommandBufferAllocateInfo {
 			s_type: vk::StructureType::COMMAND_BUFFER_ALLOCATE_INFO,
 			p_next: ptr::null(),
 			command_pool, //Command pool from which command buffer is allocated
 			level: vk::CommandBufferLevel::PRIMARY, //Primary or secondary. Primary command buffers can execute secondary command buffers, kinda like executing a function
-			command_buffer_count: 1, //Number of command buffers to allocate. If doing multiple frames in flight, must have one for each framebuffer (one for each swapchain image)
+			command_buffer_count, //Number of command buffers to allocate. One for each frame in flight, so a frame can be recorded while the others are still executing
 			..Default::default()
 		};
 
-		//Allocate and return the command buffer
+		//Allocate and return the command buffers
 		unsafe { device.allocate_command_buffers(&command_buffer_info).expect("Failed to allocate command buffers") }
 	}
 
-	//Creates a vertex buffer - will hold vertex data
-	//Uses a staging buffer that is host visible and host coherent. Then, will transfer that to device local memory (faster)
-	fn create_vertex_buffer(instance: &ash::Instance, device: &ash::Device, physical_device: vk::PhysicalDevice, command_pool: vk::CommandPool, submit_queue: vk::Queue) -> (vk::Buffer, vk::DeviceMemory) {
-		//Setup size + usage for the vertex buffer
-		let buffer_size = core::mem::size_of_val(&TEST_TRIANGLE_VERTICES) as u64;
-		let staging_buffer_usage = vk::BufferUsageFlags::TRANSFER_SRC; //Staging buffer will end up transferring to the vertex buffer
-		//If host coherent, "vkFlushMappedMemoryRanges" and "vkInvalidateMappedMemoryRanges" aren't needed during memory mapping, but it's slower
-		let staging_required_memory_properties = vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT;
-		
-		//Create the staging buffer
-		let (staging_buffer, staging_buffer_memory) = create_buffer(instance, device, physical_device, buffer_size, staging_buffer_usage, staging_required_memory_properties);
-
-		//Have to fill the vertex buffer - map buffer memory into CPU accessible memory
-		//This gives a pointer to a region of mappable memory
-		let p_mappable = unsafe { device.map_memory(staging_buffer_memory, 0, buffer_size, vk::MemoryMapFlags::empty()).expect("Failed to map device memory") as *mut Vertex};
-		//Copy the data into that mappable memory - rust equivalent of "memcpy"
-		unsafe { ptr::copy_nonoverlapping(TEST_TRIANGLE_VERTICES.as_ptr(), p_mappable, TEST_TRIANGLE_VERTICES.len()) };
-		//Unmap the memory. Typically we can't guarantee the order, and would have to use "vkFlushMappedMemoryRanges" and "vkInvalidateMappedMemoryRanges"
-		//The memory was chosen to be host coherent with "vk::MemoryPropertyFlags::HOST_COHERENT" so those aren't needed
-		unsafe { device.unmap_memory(staging_buffer_memory) };
-
-		//Now create the vertex buffer
-		let vertex_buffer_usage = vk::BufferUsageFlags::TRANSFER_DST | vk::BufferUsageFlags::VERTEX_BUFFER; //Want both the transfer destination bit the and vertex buffer bit
-		let vertex_required_memory_properties = vk::MemoryPropertyFlags::DEVICE_LOCAL;
+	//Creates a uniform buffer + descriptor set for each frame in flight, and points each set at its buffer
+	//The buffers get written every frame in "record_command_buffer", the sets never need to change
+	fn create_frame_descriptors(device: &ash::Device, allocator: &mut GpuAllocator, descriptor_allocator: &mut DescriptorAllocator, frame_set_layout: vk::DescriptorSetLayout) -> (UniformBuffers<FrameUniforms>, Vec<vk::DescriptorSet>) {
+		let frame_uniform_buffers = UniformBuffers::new(device, allocator, MAX_FRAMES_IN_FLIGHT);
+
+		let frame_descriptor_sets = (0..MAX_FRAMES_IN_FLIGHT)
+			.map(|frame| {
+				let descriptor_set = descriptor_allocator.allocate(device, frame_set_layout);
+				write_uniform_buffer_descriptor(device, descriptor_set, 0, frame_uniform_buffers.buffer(frame));
+				descriptor_set
+			})
+			.collect();
 
-		let (vertex_buffer, vertex_buffer_memory) = create_buffer(instance, device, physical_device, buffer_size, vertex_buffer_usage, vertex_required_memory_properties);
+		(frame_uniform_buffers, frame_descriptor_sets)
+	}
 
-		//Copy the staging buffer into the vertex buffer
-		//Pass in graphics queue, since that's required to support transfer by spec. Could find a separate queue for transfer operations, but this is really only a concern when multithreading transfers
-		copy_buffer(device, command_pool, submit_queue, staging_buffer, vertex_buffer, buffer_size);
+	//Create synchronization objects to deal with frames in flight + swapchain sync stuff
+	//Makes the image available semaphores and in flight fences, one of each for every frame slot
+	fn create_sync_objects(device: &ash::Device) -> (Vec<vk::Semaphore>, Vec<vk::Fence>) {
+		let fence_info = vk::FenceCreateInfo {
+			s_type: vk::StructureType::FENCE_CREATE_INFO,
+			p_next: ptr::null(),
+			flags: vk::FenceCreateFlags::SIGNALED, //Create the fences signaled to show that the slots are free
+			..Default::default()
+		};
 
-		//Can get rid of the staging buffers now
-		unsafe { device.destroy_buffer(staging_buffer, None) };
-		unsafe { device.free_memory(staging_buffer_memory, None) };
+		//Create semaphores + fences
+		let image_available_semaphores = VulkanApp::create_semaphores(device, MAX_FRAMES_IN_FLIGHT);
+		let in_flight_fences = (0..MAX_FRAMES_IN_FLIGHT)
+			.map(|_| unsafe { device.create_fence(&fence_info, None).expect("Failed to create fence") })
+			.collect();
 
-		//Return the vertex buffer as well as its memory to be freed later
-		(vertex_buffer, vertex_buffer_memory)
+		//Return the semaphores + fences in a tuple
+		(image_available_semaphores, in_flight_fences)
 	}
 
-	//Create synchronization objects to deal with frames in flight + swapchain sync stuff
-	fn create_sync_objects(device: &ash::Device) -> (vk::Semaphore, vk::Semaphore, vk::Fence) {
+	//Makes a bunch of binary semaphores
+	fn create_semaphores(device: &ash::Device, count: usize) -> Vec<vk::Semaphore> {
 		let semaphore_info = vk::SemaphoreCreateInfo {
 			s_type: vk::StructureType::SEMAPHORE_CREATE_INFO,
 			p_next: ptr::null(),
@@ -1217,51 +1728,327 @@ impl VulkanApp {
 			..Default::default()
 		};
 
-		let fence_info = vk::FenceCreateInfo {
-			s_type: vk::StructureType::FENCE_CREATE_INFO,
-			p_next: ptr::null(),
-			flags: vk::FenceCreateFlags::SIGNALED, //Create the fence signaled to show that it's free
-			..Default::default()
-		};
+		(0..count)
+			.map(|_| unsafe { device.create_semaphore(&semaphore_info, None).expect("Failed to create semaphore") })
+			.collect()
+	}
 
-		//Create semaphores + fences
-		let image_available_semaphore = unsafe { device.create_semaphore(&semaphore_info, None).expect("Failed to create semaphore") };
-		let render_finished_semaphore = unsafe { device.create_semaphore(&semaphore_info, None).expect("Failed to create semaphore") };
-		let in_flight_fence = unsafe { device.create_fence(&fence_info, None).expect("Failed to create fence") };
+	//A little note - all of the above functions didn't use "self" because they were to be called in "init_vulkan." These next ones aren't, and pertain to when the event loop is running
 
-		//Return the semaphores + fences in a tuple
-		(image_available_semaphore, render_finished_semaphore, in_flight_fence)
+	//Starts uploading any meshes/textures in the scene that aren't on the GPU yet
+	//They only ever get added to the end of the scene's lists, so everything past the ones already uploaded is new. Cheap to call when there's nothing new
+	//Doesn't wait - the uploads go on the transfer queue, and objects start showing up once they land (untextured until their texture does)
+	pub fn upload_scene_assets(&mut self, scene: &Scene) {
+		for mesh_data in &scene.meshes[self.meshes.len()..] {
+			let mesh = Mesh::upload(&self.device, &mut self.allocator, &mut self.uploader, mesh_data);
+			self.meshes.push(mesh);
+			self.name_mesh(self.meshes.len() - 1);
+		}
+
+		//Each texture gets its own descriptor set pointing at its image view + sampler
+		for texture_data in &scene.textures[self.textures.len()..] {
+			let texture = Texture::upload(&self.instance, &self.device, &mut self.allocator, &mut self.uploader, self.physical_device, texture_data, self.max_sampler_anisotropy);
+			let descriptor_set = self.descriptor_allocator.allocate(&self.device, self.texture_set_layout);
+			write_combined_image_sampler_descriptor(&self.device, descriptor_set, 0, texture.image_view, texture.sampler);
+
+			self.textures.push(texture);
+			self.texture_descriptor_sets.push(descriptor_set);
+			self.name_texture(self.textures.len() - 1);
+		}
+
+		//Send off everything that just got queued up
+		self.uploader.submit(&self.device);
 	}
 
-	//A little note - all of the above functions didn't use "self" because they were to be called in "init_vulkan." These next ones aren't, and pertain to when the event loop is running
+	//GPU memory usage/fragmentation stats, for all memory together
+	pub fn memory_stats(&self) -> MemoryStats {
+		self.allocator.stats()
+	}
+
+	//The GPU memory stats as lines of text for the HUD - the total, then each memory type that's being used
+	pub fn memory_stats_lines(&self) -> Vec<String> {
+		let mut lines = vec![format!("GPU memory: {}", self.allocator.stats())];
+		for (memory_type_index, property_flags, stats) in self.allocator.memory_type_stats() {
+			lines.push(format!("Memory type {:?} ({:?}): {}", memory_type_index, property_flags, stats));
+		}
+		lines
+	}
+
+	//Gives every vulkan object a debug name, so validation messages + captures say what things are instead of just showing handles
+	//Gets called again whenever things get remade (pipelines, swapchain), so the new ones get names too. Meshes + textures get named as they're uploaded
+	fn name_objects(&self) {
+		let Some(object_namer) = &self.object_namer else { return };
+
+		object_namer.name(self.graphics_queue, "graphics queue");
+		object_namer.name(self.present_queue, "present queue");
+		object_namer.name(self.uploader.staging_buffer(), "upload staging ring");
+		object_namer.name(self.uploader.command_pool(), "upload command pool");
+
+		//What gets rendered to
+		if let Some(swapchain_req) = &self.swapchain_req {
+			object_namer.name(swapchain_req.swapchain, "swapchain");
+			for (image_index, &swapchain_image) in swapchain_req.swapchain_images.iter().enumerate() {
+				object_namer.name(swapchain_image, &format!("swapchain image {}", image_index));
+			}
+		}
+		if let Some(offscreen_target) = &self.offscreen_target {
+			object_namer.name(offscreen_target.image, "offscreen image");
+		}
+		for (image_index, (&render_image_view, &render_framebuffer)) in self.render_image_views.iter().zip(&self.render_framebuffers).enumerate() {
+			object_namer.name(render_image_view, &format!("render image view {}", image_index));
+			object_namer.name(render_framebuffer, &format!("framebuffer {}", image_index));
+		}
+		object_namer.name(self.depth_image, "depth image");
+		object_namer.name(self.depth_image_view, "depth image view");
+
+		//Pipelines + everything that goes into them
+		object_namer.name(self.render_pass, "render pass");
+		for (name, (pipeline, pipeline_layout)) in self.render_pipelines.all() {
+			object_namer.name(pipeline, &format!("{} pipeline", name));
+			object_namer.name(pipeline_layout, &format!("{} pipeline layout", name));
+		}
+		object_namer.name(self.pipeline_cache, "pipeline cache");
+		object_namer.name(self.frame_set_layout, "frame set layout");
+		object_namer.name(self.texture_set_layout, "texture set layout");
+
+		//Per frame slot stuff
+		for frame in 0..MAX_FRAMES_IN_FLIGHT {
+			object_namer.name(self.frame_uniform_buffers.buffer(frame), &format!("frame uniforms {}", frame));
+			object_namer.name(self.frame_descriptor_sets[frame], &format!("frame descriptor set {}", frame));
+			object_namer.name(self.command_buffers[frame], &format!("frame command buffer {}", frame));
+			object_namer.name(self.image_available_semaphores[frame], &format!("image available semaphore {}", frame));
+			object_namer.name(self.in_flight_fences[frame], &format!("in flight fence {}", frame));
+		}
+		for (image_index, &render_finished_semaphore) in self.render_finished_semaphores.iter().enumerate() {
+			object_namer.name(render_finished_semaphore, &format!("render finished semaphore {}", image_index));
+		}
+		object_namer.name(self.command_pool, "command pool");
+		object_namer.name(self.command_pool_short, "short lived command pool");
+
+		//Buffers that get rewritten every frame
+		object_namer.name(self.streaming_geometry.buffer(), "streaming geometry");
+		object_namer.name(self.streaming_instances.buffer(), "streaming instances");
+		object_namer.name(self.streaming_debug_lines.buffer(), "streaming debug lines");
+		object_namer.name(self.streaming_overlay.buffer(), "streaming overlay");
+
+		//Font atlas, then whatever the scene's uploaded so far
+		object_namer.name(self.font_texture.image, "font atlas");
+		object_namer.name(self.font_texture.image_view, "font atlas view");
+		object_namer.name(self.font_texture.sampler, "font atlas sampler");
+		object_namer.name(self.font_descriptor_set, "font atlas descriptor set");
+		if let Some(gpu_profiler) = &self.gpu_profiler {
+			object_namer.name(gpu_profiler.query_pool(), "GPU profiler timestamps");
+		}
+		for mesh_index in 0..self.meshes.len() {
+			self.name_mesh(mesh_index);
+		}
+		for texture_index in 0..self.textures.len() {
+			self.name_texture(texture_index);
+		}
+	}
+
+	//Names a mesh's buffers after its index in the scene
+	fn name_mesh(&self, mesh_index: usize) {
+		let Some(object_namer) = &self.object_namer else { return };
+		let mesh = &self.meshes[mesh_index];
+		object_namer.name(mesh.vertex_buffer, &format!("mesh {} vertices", mesh_index));
+		object_namer.name(mesh.index_buffer, &format!("mesh {} indices", mesh_index));
+	}
+
+	//Names a texture's image, view, sampler, and descriptor set after its index in the scene
+	fn name_texture(&self, texture_index: usize) {
+		let Some(object_namer) = &self.object_namer else { return };
+		let texture = &self.textures[texture_index];
+		object_namer.name(texture.image, &format!("texture {}", texture_index));
+		object_namer.name(texture.image_view, &format!("texture {} view", texture_index));
+		object_namer.name(texture.sampler, &format!("texture {} sampler", texture_index));
+		object_namer.name(self.texture_descriptor_sets[texture_index], &format!("texture {} descriptor set", texture_index));
+	}
+
+	//Labeled regions in a command buffer - they show up as groups in captures, and validation messages say which one they came from
+	fn cmd_begin_label(&self, command_buffer: vk::CommandBuffer, label: &str) {
+		if let Some(object_namer) = &self.object_namer {
+			object_namer.cmd_begin_label(command_buffer, label);
+		}
+	}
+	fn cmd_end_label(&self, command_buffer: vk::CommandBuffer) {
+		if let Some(object_namer) = &self.object_namer {
+			object_namer.cmd_end_label(command_buffer);
+		}
+	}
+
+	//Labeled regions that also get timed on the GPU. Every begin needs an end for the same scope, in the same command buffer
+	fn cmd_begin_scope(&self, command_buffer: vk::CommandBuffer, frame: usize, scope: GpuScope) {
+		self.cmd_begin_label(command_buffer, scope.name());
+		if let Some(gpu_profiler) = &self.gpu_profiler {
+			gpu_profiler.cmd_begin_scope(&self.device, command_buffer, frame, scope);
+		}
+	}
+	fn cmd_end_scope(&self, command_buffer: vk::CommandBuffer, frame: usize, scope: GpuScope) {
+		if let Some(gpu_profiler) = &self.gpu_profiler {
+			gpu_profiler.cmd_end_scope(&self.device, command_buffer, frame, scope);
+		}
+		self.cmd_end_label(command_buffer);
+	}
+
+	//Clears out the validation errors from the last frame (they've already been printed). With "PANIC_ON_VALIDATION_ERROR" on, panics if there were any
+	fn check_validation_errors(&self) {
+		let validation_errors = take_validation_errors();
+		if PANIC_ON_VALIDATION_ERROR && !validation_errors.is_empty() {
+			panic!("{} Vulkan validation error(s):\n{}", validation_errors.len(), validation_errors.join("\n"));
+		}
+	}
+
+	//Name of the GPU being used, like "NVIDIA GeForce RTX 3070"
+	pub fn device_name(&self) -> String {
+		let device_properties = unsafe { self.instance.get_physical_device_properties(self.physical_device) };
+		device_properties.device_name_as_c_str().map(|device_name| device_name.to_string_lossy().into_owned()).unwrap_or_default()
+	}
+
+	//Some info about the GPU being used (this is all debug style stuff, probably useful though), as lines of text for the HUD
+	pub fn device_info_lines(&self) -> Vec<String> {
+		let device_properties = unsafe { self.instance.get_physical_device_properties(self.physical_device) };
+		let queue_families = unsafe { self.instance.get_physical_device_queue_family_properties(self.physical_device) };
+
+		let api_version_major = vk::api_version_major(device_properties.api_version);
+		let api_version_minor = vk::api_version_minor(device_properties.api_version);
+		let api_version_patch = vk::api_version_patch(device_properties.api_version);
+		let queue_families_text: Vec<String> = queue_families
+			.iter()
+			.map(|family| format!("{:?}x {:?}", family.queue_count, family.queue_flags))
+			.collect();
+
+		vec![
+			format!("Device ID: {:?}, Type: {:?}", device_properties.device_id, device_properties.device_type),
+			format!("API version: {:?}.{:?}.{:?}", api_version_major, api_version_minor, api_version_patch),
+			format!("Queue families: {}", queue_families_text.join(", ")),
+		]
+	}
+
+	//Recompiles any shader sources that changed on disk, then rebuilds the pipeline with them. Does nothing unless hot reloading is on
+	//A shader that doesn't compile gets its error printed, and the pipeline keeps using the last version that did
+	pub fn reload_changed_shaders(&mut self) {
+		let Some(shader_watcher) = &mut self.shader_watcher else { return };
+		let changed_paths = shader_watcher.poll();
+
+		//Work on copies, so nothing changes unless the new shaders are good
+		let mut shader_code = self.shader_code.clone();
+		let mut rebuild_needed = false;
+		for path in changed_paths {
+			let compiled_code = match compile_glsl(&path) {
+				Ok(compiled_code) => compiled_code,
+				Err(error) => {
+					println!("Failed to compile {:?}, keeping the last good pipeline:\n{}", path, error);
+					continue
+				},
+			};
+
+			//Anything else in the folder isn't used by the pipelines
+			let file_name = path.file_name().and_then(|file_name| file_name.to_str()).unwrap_or_default();
+			if !shader_code.replace(file_name, compiled_code) {
+				continue
+			}
+			println!("Recompiled {:?}", path);
+			rebuild_needed = true;
+		}
+
+		if !rebuild_needed {
+			return
+		}
+		//Compiling fine doesn't mean they still line up with the vertex structs/set layouts
+		if let Err(problems) = shader_code.check() {
+			println!("Shaders don't match the pipeline, keeping the last good pipeline:\n{}", problems);
+			return
+		}
+		self.shader_code = shader_code;
+		self.rebuild_pipelines();
+	}
+
+	//Remakes the render mode pipelines (+ layouts) with the current shader code
+	//Waits for the GPU to go idle first, since frames in flight might still be using the old ones
+	fn rebuild_pipelines(&mut self) {
+		unsafe { self.device.device_wait_idle().expect("Failed to wait until device idle") };
+
+		let wireframe_supported = self.render_pipelines.wireframe.is_some();
+		let render_pipelines = RenderPipelines::new(&self.device, self.pipeline_cache, self.render_pass, self.render_extent, &[self.frame_set_layout, self.texture_set_layout], &self.shader_code, wireframe_supported);
+		self.render_pipelines.destroy(&self.device);
+		self.render_pipelines = render_pipelines;
+		self.name_objects();
+	}
+
+	//Switches to the next render mode, and returns a message saying which one it is now
+	pub fn cycle_render_mode(&mut self) -> String {
+		self.render_mode = self.render_mode.next();
+		match (self.render_mode, self.render_pipelines.wireframe) {
+			(RenderMode::Wireframe, None) => format!("Render mode: {:?} (not supported on this device - no fillModeNonSolid, so it's drawn filled)", self.render_mode),
+			_ => format!("Render mode: {:?}", self.render_mode),
+		}
+	}
+
+	//How many objects got drawn/culled in the last frame that was recorded
+	pub fn cull_stats(&self) -> CullStats {
+		self.cull_stats
+	}
+
+	//Average GPU time (ms) of each part of the frame, over the last few frames. Empty if timestamps aren't supported
+	//Lags a couple frames behind, since timestamps only get read once the GPU's done with them
+	pub fn gpu_timings(&self) -> Vec<(&'static str, f64)> {
+		self.gpu_profiler.as_ref().map(GpuProfiler::averages).unwrap_or_default()
+	}
 
 	//Draw a frame to the surface
 	//Because of how this is synchronized, this will draw start drawing the frame, then get the swapchain image, then finish drawing the frame to that swapchain image
 	//Need to pass in the window to get width/height, need to pass scene info
-	pub fn draw_frame(&self, window: &Window, scene: &Scene) {
+	//Returns the index of the swapchain image that got presented, or None if nothing was presented this time
+	//Up to MAX_FRAMES_IN_FLIGHT frames can be queued up on the GPU at once - this only blocks if the frame slot it's about to reuse is still executing
+	pub fn draw_frame(&mut self, window: &Window, scene: &Scene) -> Option<u32> {
 		//If the window is size 0, don't even deal with it
 		//Running into too many problems with keeping the command buffer extent + framebuffer extent + swapchain extent the same
 		if window.inner_size().width == 0 || window.inner_size().height == 0 {
-			return
+			return None
 		}
 
-		//Just have on frame in flight
-		let current_frame_fence_array = [self.in_flight_fence];
+		//A readback from an earlier frame that never got taken isn't wanted anymore
+		self.discard_swapchain_readback();
+
+		//Drawing a frame only makes sense with a swapchain to present to. Headless apps go through "render_offscreen"
+		let swapchain_req = self.swapchain_req.as_ref().expect("Can't draw a frame without a swapchain - use render_offscreen when headless");
 
-		//Only waiting for the fence for the current frame. With one frame in flight, this is all there is
-		unsafe { self.device.wait_for_fences(&current_frame_fence_array, true, std::u64::MAX).expect("Failed to wait for fence") }; //No timeout, set as the max u64
+		//Everything for the current frame slot
+		let current_frame = self.current_frame;
+		let command_buffer = self.command_buffers[current_frame];
+		let in_flight_fence = self.in_flight_fences[current_frame];
+		let current_frame_fence_array = [in_flight_fence];
+
+		//Wait for this slot's last frame to finish, so its command buffer + semaphore are free to reuse. The other slots can still be executing
+		unsafe { self.device.wait_for_fences(&current_frame_fence_array, true, u64::MAX).expect("Failed to wait for fence") }; //No timeout, set as the max u64
 
 		//Acquire next image from swapchain
-		//The command buffer will be queued on this image index, so will need to use the appropriate command buffer
-		let (image_index, is_suboptimal) = match unsafe { self.swapchain_loader.acquire_next_image(self.swapchain, std::u64::MAX, self.image_available_semaphore, vk::Fence::null())} {
+		//The command buffer will be queued on this image index, so will need to use the appropriate framebuffer
+		let (image_index, is_suboptimal) = match unsafe { swapchain_req.swapchain_loader.acquire_next_image(swapchain_req.swapchain, u64::MAX, self.image_available_semaphores[current_frame], vk::Fence::null())} {
 			Ok((image_index, is_suboptimal)) => (image_index, is_suboptimal),
-			Err(vk::Result::ERROR_OUT_OF_DATE_KHR) => return, //If the swapchain image is out of date, return out of this function without drawing the frame
+			Err(vk::Result::ERROR_OUT_OF_DATE_KHR) => return None, //If the swapchain image is out of date, return out of this function without drawing the frame
 			_ => panic!("Failed to acquire next swapchain image")
 		};
 
-		//Need the window's width and height to record the command buffer
-		unsafe { self.device.reset_command_buffer(self.command_buffers[0], vk::CommandBufferResetFlags::empty()).expect("Failed to reset command buffer"); } //Reset
-		self.record_command_buffer(window, scene, image_index as usize); //Record into the command buffers
+		//The swapchain can hand back an image that a different frame slot is still rendering into (images don't come back in slot order)
+		//If so, wait for that slot to finish with it first. Then mark the image as being used by this slot
+		let image_in_flight_fence = self.images_in_flight[image_index as usize];
+		if image_in_flight_fence != vk::Fence::null() && image_in_flight_fence != in_flight_fence {
+			unsafe { self.device.wait_for_fences(&[image_in_flight_fence], true, u64::MAX).expect("Failed to wait for fence") };
+		}
+		self.images_in_flight[image_index as usize] = in_flight_fence;
+
+		//If a readback was asked for (and the swapchain can be copied from), make the buffer for this frame's copy. "record_command_buffer" records the copy into it
+		if std::mem::take(&mut self.swapchain_readback_requested) && swapchain_req.readback_supported {
+			let extent = swapchain_req.swapchain_extent;
+			let (buffer, allocation) = create_readback_buffer(&self.device, &mut self.allocator, extent);
+			self.swapchain_readback = Some(SwapchainReadback {buffer, allocation, extent, fence: in_flight_fence});
+		}
+
+		//Rerecord this slot's command buffer for this swapchain image
+		unsafe { self.device.reset_command_buffer(command_buffer, vk::CommandBufferResetFlags::empty()).expect("Failed to reset command buffer"); } //Reset
+		self.record_command_buffer(scene, current_frame, image_index as usize); //Record into the command buffer
 		
 		//After waiting, have to reset the fence
 		//Delay resetting fence until we know acquire_next_image succeeded, in case of any weird behavior with resizing
@@ -1270,10 +2057,12 @@ impl VulkanApp {
 		//Setup semaphores into arrays to deal with queue submission
 		//Want to wait at the color attachment output stage - don't want to output any colors until the image to write to becomes available
 		//This allows vertex shader to be run while still waiting for a swapchain image
-		let wait_available_array = [self.image_available_semaphore];
+		let wait_available_array = [self.image_available_semaphores[current_frame]];
 		let wait_pipeline_stage = [vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT];
 		//This will be the semaphore to signal when the command buffers finish executing
-		let signal_finished_array = [self.render_finished_semaphore];
+		//It goes with the swapchain image rather than the frame slot - the presentation engine hangs on to it until the image gets presented, and there's no fence for that
+		//By the time an image gets acquired again, its last present is done waiting on the semaphore, so it's safe to reuse
+		let signal_finished_array = [self.render_finished_semaphores[image_index as usize]];
 
 		//Info for command buffer to be submitted to the queue
 		let submit_infos = [vk::SubmitInfo {
@@ -1283,7 +2072,7 @@ impl VulkanApp {
 			p_wait_semaphores: wait_available_array.as_ptr(), //Array of semaphores to wait at
 			p_wait_dst_stage_mask: wait_pipeline_stage.as_ptr(), //Array of pipeline stages that get waited at. These correspond to the p_wait_semaphores
 			command_buffer_count: 1, //Number of command buffers
-			p_command_buffers: &self.command_buffers[0], //Which command buffer to queue - just have the one frame in flight
+			p_command_buffers: &command_buffer, //Which command buffer to queue - the current frame slot's
 			signal_semaphore_count: signal_finished_array.len() as u32,
 			p_signal_semaphores: signal_finished_array.as_ptr(), //Semaphore to signal when the command buffers finish
 			..Default::default()
@@ -1291,10 +2080,16 @@ impl VulkanApp {
 
 		//Submit command buffer to queue
 		//Signals fence once the command buffers complete execution - can then reuse the command buffer
-		unsafe {self.device.queue_submit(self.graphics_queue, &submit_infos, self.in_flight_fence).expect("Failed to submit command buffer to queue") };
+		unsafe {self.device.queue_submit(self.graphics_queue, &submit_infos, in_flight_fence).expect("Failed to submit command buffer to queue") };
+		self.check_validation_errors();
+
+		//Move on to the next frame slot - whether or not presenting works out, this slot's fence is going to be signaled
+		self.current_frame = (current_frame + 1) % MAX_FRAMES_IN_FLIGHT;
 
 		//Need an array of the swapchains for the present info
-		let swapchains_array = [self.swapchain];
+		//(Borrowed again, since recording the command buffer needed self mutably)
+		let swapchain_req = self.swapchain_req.as_ref().expect("Can't draw a frame without a swapchain - use render_offscreen when headless");
+		let swapchains_array = [swapchain_req.swapchain];
 
 		//Presentation info with semaphores and swapchains and stuff
 		let present_info = vk::PresentInfoKHR {
@@ -1311,27 +2106,115 @@ impl VulkanApp {
 
 		//Queue an image for presentation
 		//If "ERROR_OUT_OF_DATE_KHR" error happens, it means the apphandler didn't handle the resize, so one of the window dimensions must be zero
-		match unsafe { self.swapchain_loader.queue_present(self.present_queue, &present_info) } {
-			Ok(_) => (),
-			Err(vk::Result::ERROR_OUT_OF_DATE_KHR) => (), //If the swapchain image is out of date, return out of this function without drawing the frame
+		match unsafe { swapchain_req.swapchain_loader.queue_present(self.present_queue, &present_info) } {
+			Ok(_) => Some(image_index),
+			Err(vk::Result::ERROR_OUT_OF_DATE_KHR) => None, //If the swapchain image is out of date, return out of this function without drawing the frame
 			_ => panic!("Failed to execute queue present")
 		}
 	}
 
-	//Will record during frame draw
-	//When only frame is in flight, it's probably faster to reuse one command buffer and just rerecord it for the appropiate swapchain image
+	//Has the next frame "draw_frame" draws get copied back to the CPU, to pick up with "take_swapchain_readback"
+	//Does nothing if the swapchain can't be copied from (check with "swapchain_readback_supported")
+	pub fn request_swapchain_readback(&mut self) {
+		self.swapchain_readback_requested = true;
+	}
+
+	//Whether swapchain images can be copied back at all (screenshots, frame captures). False when headless
+	pub fn swapchain_readback_supported(&self) -> bool {
+		self.swapchain_req.as_ref().is_some_and(|swapchain_req| swapchain_req.readback_supported)
+	}
+
+	//Gets the frame copied back after "request_swapchain_readback". Only waits for that frame to finish, not the whole device
+	//Returns the swapchain extent and the pixels as rgba bytes (swizzled if the swapchain is bgra), or None if no frame has been copied back
+	//Has to be called before the next "draw_frame", otherwise the copy gets thrown out
+	pub fn take_swapchain_readback(&mut self) -> Option<(vk::Extent2D, Vec<u8>)> {
+		let swapchain_readback = self.swapchain_readback.take()?;
+		unsafe { self.device.wait_for_fences(&[swapchain_readback.fence], true, u64::MAX).expect("Failed to wait for fence") };
+
+		let mut pixels = read_readback_buffer(&swapchain_readback.allocation, swapchain_readback.extent);
+		unsafe { self.device.destroy_buffer(swapchain_readback.buffer, None) };
+		self.allocator.free(&self.device, &swapchain_readback.allocation);
+
+		//Swapchain might be bgra depending on the platform - swap red and blue so it always comes out rgba
+		let swapchain_req = self.swapchain_req.as_ref().expect("Can't read a swapchain image without a swapchain");
+		if matches!(swapchain_req.swapchain_format.format, vk::Format::B8G8R8A8_SRGB | vk::Format::B8G8R8A8_UNORM) {
+			for pixel in pixels.chunks_exact_mut(4) {
+				pixel.swap(0, 2);
+			}
+		}
+
+		Some((swapchain_readback.extent, pixels))
+	}
+
+	//Throws out a readback that hasn't been taken, once the GPU's done copying into it
+	fn discard_swapchain_readback(&mut self) {
+		if let Some(swapchain_readback) = self.swapchain_readback.take() {
+			unsafe { self.device.wait_for_fences(&[swapchain_readback.fence], true, u64::MAX).expect("Failed to wait for fence") };
+			unsafe { self.device.destroy_buffer(swapchain_readback.buffer, None) };
+			self.allocator.free(&self.device, &swapchain_readback.allocation);
+		}
+	}
+
+	//Headless version of "draw_frame" - renders the scene into the offscreen image, then reads the pixels back to the CPU
+	//No semaphores needed since there's no swapchain image to wait on or present, just wait on the fence until the render is done
+	//The render gets waited on right away, so only the first frame slot ever gets used
+	//Returns the image as tightly packed rgba bytes (OFFSCREEN_FORMAT), row by row from the top left
+	pub fn render_offscreen(&mut self, scene: &Scene) -> Vec<u8> {
+		let offscreen_target = self.offscreen_target.as_ref().expect("Can't render offscreen without an offscreen target - use init_headless");
+		let (offscreen_image, offscreen_extent) = (offscreen_target.image, offscreen_target.extent);
+		let command_buffer = self.command_buffers[0];
+		let in_flight_fence = self.in_flight_fences[0];
+		let current_frame_fence_array = [in_flight_fence];
+
+		//Wait for the last render to finish, then reset + rerecord the command buffer for the offscreen framebuffer (there's only the one)
+		unsafe { self.device.wait_for_fences(&current_frame_fence_array, true, u64::MAX).expect("Failed to wait for fence") };
+		unsafe { self.device.reset_fences(&current_frame_fence_array).expect("Failed to reset fences") };
+		unsafe { self.device.reset_command_buffer(command_buffer, vk::CommandBufferResetFlags::empty()).expect("Failed to reset command buffer") };
+		self.record_command_buffer(scene, 0, 0);
+
+		//Submit with nothing to wait on or signal except the fence
+		let submit_infos = [vk::SubmitInfo {
+			s_type: vk::StructureType::SUBMIT_INFO,
+			p_next: ptr::null(),
+			wait_semaphore_count: 0,
+			p_wait_semaphores: ptr::null(),
+			p_wait_dst_stage_mask: ptr::null(),
+			command_buffer_count: 1,
+			p_command_buffers: &command_buffer,
+			signal_semaphore_count: 0,
+			p_signal_semaphores: ptr::null(),
+			..Default::default()
+		}];
+		unsafe { self.device.queue_submit(self.graphics_queue, &submit_infos, in_flight_fence).expect("Failed to submit command buffer to queue") };
+		unsafe { self.device.wait_for_fences(&current_frame_fence_array, true, u64::MAX).expect("Failed to wait for fence") };
+
+		//Render pass left the image in the transfer src layout, copy it out
+		let pixels = read_image(&self.device, &mut self.allocator, self.command_pool_short, self.graphics_queue, offscreen_image, vk::ImageLayout::TRANSFER_SRC_OPTIMAL, offscreen_extent);
+		self.check_validation_errors();
+		pixels
+	}
+
+	//Descriptor set for a scene texture, or the white texture's if it's still uploading
+	fn ready_texture_descriptor_set(&self, texture_index: usize) -> vk::DescriptorSet {
+		let texture = self.textures.get(texture_index).expect("Scene object's texture hasn't been uploaded - call upload_scene_assets after adding textures");
+		match self.uploader.is_collected(texture.upload_batch) {
+			true => self.texture_descriptor_sets[texture_index],
+			false => self.texture_descriptor_sets[WHITE_TEXTURE],
+		}
+	}
+
+	//Will record during frame draw, into the frame slot's command buffer. Also fills in the frame slot's uniform buffer
+	//The frame slot's fence has to have been waited on already, since the command buffer + uniform buffer get overwritten
 	//Frames in flight are only there to give CPU something to do while GPU chugs away, but they increase lag by letting the CPU game physics go farther ahead than the rendering
-	fn record_command_buffer(&self, window: &Window, scene: &Scene, image_index: usize) {
+	fn record_command_buffer(&mut self, scene: &Scene, frame: usize, image_index: usize) {
 		//First, setup everything needed with in VulkanApp (there's a bunch)
 		let device = &self.device;
-		let command_buffer = self.command_buffers[0];
+		let command_buffer = self.command_buffers[frame];
 		let render_pass = self.render_pass;
-		let pipeline = self.pipeline;
-		let pipeline_layout = self.pipeline_layout;
-		let framebuffer = self.swapchain_framebuffers[image_index];
-		let vertex_buffer = self.vertex_buffer;
-		let window_width = window.inner_size().width;
-		let window_height = window.inner_size().height;
+		let (pipeline, pipeline_layout) = self.render_pipelines.for_mode(self.render_mode);
+		let framebuffer = self.render_framebuffers[image_index];
+		let window_width = self.render_extent.width;
+		let window_height = self.render_extent.height;
 
 		//Start with the command buffer begin info
 		let command_buffer_begin_info = vk::CommandBufferBeginInfo {
@@ -1346,12 +2229,37 @@ impl VulkanApp {
 		//Remember - the commands submitted to the buffer will NOT necessarily go in order
 		unsafe { device.begin_command_buffer(command_buffer, &command_buffer_begin_info).expect("Failed to begin recording to command buffer") };
 
+		//Read the timestamps this frame slot wrote last time around (its fence has been waited on, so they're done), then reset them to be written again
+		//Resetting has to happen outside the render pass, before any scopes
+		if let Some(gpu_profiler) = &mut self.gpu_profiler {
+			gpu_profiler.collect(device, frame);
+			gpu_profiler.cmd_reset(device, command_buffer, frame);
+		}
+		self.cmd_begin_scope(command_buffer, frame, GpuScope::Frame);
+
+		//Take ownership of any uploads that finished since last frame, then make the mips for any textures that came in
+		//Has to happen outside the render pass. Everything drawn after this can use them
+		self.cmd_begin_scope(command_buffer, frame, GpuScope::Uploads);
+		let previously_collected = self.uploader.collected_serial();
+		for finished_batch in self.uploader.collect_finished(device, &mut self.allocator) {
+			self.uploader.cmd_acquire(device, command_buffer, &finished_batch.acquires);
+		}
+		for texture in self.textures.iter().chain([&self.font_texture]).filter(|texture| texture.upload_batch > previously_collected && self.uploader.is_collected(texture.upload_batch)) {
+			texture.cmd_generate_mips(device, command_buffer);
+		}
+		self.cmd_end_scope(command_buffer, frame, GpuScope::Uploads);
+
 		//Set the values to clear to, this will be passed to "render_pass_begin_info"
 		//An array containing clear values for each framebufffer attachment that has a loap_op (as defined in the render pass) with clearing
 		//This is a rust union, so it's defined using one field
-		let clear_values = [vk::ClearValue {
-			color: vk::ClearColorValue {float32: [0.0, 0.0, 0.0, 1.0]}, //Black at 100% opacity
-		}];
+		let clear_values = [
+			vk::ClearValue {
+				color: vk::ClearColorValue {float32: [0.0, 0.0, 0.0, 1.0]}, //Black at 100% opacity
+			},
+			vk::ClearValue {
+				depth_stencil: vk::ClearDepthStencilValue {depth: 1.0, stencil: 0}, //Far plane is depth 1.0, so everything will be in front of it
+			},
+		];
 
 		//Render pass begin info
 		let render_pass_begin_info = vk::RenderPassBeginInfo {
@@ -1370,23 +2278,17 @@ impl VulkanApp {
 
 		//Command to begin the render pass
 		//There's a begin_render_pass2, but it only adds a s_type and p_next to the SubpassContents
+		self.cmd_begin_scope(command_buffer, frame, GpuScope::RenderPass);
 		unsafe { device.cmd_begin_render_pass(command_buffer, &render_pass_begin_info, vk::SubpassContents::INLINE) }; //Inline: subpass commands will be in primary command buffer, no secondary command buffers
 
 		//Bind the pipeline to the render pass
 		//Pipeline bind point is graphics - not using compute
 		//Dynamic states would be set here, if they were set up in "create_pipeline" fn
 		unsafe { device.cmd_bind_pipeline(command_buffer, vk::PipelineBindPoint::GRAPHICS, pipeline) }; //Specified as graphics pipeline, same as specification in render pass subpass
-
-		//Bind the vertex buffer
-		let vertex_buffers = [vertex_buffer];
-		let offsets = [0];
-		unsafe { device.cmd_bind_vertex_buffers(command_buffer, 0, &vertex_buffers, &offsets) };
-
-		//Calculate the matrix to push to the shaders
-		//Need to make sure alignment rules are held to - since this is just a single Mat4 of 128 bytes
-		let render_matrix_bytes = scene.get_render_matrix_bytes();
-		//Push the matrix as a push constant
-		unsafe { device.cmd_push_constants(command_buffer, pipeline_layout, vk::ShaderStageFlags::VERTEX, 0, &render_matrix_bytes) };
+		//The debug views need to know which one to show
+		if let Some(debug_view) = self.render_mode.debug_view() {
+			unsafe { device.cmd_push_constants(command_buffer, pipeline_layout, vk::ShaderStageFlags::FRAGMENT, 0, &debug_view.to_le_bytes()) };
+		}
 
 		//Setup the viewport
 		let viewports = [vk::Viewport {
@@ -1408,14 +2310,143 @@ impl VulkanApp {
 		//Set the scissors
 		unsafe { device.cmd_set_scissor(command_buffer, 0, &scissors); }
 
-		//Draw command
-		unsafe { device.cmd_draw(command_buffer, TEST_TRIANGLE_VERTICES.len() as u32, 1, 0, 0) }; //Specify number of vertices, number of instances, vertex offset, instance offset
+		//Update this frame's uniforms, then bind the frame descriptor set (set 0)
+		self.frame_uniform_buffers.write(frame, &scene.get_frame_uniforms());
+		unsafe { device.cmd_bind_descriptor_sets(command_buffer, vk::PipelineBindPoint::GRAPHICS, pipeline_layout, 0, &[self.frame_descriptor_sets[frame]], &[]) };
+
+		//Throw out everything outside the camera's view before drawing anything
+		let frustum = scene.camera.get_frustum();
+		let mut cull_stats = CullStats::default();
+
+		//Draw the static objects - one instanced draw for every mesh + texture combo, with all the copies' matrices/tints in the instance buffer
+		//The slot's fence has been waited on, so the GPU is done with whatever was in the streaming buffers' regions from last time
+		self.cmd_begin_scope(command_buffer, frame, GpuScope::StaticObjects);
+		self.streaming_instances.begin_frame(frame);
+		for batch in scene.get_instance_batches(&frustum, &mut cull_stats) {
+			//Skip anything whose mesh is still on its way
+			let mesh = self.meshes.get(batch.mesh).expect("Scene object's mesh hasn't been uploaded - call upload_scene_assets after adding meshes");
+			if !self.uploader.is_collected(mesh.upload_batch) {
+				continue
+			}
+			//Out of room this frame - skip it rather than stalling to make more
+			let Some(instance_offset) = self.streaming_instances.push(&batch.instances) else { continue };
+
+			//Bind the batch's texture (set 1) and instance data (binding 1)
+			unsafe {
+				device.cmd_bind_descriptor_sets(command_buffer, vk::PipelineBindPoint::GRAPHICS, pipeline_layout, 1, &[self.ready_texture_descriptor_set(batch.texture)], &[]);
+				device.cmd_bind_vertex_buffers(command_buffer, 1, &[self.streaming_instances.buffer()], &[instance_offset]);
+			}
+
+			//Bind the mesh's vertex/index buffers and draw every copy
+			mesh.cmd_draw(device, command_buffer, batch.instances.len() as u32);
+		}
+
+		self.cmd_end_scope(command_buffer, frame, GpuScope::StaticObjects);
+
+		//Now the dynamic objects - write their geometry into this frame slot's region of the streaming buffer and draw it from there
+		self.cmd_begin_scope(command_buffer, frame, GpuScope::DynamicObjects);
+		self.streaming_geometry.begin_frame(frame);
+		for object in scene.get_visible_dynamic_objects(&frustum, &mut cull_stats) {
+			if object.mesh_data.indices.is_empty() {
+				continue
+			}
+			//Out of room this frame - skip it rather than stalling to make more
+			let Some(vertex_offset) = self.streaming_geometry.push(&object.mesh_data.vertices) else { continue };
+			let Some(index_offset) = self.streaming_geometry.push(&object.mesh_data.indices) else { continue };
+			//Each one's a batch of one instance
+			let Some(instance_offset) = self.streaming_instances.push(&[scene.get_instance_data(object.transform, object.tint)]) else { continue };
+
+			//Same texture + instance data as static objects
+			unsafe { device.cmd_bind_descriptor_sets(command_buffer, vk::PipelineBindPoint::GRAPHICS, pipeline_layout, 1, &[self.ready_texture_descriptor_set(object.texture)], &[]) };
+
+			//Vertices + indices are both in the streaming buffer, just at different offsets
+			unsafe {
+				device.cmd_bind_vertex_buffers(command_buffer, 0, &[self.streaming_geometry.buffer()], &[vertex_offset]);
+				device.cmd_bind_vertex_buffers(command_buffer, 1, &[self.streaming_instances.buffer()], &[instance_offset]);
+				device.cmd_bind_index_buffer(command_buffer, self.streaming_geometry.buffer(), index_offset, vk::IndexType::UINT32);
+				device.cmd_draw_indexed(command_buffer, object.mesh_data.indices.len() as u32, 1, 0, 0, 0);
+			}
+		}
+
+		self.cmd_end_scope(command_buffer, frame, GpuScope::DynamicObjects);
+		self.cull_stats = cull_stats;
+
+		//Debug lines last, on top of everything (the line pipeline doesn't depth test)
+		self.cmd_begin_scope(command_buffer, frame, GpuScope::DebugLines);
+		self.streaming_debug_lines.begin_frame(frame);
+		let mut debug_vertices = scene.debug_draw.vertices();
+		//Drop whatever doesn't fit rather than skipping them all - keeps whole lines (pairs of vertices)
+		let max_debug_vertices = (DEBUG_LINE_REGION_SIZE as usize / size_of::<DebugVertex>()) & !1;
+		debug_vertices.truncate(max_debug_vertices);
+		if let Some(vertex_offset) = self.streaming_debug_lines.push(&debug_vertices).filter(|_| !debug_vertices.is_empty()) {
+			let (line_pipeline, line_pipeline_layout) = self.render_pipelines.debug_lines;
+			unsafe {
+				device.cmd_bind_pipeline(command_buffer, vk::PipelineBindPoint::GRAPHICS, line_pipeline);
+				//Different layout (no push constants), so set 0 has to be bound again
+				device.cmd_bind_descriptor_sets(command_buffer, vk::PipelineBindPoint::GRAPHICS, line_pipeline_layout, 0, &[self.frame_descriptor_sets[frame]], &[]);
+				device.cmd_bind_vertex_buffers(command_buffer, 0, &[self.streaming_debug_lines.buffer()], &[vertex_offset]);
+				device.cmd_draw(command_buffer, debug_vertices.len() as u32, 1, 0, 0);
+			}
+		}
+
+		self.cmd_end_scope(command_buffer, frame, GpuScope::DebugLines);
+
+		//HUD text over all of that
+		if scene.hud.visible {
+			self.cmd_begin_scope(command_buffer, frame, GpuScope::Overlay);
+			self.record_overlay(command_buffer, &scene.get_hud_lines(), frame);
+			self.cmd_end_scope(command_buffer, frame, GpuScope::Overlay);
+		}
 
 		//Command to end the render pass
-		unsafe { device.cmd_end_render_pass(command_buffer)};
+		unsafe { self.device.cmd_end_render_pass(command_buffer)};
+		self.cmd_end_scope(command_buffer, frame, GpuScope::RenderPass);
+
+		//Copy the image out if a readback was asked for. The render pass left it in the present layout, and it's still ours until it gets presented after this command buffer
+		if let (Some(swapchain_readback), Some(swapchain_req)) = (&self.swapchain_readback, &self.swapchain_req) {
+			cmd_copy_image_to_readback_buffer(&self.device, command_buffer, swapchain_req.swapchain_images[image_index], vk::ImageLayout::PRESENT_SRC_KHR, swapchain_readback.extent, swapchain_readback.buffer);
+		}
+		self.cmd_end_scope(command_buffer, frame, GpuScope::Frame);
 
 		//End command buffer recording
-		unsafe { device.end_command_buffer(command_buffer).expect("Failed to record to command buffer") };
+		unsafe { self.device.end_command_buffer(command_buffer).expect("Failed to record to command buffer") };
+	}
+
+	//Records the overlay - lines of text down from the top left corner of the screen, each with its own color
+	//Has to be inside the render pass. Draws nothing until the font atlas has finished uploading
+	fn record_overlay(&mut self, command_buffer: vk::CommandBuffer, lines: &[(String, Vec4)], frame: usize) {
+		if !self.uploader.is_collected(self.font_texture.upload_batch) {
+			return
+		}
+		let device = &self.device;
+
+		let line_height = GLYPH_SIZE as f32 * HUD_TEXT_SCALE;
+		let mut vertices: Vec<OverlayVertex> = lines
+			.iter()
+			.enumerate()
+			.flat_map(|(line_index, (text, color))| text_vertices(text, vec2(HUD_MARGIN, HUD_MARGIN + line_index as f32 * line_height), HUD_TEXT_SCALE, *color))
+			.collect();
+		//Drop whatever doesn't fit, keeping whole quads (6 vertices each)
+		let max_vertices = (OVERLAY_REGION_SIZE as usize / size_of::<OverlayVertex>()) / 6 * 6;
+		vertices.truncate(max_vertices);
+		if vertices.is_empty() {
+			return
+		}
+
+		self.streaming_overlay.begin_frame(frame);
+		let Some(vertex_offset) = self.streaming_overlay.push(&vertices) else { return };
+
+		//Screen size goes in as a push constant, for turning pixels into clip space
+		let (overlay_pipeline, overlay_pipeline_layout) = self.render_pipelines.overlay;
+		let screen_size = [self.render_extent.width as f32, self.render_extent.height as f32];
+		let screen_size_bytes: Vec<u8> = screen_size.iter().flat_map(|size| size.to_le_bytes()).collect();
+		unsafe {
+			device.cmd_bind_pipeline(command_buffer, vk::PipelineBindPoint::GRAPHICS, overlay_pipeline);
+			device.cmd_push_constants(command_buffer, overlay_pipeline_layout, vk::ShaderStageFlags::VERTEX, 0, &screen_size_bytes);
+			device.cmd_bind_descriptor_sets(command_buffer, vk::PipelineBindPoint::GRAPHICS, overlay_pipeline_layout, 1, &[self.font_descriptor_set], &[]);
+			device.cmd_bind_vertex_buffers(command_buffer, 0, &[self.streaming_overlay.buffer()], &[vertex_offset]);
+			device.cmd_draw(command_buffer, vertices.len() as u32, 1, 0, 0);
+		}
 	}
 
 	//Function to call on a window resize event
@@ -1428,16 +2459,26 @@ impl VulkanApp {
 		//Destroy the stuff that'll be replaced
 		//Need to free the command buffers - not destroying the command pool, so need to go directly to command buffers for this
 		unsafe {
-			for framebuffer in &self.swapchain_framebuffers {
+			for framebuffer in &self.render_framebuffers {
 				self.device.destroy_framebuffer(*framebuffer, None);
 			}
 
-			for swapchain_image_view in &self.swapchain_image_views {
-				self.device.destroy_image_view(*swapchain_image_view, None);
+			for render_image_view in &self.render_image_views {
+				self.device.destroy_image_view(*render_image_view, None);
+			}
+			if let Some(swapchain_req) = &self.swapchain_req {
+				swapchain_req.swapchain_loader.destroy_swapchain(swapchain_req.swapchain, None);
 			}
-			self.swapchain_loader.destroy_swapchain(self.swapchain, None);
+			self.destroy_depth_resources();
 
-			self.surface_loader.destroy_surface(self.surface, None);
+			if let Some(surface_req) = &self.surface_req {
+				surface_req.surface_loader.destroy_surface(surface_req.surface, None);
+			}
+
+			//Image count might change with the new swapchain
+			for render_finished_semaphore in &self.render_finished_semaphores {
+				self.device.destroy_semaphore(*render_finished_semaphore, None);
+			}
 		}
 
 		//Get window width + height being rendered to
@@ -1449,27 +2490,40 @@ impl VulkanApp {
 		//Need to pass in the new window dimensions
 		let surface_req = VulkanApp::create_surface(&self.entry, &self.instance, window);
 		//Also need queue family indices
-		let queue_family_indices = QueueFamilyIndices::find_queue_families(&self.instance, self.physical_device, &surface_req);
+		let queue_family_indices = QueueFamilyIndices::find_queue_families(&self.instance, self.physical_device, Some(&surface_req));
 
 		//Now, recreate swapchain based on new surface
 		let swapchain_req = VulkanApp::create_swapchain(&self.instance, &self.device, self.physical_device, &surface_req, &queue_family_indices, window_width, window_height);
 		//Recreate the image views
-		let swapchain_image_views = VulkanApp::create_image_views(&self.device, swapchain_req.swapchain_format, &swapchain_req.swapchain_images);
+		let render_image_views = VulkanApp::create_image_views(&self.device, swapchain_req.swapchain_format.format, &swapchain_req.swapchain_images);
+		//Recreate the depth image at the new size
+		let (depth_image, depth_image_allocation, depth_image_view) = VulkanApp::create_depth_resources(&self.device, &mut self.allocator, self.depth_format, swapchain_req.swapchain_extent);
 		//Recreate the framebuffers that contain the image views for the swapchain images as attachments
-		let swapchain_framebuffers = VulkanApp::create_framebuffers(&self.device, self.render_pass, &swapchain_image_views, swapchain_req.swapchain_extent);
+		let render_framebuffers = VulkanApp::create_framebuffers(&self.device, self.render_pass, &render_image_views, depth_image_view, swapchain_req.swapchain_extent);
+
+		//Per image sync stuff for the new swapchain images. Device is idle, so nothing is in flight
+		let render_finished_semaphores = VulkanApp::create_semaphores(&self.device, render_image_views.len());
+		let images_in_flight = vec![vk::Fence::null(); render_image_views.len()];
 
 		//NOT going to recreate the render pass. Theoretically, this might cause problems is window is moved to like an HDR monitor. WHATEVER!
 		//Also not recreating pipeline
-		//And not recreating command buffer, since it's recorded into during frame draw
+		//And not recreating command buffers, since they're recorded into during frame draw
 
 		//Update everything in VulkanApp that needs to be updated
-		self.surface = surface_req.surface;
-		self.surface_loader = surface_req.surface_loader;
+		self.render_extent = swapchain_req.swapchain_extent;
+		self.render_image_views = render_image_views;
+		self.render_framebuffers = render_framebuffers;
+
+		self.depth_image = depth_image;
+		self.depth_image_allocation = depth_image_allocation;
+		self.depth_image_view = depth_image_view;
+
+		self.render_finished_semaphores = render_finished_semaphores;
+		self.images_in_flight = images_in_flight;
 
-		self.swapchain = swapchain_req.swapchain;
-		self.swapchain_loader = swapchain_req.swapchain_loader;
-		self.swapchain_image_views = swapchain_image_views;
-		self.swapchain_framebuffers = swapchain_framebuffers;
+		self.surface_req = Some(surface_req);
+		self.swapchain_req = Some(swapchain_req);
+		self.name_objects();
 	}
 }
 
@@ -1479,34 +2533,72 @@ impl Drop for VulkanApp {
 		//Wait until nothing is in use - make sure drawing/presenting isn't happening
 		//This was being called cpu side before exiting the app, but then things weren't waiting if "x" was hit from the taskbar (some kind of semi-force quit??)
 		unsafe { self.device.device_wait_idle().expect("Failed to wait until device idle") }
+		self.discard_swapchain_readback();
 
 		//Destroy everything
 		unsafe {
-			self.device.destroy_semaphore(self.image_available_semaphore, None);
-			self.device.destroy_semaphore(self.render_finished_semaphore, None);
-			self.device.destroy_fence(self.in_flight_fence, None);
+			for &semaphore in self.image_available_semaphores.iter().chain(&self.render_finished_semaphores) {
+				self.device.destroy_semaphore(semaphore, None);
+			}
+			for &fence in &self.in_flight_fences {
+				self.device.destroy_fence(fence, None);
+			}
 
-			self.device.destroy_buffer(self.vertex_buffer, None);
-			self.device.free_memory(self.vertex_buffer_memory, None);
+			for mesh in &self.meshes {
+				mesh.destroy(&self.device, &mut self.allocator);
+			}
+			for texture in &self.textures {
+				texture.destroy(&self.device, &mut self.allocator);
+			}
+			self.streaming_geometry.destroy(&self.device, &mut self.allocator);
+			self.streaming_instances.destroy(&self.device, &mut self.allocator);
+			self.streaming_debug_lines.destroy(&self.device, &mut self.allocator);
+			self.streaming_overlay.destroy(&self.device, &mut self.allocator);
+			self.font_texture.destroy(&self.device, &mut self.allocator);
+			self.uploader.destroy(&self.device, &mut self.allocator);
+			if let Some(gpu_profiler) = &self.gpu_profiler {
+				gpu_profiler.destroy(&self.device);
+			}
 
 			self.device.destroy_command_pool(self.command_pool, None);
 			self.device.destroy_command_pool(self.command_pool_short, None);
-			for framebuffer in &self.swapchain_framebuffers {
+			for framebuffer in &self.render_framebuffers {
 				self.device.destroy_framebuffer(*framebuffer, None);
 			}
 
-			self.device.destroy_pipeline(self.pipeline, None);
-			self.device.destroy_pipeline_layout(self.pipeline_layout, None);
+			self.render_pipelines.destroy(&self.device);
+			//Save the pipeline cache for next time before getting rid of it
+			save_pipeline_cache(&self.device, self.pipeline_cache);
+			self.device.destroy_pipeline_cache(self.pipeline_cache, None);
+
+			self.descriptor_allocator.destroy(&self.device);
+			self.device.destroy_descriptor_set_layout(self.frame_set_layout, None);
+			self.device.destroy_descriptor_set_layout(self.texture_set_layout, None);
+			self.frame_uniform_buffers.destroy(&self.device, &mut self.allocator);
 
 			self.device.destroy_render_pass(self.render_pass, None);
 
-			for swapchain_image_view in &self.swapchain_image_views {
-				self.device.destroy_image_view(*swapchain_image_view, None);
+			for render_image_view in &self.render_image_views {
+				self.device.destroy_image_view(*render_image_view, None);
+			}
+			self.destroy_depth_resources();
+			if let Some(swapchain_req) = &self.swapchain_req {
+				swapchain_req.swapchain_loader.destroy_swapchain(swapchain_req.swapchain, None);
 			}
-			self.swapchain_loader.destroy_swapchain(self.swapchain, None);
+			if let Some(offscreen_target) = &self.offscreen_target {
+				offscreen_target.destroy(&self.device, &mut self.allocator);
+			}
+
+			//Everything's been given back to the allocator by now, so it can free its blocks
+			self.allocator.destroy(&self.device);
 
 			self.device.destroy_device(None);
-			self.surface_loader.destroy_surface(self.surface, None);
+			if let Some(surface_req) = &self.surface_req {
+				surface_req.surface_loader.destroy_surface(surface_req.surface, None);
+			}
+			if let Some(debug_messenger) = &self.debug_messenger {
+				debug_messenger.destroy();
+			}
 			self.instance.destroy_instance(None);
 		}
 	}
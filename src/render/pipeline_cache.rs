@@ -0,0 +1,186 @@
+use crate::constants::PIPELINE_CACHE_DIR;
+
+use ash::vk;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::ptr;
+
+//Pipeline cache that sticks around between runs
+//Drivers compile shaders down to GPU code when pipelines get made, which is slow. The cache holds onto that compiled code, so it only happens the first time
+//Gets loaded at startup, and saved back to disk when the app shuts down
+
+
+const PIPELINE_CACHE_FILE: &str = "pipeline_cache.bin";
+
+//Every pipeline cache starts with this header (the spec's "VkPipelineCacheHeaderVersionOne"):
+//	u32 header size, u32 header version, u32 vendor id, u32 device id, [u8; 16] pipeline cache uuid
+const PIPELINE_CACHE_HEADER_SIZE: usize = 32;
+
+fn pipeline_cache_path() -> PathBuf {
+	Path::new(PIPELINE_CACHE_DIR).join(PIPELINE_CACHE_FILE)
+}
+
+//Makes the pipeline cache, starting from whatever was saved last run
+//The saved data only gets used if it came from the same GPU + driver (checked with the header). Otherwise, or if there's no file, it starts empty
+pub fn load_pipeline_cache(instance: &ash::Instance, device: &ash::Device, physical_device: vk::PhysicalDevice) -> vk::PipelineCache {
+	let properties = unsafe { instance.get_physical_device_properties(physical_device) };
+	let path = pipeline_cache_path();
+
+	let initial_data = match fs::read(&path) {
+		Ok(data) => match check_pipeline_cache_header(&data, &properties) {
+			Ok(()) => data,
+			Err(reason) => {
+				println!("Not using pipeline cache {:?}: {}", path, reason);
+				vec![]
+			},
+		},
+		Err(_) => vec![], //No cache yet - first run, or it got deleted
+	};
+
+	let pipeline_cache_info = vk::PipelineCacheCreateInfo {
+		s_type: vk::StructureType::PIPELINE_CACHE_CREATE_INFO,
+		p_next: ptr::null(),
+		flags: vk::PipelineCacheCreateFlags::empty(),
+		initial_data_size: initial_data.len(),
+		p_initial_data: initial_data.as_ptr() as *const std::ffi::c_void,
+		..Default::default()
+	};
+
+	unsafe { device.create_pipeline_cache(&pipeline_cache_info, None).expect("Failed to create pipeline cache") }
+}
+
+//Writes the pipeline cache out to disk so the next run can start with it
+//Failing to save isn't worth crashing over (this runs on shutdown), so problems just get printed
+pub fn save_pipeline_cache(device: &ash::Device, pipeline_cache: vk::PipelineCache) {
+	let data = match unsafe { device.get_pipeline_cache_data(pipeline_cache) } {
+		Ok(data) => data,
+		Err(error) => {
+			println!("Failed to get pipeline cache data: {:?}", error);
+			return
+		},
+	};
+
+	//Write to a temp file then swap it in, so a crash halfway through can't leave a broken cache behind
+	let path = pipeline_cache_path();
+	let temp_path = path.with_extension("tmp");
+	let result = fs::create_dir_all(PIPELINE_CACHE_DIR)
+		.and_then(|_| fs::write(&temp_path, &data))
+		.and_then(|_| fs::rename(&temp_path, &path));
+	if let Err(error) = result {
+		println!("Failed to save pipeline cache to {:?}: {}", path, error);
+	}
+}
+
+//Makes sure saved cache data was made by this GPU + driver. Drivers are supposed to ignore data that isn't theirs, but not all of them are careful about it
+fn check_pipeline_cache_header(data: &[u8], properties: &vk::PhysicalDeviceProperties) -> Result<(), String> {
+	if data.len() < PIPELINE_CACHE_HEADER_SIZE {
+		return Err(format!("only {} bytes, too small for a header", data.len()))
+	}
+	let read_u32 = |offset: usize| u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap());
+
+	let header_size = read_u32(0) as usize;
+	let header_version = read_u32(4);
+	let vendor_id = read_u32(8);
+	let device_id = read_u32(12);
+	let pipeline_cache_uuid = &data[16..32];
+
+	if header_size < PIPELINE_CACHE_HEADER_SIZE || header_size > data.len() {
+		Err(format!("bad header size {}", header_size))
+	} else if header_version != vk::PipelineCacheHeaderVersion::ONE.as_raw() as u32 {
+		Err(format!("unknown header version {}", header_version))
+	} else if vendor_id != properties.vendor_id || device_id != properties.device_id {
+		Err(format!("made on a different GPU (vendor {:#x} device {:#x}, this is vendor {:#x} device {:#x})", vendor_id, device_id, properties.vendor_id, properties.device_id))
+	} else if pipeline_cache_uuid != properties.pipeline_cache_uuid {
+		Err(String::from("made with a different driver version (pipeline cache uuid doesn't match)"))
+	} else {
+		Ok(())
+	}
+}
+
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn test_properties() -> vk::PhysicalDeviceProperties {
+		vk::PhysicalDeviceProperties {
+			vendor_id: 0x10de,
+			device_id: 0x2684,
+			pipeline_cache_uuid: [7; 16],
+			..Default::default()
+		}
+	}
+
+	//A header matching "properties", followed by some made up driver data
+	fn test_cache_data(properties: &vk::PhysicalDeviceProperties) -> Vec<u8> {
+		let mut data = vec![];
+		data.extend((PIPELINE_CACHE_HEADER_SIZE as u32).to_le_bytes());
+		data.extend((vk::PipelineCacheHeaderVersion::ONE.as_raw() as u32).to_le_bytes());
+		data.extend(properties.vendor_id.to_le_bytes());
+		data.extend(properties.device_id.to_le_bytes());
+		data.extend(properties.pipeline_cache_uuid);
+		data.extend([0xAB; 64]);
+		data
+	}
+
+	#[test]
+	fn matching_header() {
+		let properties = test_properties();
+		assert_eq!(check_pipeline_cache_header(&test_cache_data(&properties), &properties), Ok(()));
+	}
+
+	#[test]
+	fn short_data() {
+		let properties = test_properties();
+		let data = test_cache_data(&properties);
+		assert!(check_pipeline_cache_header(&[], &properties).is_err());
+		assert!(check_pipeline_cache_header(&data[..PIPELINE_CACHE_HEADER_SIZE - 1], &properties).is_err());
+		//Just the header's fine
+		assert_eq!(check_pipeline_cache_header(&data[..PIPELINE_CACHE_HEADER_SIZE], &properties), Ok(()));
+	}
+
+	//Header size smaller than the spec's header, or bigger than the whole file
+	#[test]
+	fn bad_header_size() {
+		let properties = test_properties();
+		for header_size in [0, PIPELINE_CACHE_HEADER_SIZE as u32 - 4, 10_000] {
+			let mut data = test_cache_data(&properties);
+			data[0..4].copy_from_slice(&header_size.to_le_bytes());
+			assert_eq!(check_pipeline_cache_header(&data, &properties), Err(format!("bad header size {}", header_size)));
+		}
+	}
+
+	#[test]
+	fn bad_header_version() {
+		let properties = test_properties();
+		let mut data = test_cache_data(&properties);
+		data[4..8].copy_from_slice(&2u32.to_le_bytes());
+		assert!(check_pipeline_cache_header(&data, &properties).is_err());
+	}
+
+	//Cache from another GPU - either a different vendor, or a different card from the same vendor
+	#[test]
+	fn different_gpu() {
+		let properties = test_properties();
+		let data = test_cache_data(&properties);
+
+		let other_vendor = vk::PhysicalDeviceProperties {vendor_id: 0x1002, ..properties};
+		let error = check_pipeline_cache_header(&data, &other_vendor).unwrap_err();
+		assert!(error.contains("different GPU"), "{}", error);
+
+		let other_device = vk::PhysicalDeviceProperties {device_id: 0x2704, ..properties};
+		let error = check_pipeline_cache_header(&data, &other_device).unwrap_err();
+		assert!(error.contains("different GPU"), "{}", error);
+	}
+
+	//Same GPU, different driver version
+	#[test]
+	fn uuid_mismatch() {
+		let properties = test_properties();
+		let data = test_cache_data(&properties);
+		let mut other_driver = properties;
+		other_driver.pipeline_cache_uuid[15] = 8;
+		let error = check_pipeline_cache_header(&data, &other_driver).unwrap_err();
+		assert!(error.contains("different driver version"), "{}", error);
+	}
+}
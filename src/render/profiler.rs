@@ -0,0 +1,151 @@
+use crate::constants::{MAX_FRAMES_IN_FLIGHT, GPU_PROFILER_SAMPLES};
+
+use ash::vk;
+use std::collections::VecDeque;
+use std::ptr;
+
+//GPU timing with timestamp queries - a timestamp gets written at the start + end of each scope in the command buffer, and the difference is how long the GPU spent on it
+//Results can't be read until the GPU's done with the frame, so each frame slot's timestamps get read the next time that slot comes around (its fence has been waited on by then)
+
+
+//Parts of the frame that get timed. Add one here for each new pass
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GpuScope {
+	Frame, //The whole command buffer
+	Uploads, //Acquiring finished uploads + generating mips
+	RenderPass, //The main render pass, everything below is inside it
+	StaticObjects,
+	DynamicObjects,
+	DebugLines,
+	Overlay,
+}
+
+impl GpuScope {
+	pub const ALL: [GpuScope; 7] = [GpuScope::Frame, GpuScope::Uploads, GpuScope::RenderPass, GpuScope::StaticObjects, GpuScope::DynamicObjects, GpuScope::DebugLines, GpuScope::Overlay];
+
+	//Shows up in the HUD, and as the debug label for the scope's region of the command buffer
+	pub fn name(self) -> &'static str {
+		match self {
+			GpuScope::Frame => "Frame",
+			GpuScope::Uploads => "Uploads",
+			GpuScope::RenderPass => "Main render pass",
+			GpuScope::StaticObjects => "Static objects",
+			GpuScope::DynamicObjects => "Dynamic objects",
+			GpuScope::DebugLines => "Debug lines",
+			GpuScope::Overlay => "Overlay",
+		}
+	}
+
+	//Each scope has its own pair of queries (start, end) in each frame slot
+	fn index(self) -> u32 {
+		GpuScope::ALL.iter().position(|&scope| scope == self).unwrap() as u32
+	}
+}
+
+//Queries each frame slot gets - a start and end timestamp for every scope
+const QUERIES_PER_FRAME: u32 = GpuScope::ALL.len() as u32 * 2;
+
+pub struct GpuProfiler {
+	query_pool: vk::QueryPool,
+	timestamp_period: f64, //Nanoseconds per timestamp tick
+	timestamp_mask: u64, //Only the low "timestampValidBits" bits of a timestamp mean anything
+	slot_recorded: [bool; MAX_FRAMES_IN_FLIGHT], //Whether each frame slot's queries have been reset + written yet. Reading ones that haven't isn't allowed
+	samples: Vec<VecDeque<f64>>, //Last few times (ms) for each scope, same order as "GpuScope::ALL"
+}
+
+impl GpuProfiler {
+	//None if the graphics queue can't do timestamps
+	pub fn new(instance: &ash::Instance, device: &ash::Device, physical_device: vk::PhysicalDevice, graphics_family: u32) -> Option<GpuProfiler> {
+		let queue_families = unsafe { instance.get_physical_device_queue_family_properties(physical_device) };
+		let timestamp_valid_bits = queue_families[graphics_family as usize].timestamp_valid_bits;
+		if timestamp_valid_bits == 0 {
+			return None
+		}
+		let timestamp_period = unsafe { instance.get_physical_device_properties(physical_device) }.limits.timestamp_period;
+
+		let query_pool_info = vk::QueryPoolCreateInfo {
+			s_type: vk::StructureType::QUERY_POOL_CREATE_INFO,
+			p_next: ptr::null(),
+			flags: vk::QueryPoolCreateFlags::empty(),
+			query_type: vk::QueryType::TIMESTAMP,
+			query_count: QUERIES_PER_FRAME * MAX_FRAMES_IN_FLIGHT as u32,
+			pipeline_statistics: vk::QueryPipelineStatisticFlags::empty(), //Only for pipeline statistics queries
+			..Default::default()
+		};
+		let query_pool = unsafe { device.create_query_pool(&query_pool_info, None).expect("Failed to create timestamp query pool") };
+
+		Some(GpuProfiler {
+			query_pool,
+			timestamp_period: timestamp_period as f64,
+			timestamp_mask: u64::MAX >> (64 - timestamp_valid_bits.min(64)),
+			slot_recorded: [false; MAX_FRAMES_IN_FLIGHT],
+			samples: vec![VecDeque::new(); GpuScope::ALL.len()],
+		})
+	}
+
+	pub fn query_pool(&self) -> vk::QueryPool {
+		self.query_pool
+	}
+
+	//Reads the timestamps the frame slot wrote last time it was used, and adds them to the averages
+	//Call after the slot's fence has been waited on, before recording into it again
+	pub fn collect(&mut self, device: &ash::Device, frame: usize) {
+		if !self.slot_recorded[frame] {
+			return
+		}
+
+		//Each result comes with whether it's available - scopes that got skipped that frame (hidden HUD, etc) never got written
+		let mut results = [[0u64; 2]; QUERIES_PER_FRAME as usize];
+		let query_result = unsafe { device.get_query_pool_results(self.query_pool, frame as u32 * QUERIES_PER_FRAME, &mut results, vk::QueryResultFlags::TYPE_64 | vk::QueryResultFlags::WITH_AVAILABILITY) };
+		if query_result.is_err() {
+			return
+		}
+
+		for (scope_index, timestamps) in results.chunks_exact(2).enumerate() {
+			let ([start, start_available], [end, end_available]) = (timestamps[0], timestamps[1]);
+			if start_available == 0 || end_available == 0 {
+				continue
+			}
+			let ticks = end.wrapping_sub(start) & self.timestamp_mask;
+			let milliseconds = ticks as f64 * self.timestamp_period / 1_000_000.0;
+
+			let samples = &mut self.samples[scope_index];
+			samples.push_back(milliseconds);
+			if samples.len() > GPU_PROFILER_SAMPLES {
+				samples.pop_front();
+			}
+		}
+	}
+
+	//Resets the frame slot's queries so they can be written again. Has to be recorded outside of a render pass, before any of the slot's scopes
+	pub fn cmd_reset(&mut self, device: &ash::Device, command_buffer: vk::CommandBuffer, frame: usize) {
+		unsafe { device.cmd_reset_query_pool(command_buffer, self.query_pool, frame as u32 * QUERIES_PER_FRAME, QUERIES_PER_FRAME) };
+		self.slot_recorded[frame] = true;
+	}
+
+	//Timestamp at the start of a scope - once everything before it has started
+	pub fn cmd_begin_scope(&self, device: &ash::Device, command_buffer: vk::CommandBuffer, frame: usize, scope: GpuScope) {
+		let query = frame as u32 * QUERIES_PER_FRAME + scope.index() * 2;
+		unsafe { device.cmd_write_timestamp(command_buffer, vk::PipelineStageFlags::TOP_OF_PIPE, self.query_pool, query) };
+	}
+
+	//Timestamp at the end of a scope - once everything before it has finished
+	pub fn cmd_end_scope(&self, device: &ash::Device, command_buffer: vk::CommandBuffer, frame: usize, scope: GpuScope) {
+		let query = frame as u32 * QUERIES_PER_FRAME + scope.index() * 2 + 1;
+		unsafe { device.cmd_write_timestamp(command_buffer, vk::PipelineStageFlags::BOTTOM_OF_PIPE, self.query_pool, query) };
+	}
+
+	//Average GPU time (ms) of each scope over the last "GPU_PROFILER_SAMPLES" frames it ran in. Scopes that haven't run yet are left out
+	pub fn averages(&self) -> Vec<(&'static str, f64)> {
+		GpuScope::ALL
+			.iter()
+			.zip(&self.samples)
+			.filter(|(_, samples)| !samples.is_empty())
+			.map(|(scope, samples)| (scope.name(), samples.iter().sum::<f64>() / samples.len() as f64))
+			.collect()
+	}
+
+	pub fn destroy(&self, device: &ash::Device) {
+		unsafe { device.destroy_query_pool(self.query_pool, None) };
+	}
+}
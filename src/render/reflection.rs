@@ -0,0 +1,487 @@
+use crate::render::descriptors::DescriptorBinding;
+
+use ash::vk;
+use std::collections::HashMap;
+
+//Small SPIR-V parser - pulls the interface out of a compiled shader, so it can be checked against what the rust side sets up
+//Only reads what the pipeline cares about:
+//	Vertex inputs (location + format)
+//	Push constant block size
+//	Descriptor bindings (set, binding, type, array size)
+//Everything else in the module (the actual code, names, etc) gets skipped over
+//Format reference: https://registry.khronos.org/SPIR-V/specs/unified1/SPIRV.html
+
+
+const SPIRV_MAGIC: u32 = 0x07230203;
+const HEADER_WORDS: usize = 5;
+
+//Opcodes
+const OP_ENTRY_POINT: u32 = 15;
+const OP_TYPE_BOOL: u32 = 20;
+const OP_TYPE_INT: u32 = 21;
+const OP_TYPE_FLOAT: u32 = 22;
+const OP_TYPE_VECTOR: u32 = 23;
+const OP_TYPE_MATRIX: u32 = 24;
+const OP_TYPE_IMAGE: u32 = 25;
+const OP_TYPE_SAMPLER: u32 = 26;
+const OP_TYPE_SAMPLED_IMAGE: u32 = 27;
+const OP_TYPE_ARRAY: u32 = 28;
+const OP_TYPE_RUNTIME_ARRAY: u32 = 29;
+const OP_TYPE_STRUCT: u32 = 30;
+const OP_TYPE_POINTER: u32 = 32;
+const OP_CONSTANT: u32 = 43;
+const OP_VARIABLE: u32 = 59;
+const OP_DECORATE: u32 = 71;
+const OP_MEMBER_DECORATE: u32 = 72;
+
+//Decorations
+const DECORATION_BLOCK: u32 = 2;
+const DECORATION_BUFFER_BLOCK: u32 = 3;
+const DECORATION_ARRAY_STRIDE: u32 = 6;
+const DECORATION_MATRIX_STRIDE: u32 = 7;
+const DECORATION_BUILT_IN: u32 = 11;
+const DECORATION_LOCATION: u32 = 30;
+const DECORATION_BINDING: u32 = 33;
+const DECORATION_DESCRIPTOR_SET: u32 = 34;
+const DECORATION_OFFSET: u32 = 35;
+
+//Storage classes
+const STORAGE_CLASS_UNIFORM_CONSTANT: u32 = 0;
+const STORAGE_CLASS_INPUT: u32 = 1;
+const STORAGE_CLASS_UNIFORM: u32 = 2;
+const STORAGE_CLASS_PUSH_CONSTANT: u32 = 9;
+const STORAGE_CLASS_STORAGE_BUFFER: u32 = 12;
+
+//Image dimensions that change what kind of descriptor an image is
+const DIM_BUFFER: u32 = 5;
+const DIM_SUBPASS_DATA: u32 = 6;
+
+//The types the parser keeps track of. Ids point at other types
+#[derive(Debug, Clone)]
+enum SpirvType {
+	Bool,
+	Int {width: u32, signed: bool},
+	Float {width: u32},
+	Vector {component: u32, count: u32},
+	Matrix {column: u32, count: u32},
+	Image {dim: u32, sampled: u32},
+	Sampler,
+	SampledImage,
+	Array {element: u32, length: u32},
+	RuntimeArray {element: u32},
+	Struct {members: Vec<u32>},
+	Pointer {pointee: u32},
+}
+
+//A vertex shader input - "layout(location = _) in ..."
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ReflectedInput {
+	pub location: u32,
+	pub format: vk::Format, //UNDEFINED if it's a type that can't be a vertex attribute
+}
+
+//A descriptor the shader uses - "layout(set = _, binding = _) uniform ..."
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ReflectedBinding {
+	pub set: u32,
+	pub binding: u32,
+	pub descriptor_type: vk::DescriptorType,
+	pub count: u32, //Array size, 1 if it's not an array, 0 if it's unsized
+}
+
+//Everything pulled out of one shader module
+#[derive(Debug, Clone)]
+pub struct ShaderReflection {
+	pub stage: vk::ShaderStageFlags,
+	pub inputs: Vec<ReflectedInput>, //Sorted by location. Built in inputs (gl_VertexIndex, etc) are left out
+	pub push_constant_size: Option<u32>, //Bytes in the push constant block, if there is one
+	pub bindings: Vec<ReflectedBinding>, //Sorted by set then binding
+}
+
+impl ShaderReflection {
+	//Parses spirv bytes (what "create_shader_module" takes). Errors if it isn't valid enough spirv to read
+	pub fn parse(spirv_bytes: &[u8]) -> Result<ShaderReflection, String> {
+		if !spirv_bytes.len().is_multiple_of(4) || spirv_bytes.len() < HEADER_WORDS * 4 {
+			return Err(format!("Spirv is {} bytes, which isn't a whole number of words with a header", spirv_bytes.len()))
+		}
+		let mut words: Vec<u32> = spirv_bytes.chunks_exact(4).map(|chunk| u32::from_le_bytes(chunk.try_into().unwrap())).collect();
+		//Spirv can be stored either endianness - the magic number says which
+		if words[0] == SPIRV_MAGIC.swap_bytes() {
+			words.iter_mut().for_each(|word| *word = word.swap_bytes());
+		}
+		if words[0] != SPIRV_MAGIC {
+			return Err(String::from("Not spirv (bad magic number)"))
+		}
+
+		//First pass - collect everything. Ids can be used before they're defined (decorations come before types), so nothing gets worked out until it's all read
+		let mut stage = None;
+		let mut types: HashMap<u32, SpirvType> = HashMap::new();
+		let mut constants: HashMap<u32, u32> = HashMap::new();
+		let mut decorations: HashMap<(u32, u32), u32> = HashMap::new(); //(id, decoration) -> first literal (0 if there isn't one)
+		let mut member_decorations: HashMap<(u32, u32, u32), u32> = HashMap::new(); //(struct id, member, decoration) -> first literal
+		let mut variables: Vec<(u32, u32, u32)> = vec![]; //(id, pointer type, storage class)
+
+		let mut offset = HEADER_WORDS;
+		while offset < words.len() {
+			let word_count = (words[offset] >> 16) as usize;
+			let opcode = words[offset] & 0xFFFF;
+			if word_count == 0 || offset + word_count > words.len() {
+				return Err(format!("Instruction at word {} runs past the end of the module", offset))
+			}
+			let operands = &words[offset + 1..offset + word_count];
+			let operand = |i: usize| operands.get(i).copied().unwrap_or(0);
+
+			match opcode {
+				//Only the first entry point counts - the engine's shaders only ever have "main"
+				OP_ENTRY_POINT if stage.is_none() => stage = Some(execution_model_stage(operand(0))?),
+				OP_TYPE_BOOL => { types.insert(operand(0), SpirvType::Bool); },
+				OP_TYPE_INT => { types.insert(operand(0), SpirvType::Int {width: operand(1), signed: operand(2) == 1}); },
+				OP_TYPE_FLOAT => { types.insert(operand(0), SpirvType::Float {width: operand(1)}); },
+				OP_TYPE_VECTOR => { types.insert(operand(0), SpirvType::Vector {component: operand(1), count: operand(2)}); },
+				OP_TYPE_MATRIX => { types.insert(operand(0), SpirvType::Matrix {column: operand(1), count: operand(2)}); },
+				OP_TYPE_IMAGE => { types.insert(operand(0), SpirvType::Image {dim: operand(2), sampled: operand(6)}); },
+				OP_TYPE_SAMPLER => { types.insert(operand(0), SpirvType::Sampler); },
+				OP_TYPE_SAMPLED_IMAGE => { types.insert(operand(0), SpirvType::SampledImage); },
+				OP_TYPE_ARRAY => { types.insert(operand(0), SpirvType::Array {element: operand(1), length: operand(2)}); }, //Length is a constant's id, looked up later
+				OP_TYPE_RUNTIME_ARRAY => { types.insert(operand(0), SpirvType::RuntimeArray {element: operand(1)}); },
+				OP_TYPE_STRUCT => { types.insert(operand(0), SpirvType::Struct {members: operands[1..].to_vec()}); },
+				OP_TYPE_POINTER => { types.insert(operand(0), SpirvType::Pointer {pointee: operand(2)}); },
+				OP_CONSTANT => { constants.insert(operand(1), operand(2)); }, //Only need the low word - array lengths
+				OP_VARIABLE => variables.push((operand(1), operand(0), operand(2))),
+				OP_DECORATE => { decorations.insert((operand(0), operand(1)), operand(2)); },
+				OP_MEMBER_DECORATE => { member_decorations.insert((operand(0), operand(1), operand(2)), operand(3)); },
+				_ => {},
+			}
+
+			offset += word_count;
+		}
+
+		let module = SpirvModule {types, constants, decorations, member_decorations};
+		let mut reflection = ShaderReflection {
+			stage: stage.ok_or("Spirv module has no entry point")?,
+			inputs: vec![],
+			push_constant_size: None,
+			bindings: vec![],
+		};
+
+		//Second pass - work out what each variable is
+		for (id, pointer_type, storage_class) in variables {
+			let Some(SpirvType::Pointer {pointee}) = module.types.get(&pointer_type) else { continue };
+			let pointee = *pointee;
+
+			match storage_class {
+				STORAGE_CLASS_INPUT => {
+					//Built ins have no location, and either the variable or its struct's members are marked built in
+					if module.is_built_in(id, pointee) {
+						continue
+					}
+					let location = module.decorations.get(&(id, DECORATION_LOCATION)).copied().ok_or(format!("Input variable {} has no location", id))?;
+					reflection.inputs.extend(module.input_formats(pointee).into_iter().enumerate().map(|(i, format)| ReflectedInput {
+						location: location + i as u32, //Matrices take up a location for each column
+						format,
+					}));
+				},
+				STORAGE_CLASS_PUSH_CONSTANT => reflection.push_constant_size = Some(module.type_size(pointee, None)),
+				STORAGE_CLASS_UNIFORM_CONSTANT | STORAGE_CLASS_UNIFORM | STORAGE_CLASS_STORAGE_BUFFER => {
+					let (Some(&set), Some(&binding)) = (module.decorations.get(&(id, DECORATION_DESCRIPTOR_SET)), module.decorations.get(&(id, DECORATION_BINDING))) else { continue };
+					let (element, count) = module.unwrap_arrays(pointee);
+					let Some(descriptor_type) = module.descriptor_type(element, storage_class) else { continue };
+
+					//An image and a sampler at the same binding are the two halves of one combined image sampler
+					match reflection.bindings.iter_mut().find(|existing| existing.set == set && existing.binding == binding) {
+						Some(existing) => {
+							let halves = [existing.descriptor_type, descriptor_type];
+							if halves.contains(&vk::DescriptorType::SAMPLED_IMAGE) && halves.contains(&vk::DescriptorType::SAMPLER) {
+								existing.descriptor_type = vk::DescriptorType::COMBINED_IMAGE_SAMPLER;
+							}
+						},
+						None => reflection.bindings.push(ReflectedBinding {set, binding, descriptor_type, count}),
+					}
+				},
+				_ => {},
+			}
+		}
+
+		reflection.inputs.sort_by_key(|input| input.location);
+		reflection.bindings.sort_by_key(|binding| (binding.set, binding.binding));
+		Ok(reflection)
+	}
+
+	//Checks the vertex inputs against the attribute descriptions the pipeline gets built with
+	//Every input needs an attribute at its location with the same format. Extra attributes are fine, the shader just doesn't read them
+	//Returns a line for each problem - empty if they match
+	pub fn check_vertex_inputs(&self, attributes: &[vk::VertexInputAttributeDescription]) -> Vec<String> {
+		self.inputs.iter().filter_map(|input| {
+			match attributes.iter().find(|attribute| attribute.location == input.location) {
+				None => Some(format!("{:?} shader input at location {} ({:?}) has no vertex attribute", self.stage, input.location, input.format)),
+				Some(attribute) if attribute.format != input.format => Some(format!("{:?} shader input at location {} is {:?}, but the vertex attribute is {:?}", self.stage, input.location, input.format, attribute.format)),
+				_ => None,
+			}
+		}).collect()
+	}
+
+	//Checks the shader's descriptors against the bindings each set layout gets made with ("set_bindings[0]" is set 0, etc)
+	//Each one has to be in the layout with the same type, enough array elements, and this shader's stage enabled
+	pub fn check_descriptor_bindings(&self, set_bindings: &[&[DescriptorBinding]]) -> Vec<String> {
+		self.bindings.iter().filter_map(|reflected| {
+			let Some(bindings) = set_bindings.get(reflected.set as usize) else {
+				return Some(format!("{:?} shader uses set {}, but the pipeline layout only has {} sets", self.stage, reflected.set, set_bindings.len()))
+			};
+			let Some(binding) = bindings.iter().find(|binding| binding.binding == reflected.binding) else {
+				return Some(format!("{:?} shader uses set {} binding {} ({:?}), but it's not in the set layout", self.stage, reflected.set, reflected.binding, reflected.descriptor_type))
+			};
+
+			if binding.descriptor_type != reflected.descriptor_type {
+				Some(format!("Set {} binding {} is {:?} in the {:?} shader, but {:?} in the set layout", reflected.set, reflected.binding, reflected.descriptor_type, self.stage, binding.descriptor_type))
+			} else if binding.count < reflected.count {
+				Some(format!("Set {} binding {} has {} elements in the {:?} shader, but only {} in the set layout", reflected.set, reflected.binding, reflected.count, self.stage, binding.count))
+			} else if !binding.stages.contains(self.stage) {
+				Some(format!("Set {} binding {} is used in the {:?} shader, but the set layout only has it for {:?}", reflected.set, reflected.binding, self.stage, binding.stages))
+			} else {
+				None
+			}
+		}).collect()
+	}
+}
+
+//Push constant ranges for a pipeline made of these shaders - one range from 0 that's as big as the biggest block, visible to every stage that has one
+//(Stages can declare different sized blocks, as long as they agree where they overlap)
+pub fn push_constant_ranges(reflections: &[&ShaderReflection]) -> Vec<vk::PushConstantRange> {
+	let with_push_constants: Vec<&&ShaderReflection> = reflections.iter().filter(|reflection| reflection.push_constant_size.is_some()).collect();
+	if with_push_constants.is_empty() {
+		return vec![]
+	}
+
+	vec![vk::PushConstantRange {
+		stage_flags: with_push_constants.iter().fold(vk::ShaderStageFlags::empty(), |stages, reflection| stages | reflection.stage),
+		offset: 0,
+		size: with_push_constants.iter().filter_map(|reflection| reflection.push_constant_size).max().unwrap(),
+	}]
+}
+
+//Checks a whole pipeline's shaders against the vertex attributes + set layouts it gets built with
+//Errors with every problem found (one per line), so a shader and the rust structs drifting apart gets caught before vulkan does something weird with it
+pub fn check_pipeline_interface(vertex: &ShaderReflection, fragment: &ShaderReflection, attributes: &[vk::VertexInputAttributeDescription], set_bindings: &[&[DescriptorBinding]]) -> Result<(), String> {
+	let mut problems = vertex.check_vertex_inputs(attributes);
+	problems.extend(vertex.check_descriptor_bindings(set_bindings));
+	problems.extend(fragment.check_descriptor_bindings(set_bindings));
+
+	match problems.is_empty() {
+		true => Ok(()),
+		false => Err(problems.join("\n")),
+	}
+}
+
+//Vulkan stage for a spirv execution model
+fn execution_model_stage(execution_model: u32) -> Result<vk::ShaderStageFlags, String> {
+	match execution_model {
+		0 => Ok(vk::ShaderStageFlags::VERTEX),
+		1 => Ok(vk::ShaderStageFlags::TESSELLATION_CONTROL),
+		2 => Ok(vk::ShaderStageFlags::TESSELLATION_EVALUATION),
+		3 => Ok(vk::ShaderStageFlags::GEOMETRY),
+		4 => Ok(vk::ShaderStageFlags::FRAGMENT),
+		5 => Ok(vk::ShaderStageFlags::COMPUTE),
+		_ => Err(format!("Unsupported execution model {}", execution_model)),
+	}
+}
+
+//Everything from the first pass, for looking things up in the second
+struct SpirvModule {
+	types: HashMap<u32, SpirvType>,
+	constants: HashMap<u32, u32>,
+	decorations: HashMap<(u32, u32), u32>,
+	member_decorations: HashMap<(u32, u32, u32), u32>,
+}
+
+impl SpirvModule {
+	fn is_built_in(&self, variable: u32, pointee: u32) -> bool {
+		if self.decorations.contains_key(&(variable, DECORATION_BUILT_IN)) {
+			return true
+		}
+		match self.types.get(&pointee) {
+			Some(SpirvType::Struct {members}) => (0..members.len() as u32).any(|member| self.member_decorations.contains_key(&(pointee, member, DECORATION_BUILT_IN))),
+			_ => false,
+		}
+	}
+
+	//Strips arrays off a type - returns the element type and how many there are (multiplied out for arrays of arrays, 0 if unsized)
+	fn unwrap_arrays(&self, type_id: u32) -> (u32, u32) {
+		match self.types.get(&type_id) {
+			Some(SpirvType::Array {element, length}) => {
+				let (inner, inner_count) = self.unwrap_arrays(*element);
+				(inner, self.constants.get(length).copied().unwrap_or(0) * inner_count)
+			},
+			Some(SpirvType::RuntimeArray {element}) => (self.unwrap_arrays(*element).0, 0),
+			_ => (type_id, 1),
+		}
+	}
+
+	//What kind of descriptor a variable of this type + storage class is. None for things that aren't descriptors
+	fn descriptor_type(&self, type_id: u32, storage_class: u32) -> Option<vk::DescriptorType> {
+		match (self.types.get(&type_id)?, storage_class) {
+			(SpirvType::Struct {..}, STORAGE_CLASS_STORAGE_BUFFER) => Some(vk::DescriptorType::STORAGE_BUFFER),
+			(SpirvType::Struct {..}, STORAGE_CLASS_UNIFORM) if self.decorations.contains_key(&(type_id, DECORATION_BUFFER_BLOCK)) => Some(vk::DescriptorType::STORAGE_BUFFER), //Old style storage buffers
+			(SpirvType::Struct {..}, STORAGE_CLASS_UNIFORM) if self.decorations.contains_key(&(type_id, DECORATION_BLOCK)) => Some(vk::DescriptorType::UNIFORM_BUFFER),
+			(SpirvType::SampledImage, _) => Some(vk::DescriptorType::COMBINED_IMAGE_SAMPLER),
+			(SpirvType::Sampler, _) => Some(vk::DescriptorType::SAMPLER),
+			(SpirvType::Image {dim: DIM_SUBPASS_DATA, ..}, _) => Some(vk::DescriptorType::INPUT_ATTACHMENT),
+			(SpirvType::Image {dim: DIM_BUFFER, sampled: 2}, _) => Some(vk::DescriptorType::STORAGE_TEXEL_BUFFER),
+			(SpirvType::Image {dim: DIM_BUFFER, ..}, _) => Some(vk::DescriptorType::UNIFORM_TEXEL_BUFFER),
+			(SpirvType::Image {sampled: 2, ..}, _) => Some(vk::DescriptorType::STORAGE_IMAGE), //Sampled = 2 means it's only read/written, never sampled
+			(SpirvType::Image {..}, _) => Some(vk::DescriptorType::SAMPLED_IMAGE),
+			_ => None,
+		}
+	}
+
+	//Vertex attribute format(s) for an input type - one for each location it takes up
+	fn input_formats(&self, type_id: u32) -> Vec<vk::Format> {
+		match self.types.get(&type_id) {
+			Some(SpirvType::Matrix {column, count}) => vec![self.scalar_format(*column); *count as usize],
+			Some(SpirvType::Array {element, length}) => {
+				let element_formats = self.input_formats(*element);
+				let length = self.constants.get(length).copied().unwrap_or(0) as usize;
+				element_formats.repeat(length)
+			},
+			_ => vec![self.scalar_format(type_id)],
+		}
+	}
+
+	//Format of a scalar or vector type
+	fn scalar_format(&self, type_id: u32) -> vk::Format {
+		let (component, count) = match self.types.get(&type_id) {
+			Some(SpirvType::Vector {component, count}) => (*component, *count),
+			_ => (type_id, 1),
+		};
+
+		use vk::Format as F;
+		let formats = match self.types.get(&component) {
+			Some(SpirvType::Float {width: 32}) => [F::R32_SFLOAT, F::R32G32_SFLOAT, F::R32G32B32_SFLOAT, F::R32G32B32A32_SFLOAT],
+			Some(SpirvType::Float {width: 64}) => [F::R64_SFLOAT, F::R64G64_SFLOAT, F::R64G64B64_SFLOAT, F::R64G64B64A64_SFLOAT],
+			Some(SpirvType::Int {width: 32, signed: true}) => [F::R32_SINT, F::R32G32_SINT, F::R32G32B32_SINT, F::R32G32B32A32_SINT],
+			Some(SpirvType::Int {width: 32, signed: false}) => [F::R32_UINT, F::R32G32_UINT, F::R32G32B32_UINT, F::R32G32B32A32_UINT],
+			_ => return F::UNDEFINED,
+		};
+		(count as usize).checked_sub(1).and_then(|i| formats.get(i)).copied().unwrap_or(F::UNDEFINED)
+	}
+
+	//Size in bytes of a type in a block (push constants, uniform buffers), going by the offset/stride decorations
+	//"matrix_stride" comes from the struct member a matrix is in, since that's where spirv puts it
+	fn type_size(&self, type_id: u32, matrix_stride: Option<u32>) -> u32 {
+		match self.types.get(&type_id) {
+			Some(SpirvType::Bool) => 4,
+			Some(SpirvType::Int {width, ..}) | Some(SpirvType::Float {width}) => width / 8,
+			Some(SpirvType::Vector {component, count}) => self.type_size(*component, None) * count,
+			Some(SpirvType::Matrix {column, count}) => matrix_stride.unwrap_or(self.type_size(*column, None)) * count,
+			Some(SpirvType::Array {element, length}) => {
+				let length = self.constants.get(length).copied().unwrap_or(0);
+				let stride = self.decorations.get(&(type_id, DECORATION_ARRAY_STRIDE)).copied().unwrap_or(self.type_size(*element, matrix_stride));
+				stride * length
+			},
+			//Ends wherever the furthest member ends
+			Some(SpirvType::Struct {members}) => members.iter().enumerate().map(|(i, &member_type)| {
+				let member_offset = self.member_decorations.get(&(type_id, i as u32, DECORATION_OFFSET)).copied().unwrap_or(0);
+				let member_matrix_stride = self.member_decorations.get(&(type_id, i as u32, DECORATION_MATRIX_STRIDE)).copied();
+				member_offset + self.type_size(member_type, member_matrix_stride)
+			}).max().unwrap_or(0),
+			_ => 0,
+		}
+	}
+}
+
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::render::{Vertex, PIPELINE_SET_BINDINGS};
+	use crate::utility::read;
+
+	fn vertex_reflection() -> ShaderReflection {
+		ShaderReflection::parse(&read::vertex_shader()).expect("Failed to reflect vertex.spv")
+	}
+	fn fragment_reflection() -> ShaderReflection {
+		ShaderReflection::parse(&read::fragment_shader()).expect("Failed to reflect fragment.spv")
+	}
+
+	//Vertex attributes + instance matrix columns, without gl_VertexIndex and friends
+	#[test]
+	fn vertex_shader_inputs() {
+		let reflection = vertex_reflection();
+		assert_eq!(reflection.stage, vk::ShaderStageFlags::VERTEX);
+
+		let inputs: Vec<(u32, vk::Format)> = reflection.inputs.iter().map(|input| (input.location, input.format)).collect();
+		assert_eq!(inputs, [
+			(0, vk::Format::R32G32B32A32_SFLOAT), //Position
+			(1, vk::Format::R32G32B32_SFLOAT), //Color
+			(2, vk::Format::R32G32B32_SFLOAT), //Normal
+			(3, vk::Format::R32G32_SFLOAT), //UV
+			(4, vk::Format::R32G32B32A32_SFLOAT), //Model matrix columns
+			(5, vk::Format::R32G32B32A32_SFLOAT),
+			(6, vk::Format::R32G32B32A32_SFLOAT),
+			(7, vk::Format::R32G32B32A32_SFLOAT),
+			(8, vk::Format::R32G32B32A32_SFLOAT), //Tint
+		]);
+	}
+
+	//Frame uniforms in the vertex shader, the texture's image + sampler halves merged into one combined image sampler in the fragment shader
+	#[test]
+	fn set_bindings() {
+		let vertex = vertex_reflection();
+		assert_eq!(vertex.bindings, [ReflectedBinding {set: 0, binding: 0, descriptor_type: vk::DescriptorType::UNIFORM_BUFFER, count: 1}]);
+
+		let fragment = fragment_reflection();
+		assert_eq!(fragment.stage, vk::ShaderStageFlags::FRAGMENT);
+		assert_eq!(fragment.bindings, [ReflectedBinding {set: 1, binding: 0, descriptor_type: vk::DescriptorType::COMBINED_IMAGE_SAMPLER, count: 1}]);
+	}
+
+	//No push constants in the main shaders. The debug view one has a uint, the overlay one has a vec2
+	#[test]
+	fn push_constants() {
+		let vertex = vertex_reflection();
+		let fragment = fragment_reflection();
+		assert_eq!(vertex.push_constant_size, None);
+		assert_eq!(fragment.push_constant_size, None);
+		assert!(push_constant_ranges(&[&vertex, &fragment]).is_empty());
+
+		let debug_fragment = ShaderReflection::parse(&read::debug_fragment_shader()).expect("Failed to reflect debug.spv");
+		let ranges = push_constant_ranges(&[&vertex, &debug_fragment]);
+		assert_eq!(ranges.len(), 1);
+		assert_eq!((ranges[0].stage_flags, ranges[0].offset, ranges[0].size), (vk::ShaderStageFlags::FRAGMENT, 0, 4));
+
+		let overlay_vertex = ShaderReflection::parse(&read::overlay_vertex_shader()).expect("Failed to reflect overlay_vert.spv");
+		assert_eq!(overlay_vertex.push_constant_size, Some(8));
+	}
+
+	//The shipped shaders match the rust side
+	#[test]
+	fn pipeline_interface_matches() {
+		let result = check_pipeline_interface(&vertex_reflection(), &fragment_reflection(), &Vertex::get_attribute_descriptions(), &PIPELINE_SET_BINDINGS);
+		assert_eq!(result, Ok(()));
+	}
+
+	//Missing or mismatched attributes and set layouts each get reported
+	#[test]
+	fn pipeline_interface_mismatch() {
+		let mut attributes = Vertex::get_attribute_descriptions().to_vec();
+		attributes.retain(|attribute| attribute.location != 3); //No UVs
+		attributes[1].format = vk::Format::R32G32B32A32_SFLOAT; //Color as a vec4
+
+		let error = check_pipeline_interface(&vertex_reflection(), &fragment_reflection(), &attributes, &PIPELINE_SET_BINDINGS).unwrap_err();
+		let problems: Vec<&str> = error.lines().collect();
+		assert_eq!(problems.len(), 2, "{}", error);
+		assert!(problems[0].contains("location 1 is R32G32B32_SFLOAT, but the vertex attribute is R32G32B32A32_SFLOAT"), "{}", error);
+		assert!(problems[1].contains("location 3 (R32G32_SFLOAT) has no vertex attribute"), "{}", error);
+
+		//Only set 0 in the layout - the fragment shader's texture has nowhere to go
+		let error = check_pipeline_interface(&vertex_reflection(), &fragment_reflection(), &Vertex::get_attribute_descriptions(), &PIPELINE_SET_BINDINGS[..1]).unwrap_err();
+		assert!(error.contains("uses set 1, but the pipeline layout only has 1 sets"), "{}", error);
+	}
+
+	#[test]
+	fn not_spirv() {
+		assert!(ShaderReflection::parse(&[0; 7]).is_err());
+		assert!(ShaderReflection::parse(&[0; 20]).is_err());
+		//Header's fine, but the module's cut off partway through its first instruction (OpCapability, 2 words)
+		let spirv = read::vertex_shader();
+		assert!(ShaderReflection::parse(&spirv[..24]).is_err());
+	}
+}
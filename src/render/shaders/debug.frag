@@ -0,0 +1,36 @@
+#version 460
+//DEBUG FRAGMENT SHADER
+//Used by the debug render modes - shows one thing about the geometry instead of the shaded + textured color
+
+//Get input from the vertex shader - same as "fragment.frag", plus the debug outputs
+layout(location = 0) in vec3 fragColor;
+layout(location = 1) in vec2 fragUV;
+layout(location = 2) in vec3 fragNormal;
+layout(location = 3) in vec3 fragVertexColor;
+layout(location = 4) in float fragViewDepth;
+//Output the color RBGa
+layout(location = 0) out vec4 outColor;
+
+//Which thing to show - has to match "RenderMode::debug_view"
+//0 = vertex color, 1 = normals, 2 = depth
+layout(push_constant) uniform DebugConstants {
+    uint view;
+} Debug;
+
+void main() {
+    vec3 color = fragVertexColor;
+
+    if (Debug.view == 1u) {
+        //Normals mapped from -1..1 to 0..1, so +x is red, +y is green, +z is blue. No normal shows up grey
+        color = vec3(0.5);
+        if (dot(fragNormal, fragNormal) > 0.0) {
+            color = normalize(fragNormal) * 0.5 + 0.5;
+        }
+    } else if (Debug.view == 2u) {
+        //Log scale, so nearby detail doesn't all come out white - white right in front of the camera, black at 1000 units and past
+        float depth = clamp(log2(max(fragViewDepth, 1.0)) / log2(1000.0), 0.0, 1.0);
+        color = vec3(1.0 - depth);
+    }
+
+    outColor = vec4(color, 1.0);
+}
@@ -0,0 +1,20 @@
+#version 460
+//DEBUG LINE VERTEX SHADER
+//For the debug line pipeline - lines are already in world space, so they just go through the camera
+
+//Has to match "DebugVertex"
+layout(location = 0) in vec3 inPosition;
+layout(location = 1) in vec3 inColor;
+
+layout(location = 0) out vec3 fragColor;
+
+//Per frame uniform buffer - same as "vertex.vert", has to match "FrameUniforms"
+layout(set = 0, binding = 0) uniform FrameData {
+    mat4 view_projection; //World -> clip space, from the camera
+    vec4 light_dir; //Not used here
+} Frame;
+
+void main() {
+	gl_Position = Frame.view_projection * vec4(inPosition, 1.0);
+	fragColor = inColor;
+}
@@ -4,10 +4,17 @@
 //Get input from the vertex shader
 //Name doesn't necessarily need to match, just indices
 layout(location = 0) in vec3 fragColor;
+layout(location = 1) in vec2 fragUV;
 //Output the color RBGa
 layout(location = 0) out vec4 outColor;
 
+//The object's texture (set 1) - untextured objects get a plain white one
+//It's one combined image sampler descriptor, just declared as its image + sampler halves at the same binding (vulkan allows that, and it's what the shader compiler supports)
+layout(set = 1, binding = 0) uniform texture2D baseColorTexture;
+layout(set = 1, binding = 0) uniform sampler baseColorSampler;
+
 void main() {
     //Automatically gets interpolated
-    outColor = vec4(fragColor, 1.0);
+    //Shaded vertex color tinted by the texture
+    outColor = vec4(fragColor, 1.0) * texture(sampler2D(baseColorTexture, baseColorSampler), fragUV);
 }
\ No newline at end of file
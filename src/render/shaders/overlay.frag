@@ -0,0 +1,15 @@
+#version 460
+//OVERLAY FRAGMENT SHADER
+//Font atlas is white where the glyphs are, so this is just the vertex color with the atlas' alpha (gets alpha blended onto the scene)
+
+layout(location = 0) in vec2 fragUV;
+layout(location = 1) in vec4 fragColor;
+layout(location = 0) out vec4 outColor;
+
+//The font atlas, in the texture set (set 1) - same combined image sampler setup as "fragment.frag"
+layout(set = 1, binding = 0) uniform texture2D fontTexture;
+layout(set = 1, binding = 0) uniform sampler fontSampler;
+
+void main() {
+    outColor = fragColor * texture(sampler2D(fontTexture, fontSampler), fragUV);
+}
@@ -0,0 +1,23 @@
+#version 460
+//OVERLAY VERTEX SHADER
+//For the overlay pipeline (HUD text) - positions come in as pixels from the top left of the screen, and get turned into clip space here
+
+//Has to match "OverlayVertex"
+layout(location = 0) in vec2 inPosition;
+layout(location = 1) in vec2 inUV;
+layout(location = 2) in vec4 inColor;
+
+layout(location = 0) out vec2 fragUV;
+layout(location = 1) out vec4 fragColor;
+
+//Size of the screen in pixels, pushed every frame (it changes when the window gets resized)
+layout(push_constant) uniform OverlayPushConstants {
+    vec2 screen_size;
+} Overlay;
+
+void main() {
+	//Pixels -> -1 to 1. Vulkan's clip space already has y going down, same as the pixels
+	gl_Position = vec4(inPosition / Overlay.screen_size * 2.0 - 1.0, 0.0, 1.0);
+	fragUV = inUV;
+	fragColor = inColor;
+}
@@ -7,21 +7,56 @@
 //Some variables (dvec3, for example) will take up multiple slots so the next index must be higher, be careful with that
 layout(location = 0) in vec4 inPosition;
 layout(location = 1) in vec3 inColor;
+layout(location = 2) in vec3 inNormal;
+layout(location = 3) in vec2 inUV;
+//Per instance inputs (binding 1) - has to match "InstanceData"
+//Model -> world space matrix from the object being drawn, one column per location (4 through 7)
+layout(location = 4) in vec4 inModel0;
+layout(location = 5) in vec4 inModel1;
+layout(location = 6) in vec4 inModel2;
+layout(location = 7) in vec4 inModel3;
+layout(location = 8) in vec4 inTint; //Multiplied into the color
 
 //Define outputs
 //gl_Position is a predefined output
 //layout(location = 0) specifies framebuffer index
 layout(location = 0) out vec3 fragColor;
+layout(location = 1) out vec2 fragUV;
+//Extra outputs for the debug render modes ("debug.frag") - the normal shader ignores these
+layout(location = 2) out vec3 fragNormal; //World space, zero if the vertex has no normal
+layout(location = 3) out vec3 fragVertexColor; //Color before lighting
+layout(location = 4) out float fragViewDepth; //Distance in front of the camera
 
-//Push constants
-layout(push_constant) uniform constants {
-    mat4 render_matrix;
-} PushConstants;
+//Per frame uniform buffer - has to match "FrameUniforms"
+layout(set = 0, binding = 0) uniform FrameData {
+    mat4 view_projection; //World -> clip space, from the camera
+    vec4 light_dir; //Direction the light comes from (world space, w unused), doesn't need to be normalized
+} Frame;
 
 //Main function is called for every vertex
 void main() {
-	//Sets vertex position
-	gl_Position = PushConstants.render_matrix * inPosition;
+	mat4 inModel = mat4(inModel0, inModel1, inModel2, inModel3);
+
+	//Sets vertex position - model space to world space to clip space
+	gl_Position = Frame.view_projection * inModel * inPosition;
+
+	//Simple directional light, with a bit of ambient so the back sides aren't pitch black
+	//Normals need the inverse transpose of the model matrix so non-uniform scale doesn't skew them
+	//Vertices with no normal just use their color as is
+	float brightness = 1.0;
+	vec3 world_normal = vec3(0.0);
+	if (dot(inNormal, inNormal) > 0.0) {
+		world_normal = normalize(transpose(inverse(mat3(inModel))) * inNormal);
+		brightness = 0.35 + 0.65 * max(dot(world_normal, normalize(Frame.light_dir.xyz)), 0.0);
+	}
+
 	//Sets vertex color (this just is an output to pass to the fragment shader)
-	fragColor = inColor;
-}
\ No newline at end of file
+	fragColor = inColor * inTint.rgb * brightness;
+	//Texture coords just get passed through
+	fragUV = inUV;
+
+	//Debug stuff. The projection puts view depth in w
+	fragNormal = world_normal;
+	fragVertexColor = inColor * inTint.rgb;
+	fragViewDepth = gl_Position.w;
+}
@@ -0,0 +1,81 @@
+use crate::render::memory::{GpuAllocator, Allocation, create_buffer};
+
+use ash::vk;
+use std::ptr;
+
+//Everything pushed into a streaming buffer starts on a multiple of this. Plenty for vertex/index/instance data
+//(Uniform/storage buffer offsets would need the device's min offset alignment instead)
+const STREAMING_ALIGNMENT: vk::DeviceSize = 16;
+
+//Host visible ring buffer for data the CPU rewrites every frame - dynamic geometry, instance data, debug lines, UI, etc
+//Split into one region for each frame in flight. A frame writes into its own slot's region while the GPU can still be reading the other regions
+//Static stuff should stay in device local memory ("create_device_local_buffer") - this is for data that'd otherwise need re-staging with "copy_buffer" every frame, which blocks the queue
+//The GPU reads this straight out of host visible memory. Slower to read than device local, but it only gets read once or twice before it's overwritten anyway
+pub struct StreamingBuffer {
+	buffer: vk::Buffer,
+	allocation: Allocation, //Host visible + coherent, so writes don't need flushing
+	region_size: vk::DeviceSize, //Size of each frame slot's region
+	region_count: usize,
+	current_region: usize, //Region being written this frame
+	write_offset: vk::DeviceSize, //Where the next push goes, relative to the start of the current region
+}
+
+impl StreamingBuffer {
+	//Makes the buffer with "region_count" regions (usually MAX_FRAMES_IN_FLIGHT) of "region_size" bytes each
+	//"usage" is what the data gets used as - vertex buffer, index buffer, etc (can be a few at once)
+	pub fn new(device: &ash::Device, allocator: &mut GpuAllocator, region_size: vk::DeviceSize, region_count: usize, usage: vk::BufferUsageFlags) -> StreamingBuffer {
+		let region_size = region_size.next_multiple_of(STREAMING_ALIGNMENT); //So every region starts aligned too
+		let (buffer, allocation) = create_buffer(device, allocator, region_size * region_count as vk::DeviceSize, usage, vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT);
+
+		StreamingBuffer {
+			buffer,
+			allocation,
+			region_size,
+			region_count,
+			current_region: 0,
+			write_offset: 0,
+		}
+	}
+
+	//Starts writing into a frame slot's region, throwing away whatever got written there last time
+	//Only call this once the slot's fence has been waited on, otherwise the GPU might still be reading the old data
+	pub fn begin_frame(&mut self, frame: usize) {
+		assert!(frame < self.region_count, "Frame slot {:?} is past the streaming buffer's {:?} regions", frame, self.region_count);
+		self.current_region = frame;
+		self.write_offset = 0;
+	}
+
+	//Copies data into the current region
+	//Returns the offset into the buffer to bind/read it at, or None if the region doesn't have room left this frame
+	pub fn push<T: Copy>(&mut self, data: &[T]) -> Option<vk::DeviceSize> {
+		let size = core::mem::size_of_val(data) as vk::DeviceSize;
+		let start = self.write_offset.next_multiple_of(STREAMING_ALIGNMENT);
+		if start + size > self.region_size {
+			return None
+		}
+
+		let buffer_offset = self.current_region as vk::DeviceSize * self.region_size + start;
+		unsafe {
+			let p_destination = self.allocation.mapped_pointer::<u8>().add(buffer_offset as usize) as *mut T;
+			ptr::copy_nonoverlapping(data.as_ptr(), p_destination, data.len());
+		}
+
+		self.write_offset = start + size;
+		Some(buffer_offset)
+	}
+
+	pub fn buffer(&self) -> vk::Buffer {
+		self.buffer
+	}
+
+	//Bytes written into the current region so far this frame (including alignment padding)
+	pub fn bytes_used(&self) -> vk::DeviceSize {
+		self.write_offset
+	}
+
+	//Destroys the buffer and gives its memory back. Make sure the GPU is done with every region first
+	pub fn destroy(&self, device: &ash::Device, allocator: &mut GpuAllocator) {
+		unsafe { device.destroy_buffer(self.buffer, None) };
+		allocator.free(device, &self.allocation);
+	}
+}
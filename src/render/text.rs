@@ -0,0 +1,193 @@
+use crate::render::OverlayVertex;
+use crate::render::texture::{TextureData, SamplerSettings};
+
+use ash::vk;
+use glam::f32::{vec2, vec4, Vec2, Vec4};
+
+//Text for the overlay, drawn from a bitmap font baked into the exe - no font files, no font rasterizing
+//The font gets turned into an atlas texture once, then every character is a textured quad out of it
+
+
+//Size of one glyph in the font, in texels
+pub const GLYPH_SIZE: u32 = 8;
+//Glyphs per row in the atlas. 95 printable characters -> 16x6 grid, 128x48 texels
+const ATLAS_COLUMNS: u32 = 16;
+//First + last characters the font has (printable ascii). Anything else gets drawn as "?"
+const FIRST_CHAR: u8 = b' ';
+const LAST_CHAR: u8 = b'~';
+
+//Public domain 8x8 font (font8x8_basic) for ascii 32 to 126
+//Each glyph is 8 rows top to bottom, and the lowest bit of each row is the leftmost pixel
+const FONT_8X8: [[u8; 8]; 95] = [
+	[0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], //Space
+	[0x18, 0x3C, 0x3C, 0x18, 0x18, 0x00, 0x18, 0x00], //Exclamation mark
+	[0x36, 0x36, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], //"
+	[0x36, 0x36, 0x7F, 0x36, 0x7F, 0x36, 0x36, 0x00], //#
+	[0x0C, 0x3E, 0x03, 0x1E, 0x30, 0x1F, 0x0C, 0x00], //$
+	[0x00, 0x63, 0x33, 0x18, 0x0C, 0x66, 0x63, 0x00], //%
+	[0x1C, 0x36, 0x1C, 0x6E, 0x3B, 0x33, 0x6E, 0x00], //&
+	[0x06, 0x06, 0x03, 0x00, 0x00, 0x00, 0x00, 0x00], //'
+	[0x18, 0x0C, 0x06, 0x06, 0x06, 0x0C, 0x18, 0x00], //(
+	[0x06, 0x0C, 0x18, 0x18, 0x18, 0x0C, 0x06, 0x00], //)
+	[0x00, 0x66, 0x3C, 0xFF, 0x3C, 0x66, 0x00, 0x00], //*
+	[0x00, 0x0C, 0x0C, 0x3F, 0x0C, 0x0C, 0x00, 0x00], //+
+	[0x00, 0x00, 0x00, 0x00, 0x00, 0x0C, 0x0C, 0x06], //,
+	[0x00, 0x00, 0x00, 0x3F, 0x00, 0x00, 0x00, 0x00], //-
+	[0x00, 0x00, 0x00, 0x00, 0x00, 0x0C, 0x0C, 0x00], //.
+	[0x60, 0x30, 0x18, 0x0C, 0x06, 0x03, 0x01, 0x00], //Slash
+	[0x3E, 0x63, 0x73, 0x7B, 0x6F, 0x67, 0x3E, 0x00], //0
+	[0x0C, 0x0E, 0x0C, 0x0C, 0x0C, 0x0C, 0x3F, 0x00], //1
+	[0x1E, 0x33, 0x30, 0x1C, 0x06, 0x33, 0x3F, 0x00], //2
+	[0x1E, 0x33, 0x30, 0x1C, 0x30, 0x33, 0x1E, 0x00], //3
+	[0x38, 0x3C, 0x36, 0x33, 0x7F, 0x30, 0x78, 0x00], //4
+	[0x3F, 0x03, 0x1F, 0x30, 0x30, 0x33, 0x1E, 0x00], //5
+	[0x1C, 0x06, 0x03, 0x1F, 0x33, 0x33, 0x1E, 0x00], //6
+	[0x3F, 0x33, 0x30, 0x18, 0x0C, 0x0C, 0x0C, 0x00], //7
+	[0x1E, 0x33, 0x33, 0x1E, 0x33, 0x33, 0x1E, 0x00], //8
+	[0x1E, 0x33, 0x33, 0x3E, 0x30, 0x18, 0x0E, 0x00], //9
+	[0x00, 0x0C, 0x0C, 0x00, 0x00, 0x0C, 0x0C, 0x00], //:
+	[0x00, 0x0C, 0x0C, 0x00, 0x00, 0x0C, 0x0C, 0x06], //;
+	[0x18, 0x0C, 0x06, 0x03, 0x06, 0x0C, 0x18, 0x00], //<
+	[0x00, 0x00, 0x3F, 0x00, 0x00, 0x3F, 0x00, 0x00], //=
+	[0x06, 0x0C, 0x18, 0x30, 0x18, 0x0C, 0x06, 0x00], //>
+	[0x1E, 0x33, 0x30, 0x18, 0x0C, 0x00, 0x0C, 0x00], //?
+	[0x3E, 0x63, 0x7B, 0x7B, 0x7B, 0x03, 0x1E, 0x00], //@
+	[0x0C, 0x1E, 0x33, 0x33, 0x3F, 0x33, 0x33, 0x00], //A
+	[0x3F, 0x66, 0x66, 0x3E, 0x66, 0x66, 0x3F, 0x00], //B
+	[0x3C, 0x66, 0x03, 0x03, 0x03, 0x66, 0x3C, 0x00], //C
+	[0x1F, 0x36, 0x66, 0x66, 0x66, 0x36, 0x1F, 0x00], //D
+	[0x7F, 0x46, 0x16, 0x1E, 0x16, 0x46, 0x7F, 0x00], //E
+	[0x7F, 0x46, 0x16, 0x1E, 0x16, 0x06, 0x0F, 0x00], //F
+	[0x3C, 0x66, 0x03, 0x03, 0x73, 0x66, 0x7C, 0x00], //G
+	[0x33, 0x33, 0x33, 0x3F, 0x33, 0x33, 0x33, 0x00], //H
+	[0x1E, 0x0C, 0x0C, 0x0C, 0x0C, 0x0C, 0x1E, 0x00], //I
+	[0x78, 0x30, 0x30, 0x30, 0x33, 0x33, 0x1E, 0x00], //J
+	[0x67, 0x66, 0x36, 0x1E, 0x36, 0x66, 0x67, 0x00], //K
+	[0x0F, 0x06, 0x06, 0x06, 0x46, 0x66, 0x7F, 0x00], //L
+	[0x63, 0x77, 0x7F, 0x7F, 0x6B, 0x63, 0x63, 0x00], //M
+	[0x63, 0x67, 0x6F, 0x7B, 0x73, 0x63, 0x63, 0x00], //N
+	[0x1C, 0x36, 0x63, 0x63, 0x63, 0x36, 0x1C, 0x00], //O
+	[0x3F, 0x66, 0x66, 0x3E, 0x06, 0x06, 0x0F, 0x00], //P
+	[0x1E, 0x33, 0x33, 0x33, 0x3B, 0x1E, 0x38, 0x00], //Q
+	[0x3F, 0x66, 0x66, 0x3E, 0x36, 0x66, 0x67, 0x00], //R
+	[0x1E, 0x33, 0x07, 0x0E, 0x38, 0x33, 0x1E, 0x00], //S
+	[0x3F, 0x2D, 0x0C, 0x0C, 0x0C, 0x0C, 0x1E, 0x00], //T
+	[0x33, 0x33, 0x33, 0x33, 0x33, 0x33, 0x3F, 0x00], //U
+	[0x33, 0x33, 0x33, 0x33, 0x33, 0x1E, 0x0C, 0x00], //V
+	[0x63, 0x63, 0x63, 0x6B, 0x7F, 0x77, 0x63, 0x00], //W
+	[0x63, 0x63, 0x36, 0x1C, 0x1C, 0x36, 0x63, 0x00], //X
+	[0x33, 0x33, 0x33, 0x1E, 0x0C, 0x0C, 0x1E, 0x00], //Y
+	[0x7F, 0x63, 0x31, 0x18, 0x4C, 0x66, 0x7F, 0x00], //Z
+	[0x1E, 0x06, 0x06, 0x06, 0x06, 0x06, 0x1E, 0x00], //[
+	[0x03, 0x06, 0x0C, 0x18, 0x30, 0x60, 0x40, 0x00], //Backslash
+	[0x1E, 0x18, 0x18, 0x18, 0x18, 0x18, 0x1E, 0x00], //]
+	[0x08, 0x1C, 0x36, 0x63, 0x00, 0x00, 0x00, 0x00], //^
+	[0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xFF], //_
+	[0x0C, 0x0C, 0x18, 0x00, 0x00, 0x00, 0x00, 0x00], //`
+	[0x00, 0x00, 0x1E, 0x30, 0x3E, 0x33, 0x6E, 0x00], //a
+	[0x07, 0x06, 0x06, 0x3E, 0x66, 0x66, 0x3B, 0x00], //b
+	[0x00, 0x00, 0x1E, 0x33, 0x03, 0x33, 0x1E, 0x00], //c
+	[0x38, 0x30, 0x30, 0x3E, 0x33, 0x33, 0x6E, 0x00], //d
+	[0x00, 0x00, 0x1E, 0x33, 0x3F, 0x03, 0x1E, 0x00], //e
+	[0x1C, 0x36, 0x06, 0x0F, 0x06, 0x06, 0x0F, 0x00], //f
+	[0x00, 0x00, 0x6E, 0x33, 0x33, 0x3E, 0x30, 0x1F], //g
+	[0x07, 0x06, 0x36, 0x6E, 0x66, 0x66, 0x67, 0x00], //h
+	[0x0C, 0x00, 0x0E, 0x0C, 0x0C, 0x0C, 0x1E, 0x00], //i
+	[0x30, 0x00, 0x30, 0x30, 0x30, 0x33, 0x33, 0x1E], //j
+	[0x07, 0x06, 0x66, 0x36, 0x1E, 0x36, 0x67, 0x00], //k
+	[0x0E, 0x0C, 0x0C, 0x0C, 0x0C, 0x0C, 0x1E, 0x00], //l
+	[0x00, 0x00, 0x33, 0x7F, 0x7F, 0x6B, 0x63, 0x00], //m
+	[0x00, 0x00, 0x1F, 0x33, 0x33, 0x33, 0x33, 0x00], //n
+	[0x00, 0x00, 0x1E, 0x33, 0x33, 0x33, 0x1E, 0x00], //o
+	[0x00, 0x00, 0x3B, 0x66, 0x66, 0x3E, 0x06, 0x0F], //p
+	[0x00, 0x00, 0x6E, 0x33, 0x33, 0x3E, 0x30, 0x78], //q
+	[0x00, 0x00, 0x3B, 0x6E, 0x66, 0x06, 0x0F, 0x00], //r
+	[0x00, 0x00, 0x3E, 0x03, 0x1E, 0x30, 0x1F, 0x00], //s
+	[0x08, 0x0C, 0x3E, 0x0C, 0x0C, 0x2C, 0x18, 0x00], //t
+	[0x00, 0x00, 0x33, 0x33, 0x33, 0x33, 0x6E, 0x00], //u
+	[0x00, 0x00, 0x33, 0x33, 0x33, 0x1E, 0x0C, 0x00], //v
+	[0x00, 0x00, 0x63, 0x6B, 0x7F, 0x7F, 0x36, 0x00], //w
+	[0x00, 0x00, 0x63, 0x36, 0x1C, 0x36, 0x63, 0x00], //x
+	[0x00, 0x00, 0x33, 0x33, 0x33, 0x3E, 0x30, 0x1F], //y
+	[0x00, 0x00, 0x3F, 0x19, 0x0C, 0x26, 0x3F, 0x00], //z
+	[0x38, 0x0C, 0x0C, 0x07, 0x0C, 0x0C, 0x38, 0x00], //{
+	[0x18, 0x18, 0x18, 0x00, 0x18, 0x18, 0x18, 0x00], //|
+	[0x07, 0x0C, 0x0C, 0x38, 0x0C, 0x0C, 0x07, 0x00], //}
+	[0x6E, 0x3B, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], //~
+];
+
+//Where a character's glyph is in the atlas, by grid cell
+fn glyph_cell(character: char) -> (u32, u32) {
+	let index = match character as u32 {
+		code @ 0x20..=0x7E => code - FIRST_CHAR as u32,
+		_ => (b'?' - FIRST_CHAR) as u32,
+	};
+	(index % ATLAS_COLUMNS, index / ATLAS_COLUMNS)
+}
+
+//Every glyph laid out in a grid, white where the glyph is and clear everywhere else - so the overlay's vertex color is the text color
+//Nearest filtering + no mips keeps the pixels crisp when it's scaled up
+pub fn font_atlas() -> TextureData {
+	let glyph_count = (LAST_CHAR - FIRST_CHAR + 1) as u32;
+	let width = ATLAS_COLUMNS * GLYPH_SIZE;
+	let height = glyph_count.div_ceil(ATLAS_COLUMNS) * GLYPH_SIZE;
+
+	let mut pixels = vec![0; (width * height * 4) as usize];
+	for (index, glyph) in FONT_8X8.iter().enumerate() {
+		let (column, row) = (index as u32 % ATLAS_COLUMNS, index as u32 / ATLAS_COLUMNS);
+		for (y, bits) in glyph.iter().enumerate() {
+			for x in 0..GLYPH_SIZE {
+				if bits & (1 << x) != 0 {
+					let pixel = ((row * GLYPH_SIZE + y as u32) * width + column * GLYPH_SIZE + x) as usize * 4;
+					pixels[pixel..pixel + 4].copy_from_slice(&[255; 4]);
+				}
+			}
+		}
+	}
+
+	let mut texture_data = TextureData::from_raw_rgba(pixels, width, height);
+	texture_data.sampler_settings = SamplerSettings {
+		mag_filter: vk::Filter::NEAREST,
+		min_filter: vk::Filter::NEAREST,
+		mipmap_mode: vk::SamplerMipmapMode::NEAREST,
+		address_mode: vk::SamplerAddressMode::CLAMP_TO_EDGE,
+		max_anisotropy: None,
+		generate_mips: false,
+	};
+	texture_data
+}
+
+//Quads (2 triangles each) for a string, starting with its top left corner at "origin" (in pixels, from the top left of the screen)
+//Each glyph is "scale" pixels per font texel. Newlines go down a line. Gets a black drop shadow so it's readable on anything
+pub fn text_vertices(text: &str, origin: Vec2, scale: f32, color: Vec4) -> Vec<OverlayVertex> {
+	let mut vertices = vec![];
+	let shadow_color = vec4(0.0, 0.0, 0.0, color.w);
+	//Shadow first, so the text goes on top of it
+	push_text_quads(&mut vertices, text, origin + Vec2::splat(scale), scale, shadow_color);
+	push_text_quads(&mut vertices, text, origin, scale, color);
+	vertices
+}
+
+fn push_text_quads(vertices: &mut Vec<OverlayVertex>, text: &str, origin: Vec2, scale: f32, color: Vec4) {
+	let glyph_size = GLYPH_SIZE as f32 * scale;
+	let atlas_size = vec2((ATLAS_COLUMNS * GLYPH_SIZE) as f32, (FONT_8X8.len() as u32).div_ceil(ATLAS_COLUMNS) as f32 * GLYPH_SIZE as f32);
+
+	for (line_index, line) in text.lines().enumerate() {
+		for (char_index, character) in line.chars().enumerate() {
+			if character == ' ' {
+				continue
+			}
+			let top_left = origin + vec2(char_index as f32, line_index as f32) * glyph_size;
+			let (column, row) = glyph_cell(character);
+			let uv_top_left = vec2(column as f32, row as f32) * GLYPH_SIZE as f32 / atlas_size;
+			let uv_size = Vec2::splat(GLYPH_SIZE as f32) / atlas_size;
+
+			let corner = |x: f32, y: f32| OverlayVertex {
+				pos: top_left + vec2(x, y) * glyph_size,
+				uv: uv_top_left + vec2(x, y) * uv_size,
+				color,
+			};
+			vertices.extend([corner(0.0, 0.0), corner(1.0, 0.0), corner(1.0, 1.0), corner(0.0, 0.0), corner(1.0, 1.0), corner(0.0, 1.0)]);
+		}
+	}
+}
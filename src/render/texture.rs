@@ -0,0 +1,261 @@
+use crate::render::memory::{GpuAllocator, Allocation, create_image};
+use crate::render::upload::Uploader;
+use crate::utility::image::decode_png;
+
+use ash::vk;
+use std::ptr;
+
+//Format every color texture gets uploaded as. Srgb so the sampler converts to linear when reading, same as the swapchain converts back when writing
+const TEXTURE_FORMAT: vk::Format = vk::Format::R8G8B8A8_SRGB;
+
+
+//Texture on the CPU side - 8 bit rgba pixels, row by row from the top left
+#[derive(Clone)]
+pub struct TextureData {
+	pub width: u32,
+	pub height: u32,
+	pub pixels: Vec<u8>,
+	pub sampler_settings: SamplerSettings, //How the texture gets filtered/wrapped when sampled
+}
+
+impl TextureData {
+	//Decodes a png (any color type gets expanded to rgba)
+	pub fn from_png(png_bytes: &[u8]) -> TextureData {
+		let (width, height, pixels) = decode_png(png_bytes);
+		TextureData::from_raw_rgba(pixels, width, height)
+	}
+
+	//Raw rgba bytes with no header, like "icon.rgba" - the size has to come from somewhere else
+	pub fn from_raw_rgba(pixels: Vec<u8>, width: u32, height: u32) -> TextureData {
+		assert!(pixels.len() == (width * height * 4) as usize, "Raw rgba texture is {} bytes, expected {} for {}x{}", pixels.len(), width * height * 4, width, height);
+
+		TextureData {
+			width,
+			height,
+			pixels,
+			sampler_settings: SamplerSettings::default(),
+		}
+	}
+
+	//1x1 white texture - for things that don't have a texture, so sampling it doesn't change the color
+	pub fn white() -> TextureData {
+		TextureData::from_raw_rgba(vec![255; 4], 1, 1)
+	}
+
+	//Number of mip levels for a full chain down to 1x1
+	pub fn full_mip_levels(&self) -> u32 {
+		self.width.max(self.height).ilog2() + 1
+	}
+}
+
+//How a texture gets sampled
+#[derive(Debug, Clone, Copy)]
+pub struct SamplerSettings {
+	pub mag_filter: vk::Filter, //Filtering when the texture is bigger on screen than it is in texels
+	pub min_filter: vk::Filter, //Filtering when the texture is smaller on screen than it is in texels
+	pub mipmap_mode: vk::SamplerMipmapMode, //Filtering between mip levels
+	pub address_mode: vk::SamplerAddressMode, //What happens outside of 0..1 uvs (repeat, clamp, etc) - same for u and v
+	pub max_anisotropy: Option<f32>, //Anisotropic filtering amount, None to turn it off. Gets clamped to what the device supports
+	pub generate_mips: bool, //Whether to make the full mip chain, or just use the full size image
+}
+
+impl Default for SamplerSettings {
+	//Trilinear + repeating + as much anisotropy as the device can do
+	fn default() -> SamplerSettings {
+		SamplerSettings {
+			mag_filter: vk::Filter::LINEAR,
+			min_filter: vk::Filter::LINEAR,
+			mipmap_mode: vk::SamplerMipmapMode::LINEAR,
+			address_mode: vk::SamplerAddressMode::REPEAT,
+			max_anisotropy: Some(16.0),
+			generate_mips: true,
+		}
+	}
+}
+
+
+//Texture that lives on the GPU - image with all its mips, a view of it, and the sampler to read it with
+//Ends up in the shader read only layout, ready to go in a combined image sampler descriptor (once it's uploaded + its mips are generated)
+pub struct Texture {
+	pub image: vk::Image,
+	pub image_allocation: Allocation,
+	pub image_view: vk::ImageView,
+	pub sampler: vk::Sampler,
+	pub extent: vk::Extent2D,
+	pub mip_levels: u32,
+	pub upload_batch: u64, //Uploader batch the pixels are in. Can't sample it until the uploader has collected this batch
+}
+
+impl Texture {
+	//Makes the image, view, and sampler, and queues up the pixels to be copied into mip 0 on the transfer queue. Doesn't wait for the copy
+	//Once the upload batch is collected, "cmd_generate_mips" has to be recorded (on the graphics queue) before it can be sampled
+	//"device_max_anisotropy" is the device's limit, 0 if anisotropic filtering isn't supported/enabled
+	pub fn upload(instance: &ash::Instance, device: &ash::Device, allocator: &mut GpuAllocator, uploader: &mut Uploader, physical_device: vk::PhysicalDevice, texture_data: &TextureData, device_max_anisotropy: f32) -> Texture {
+		let extent = vk::Extent2D {width: texture_data.width, height: texture_data.height};
+		let settings = texture_data.sampler_settings;
+
+		//Blitting with linear filtering needs format support. Pretty much everything supports it for rgba8, but if not, just don't make mips
+		let format_properties = unsafe { instance.get_physical_device_format_properties(physical_device, TEXTURE_FORMAT) };
+		let can_blit = format_properties.optimal_tiling_features.contains(vk::FormatFeatureFlags::SAMPLED_IMAGE_FILTER_LINEAR);
+		let mip_levels = match settings.generate_mips && can_blit {
+			true => texture_data.full_mip_levels(),
+			false => 1,
+		};
+
+		//Image gets copied into (transfer dst), blitted from to make the mips (transfer src), and sampled
+		let usage = vk::ImageUsageFlags::TRANSFER_SRC | vk::ImageUsageFlags::TRANSFER_DST | vk::ImageUsageFlags::SAMPLED;
+		let (image, image_allocation) = create_image(device, allocator, extent, mip_levels, TEXTURE_FORMAT, usage, vk::MemoryPropertyFlags::DEVICE_LOCAL);
+
+		let upload_batch = uploader.upload_image(device, allocator, &texture_data.pixels, image, extent, mip_levels);
+
+		//View of the whole mip chain
+		let image_view_info = vk::ImageViewCreateInfo {
+			s_type: vk::StructureType::IMAGE_VIEW_CREATE_INFO,
+			p_next: ptr::null(),
+			flags: vk::ImageViewCreateFlags::empty(),
+			image,
+			view_type: vk::ImageViewType::TYPE_2D,
+			format: TEXTURE_FORMAT,
+			components: vk::ComponentMapping::default(), //Identity swizzle
+			subresource_range: vk::ImageSubresourceRange {
+				aspect_mask: vk::ImageAspectFlags::COLOR,
+				base_mip_level: 0,
+				level_count: mip_levels,
+				base_array_layer: 0,
+				layer_count: 1,
+			},
+			..Default::default()
+		};
+		let image_view = unsafe { device.create_image_view(&image_view_info, None).expect("Failed to create texture image view") };
+
+		let sampler = create_sampler(device, &settings, mip_levels, device_max_anisotropy);
+
+		Texture {
+			image,
+			image_allocation,
+			image_view,
+			sampler,
+			extent,
+			mip_levels,
+			upload_batch,
+		}
+	}
+
+	//Makes each mip by blitting the one before it down to half size, then puts the whole thing in the shader read only layout
+	//Has to be recorded on the graphics queue (blits need it), after the upload's been acquired - every mip starts in the transfer dst layout
+	pub fn cmd_generate_mips(&self, device: &ash::Device, command_buffer: vk::CommandBuffer) {
+		let image = self.image;
+		let mip_levels = self.mip_levels;
+
+		//Each source mip gets switched to transfer src for the blit, then it's done and can go to shader read only
+		let mut mip_width = self.extent.width as i32;
+		let mut mip_height = self.extent.height as i32;
+		for mip_level in 1..mip_levels {
+			let next_width = (mip_width / 2).max(1);
+			let next_height = (mip_height / 2).max(1);
+
+			//Wait for the last mip's copy/blit to finish writing before reading from it
+			image_barrier(device, command_buffer, image, mip_level - 1, 1,
+				vk::ImageLayout::TRANSFER_DST_OPTIMAL, vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+				vk::AccessFlags::TRANSFER_WRITE, vk::AccessFlags::TRANSFER_READ,
+				vk::PipelineStageFlags::TRANSFER, vk::PipelineStageFlags::TRANSFER);
+
+			let blit_regions = [vk::ImageBlit {
+				src_subresource: vk::ImageSubresourceLayers {
+					aspect_mask: vk::ImageAspectFlags::COLOR,
+					mip_level: mip_level - 1,
+					base_array_layer: 0,
+					layer_count: 1,
+				},
+				src_offsets: [vk::Offset3D {x: 0, y: 0, z: 0}, vk::Offset3D {x: mip_width, y: mip_height, z: 1}],
+				dst_subresource: vk::ImageSubresourceLayers {
+					aspect_mask: vk::ImageAspectFlags::COLOR,
+					mip_level,
+					base_array_layer: 0,
+					layer_count: 1,
+				},
+				dst_offsets: [vk::Offset3D {x: 0, y: 0, z: 0}, vk::Offset3D {x: next_width, y: next_height, z: 1}],
+			}];
+			unsafe { device.cmd_blit_image(command_buffer, image, vk::ImageLayout::TRANSFER_SRC_OPTIMAL, image, vk::ImageLayout::TRANSFER_DST_OPTIMAL, &blit_regions, vk::Filter::LINEAR) };
+
+			//Done with the last mip
+			image_barrier(device, command_buffer, image, mip_level - 1, 1,
+				vk::ImageLayout::TRANSFER_SRC_OPTIMAL, vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+				vk::AccessFlags::TRANSFER_READ, vk::AccessFlags::SHADER_READ,
+				vk::PipelineStageFlags::TRANSFER, vk::PipelineStageFlags::FRAGMENT_SHADER);
+
+			mip_width = next_width;
+			mip_height = next_height;
+		}
+
+		//Last mip never got blitted from, so it's still transfer dst
+		image_barrier(device, command_buffer, image, mip_levels - 1, 1,
+			vk::ImageLayout::TRANSFER_DST_OPTIMAL, vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+			vk::AccessFlags::TRANSFER_WRITE, vk::AccessFlags::SHADER_READ,
+			vk::PipelineStageFlags::TRANSFER, vk::PipelineStageFlags::FRAGMENT_SHADER);
+	}
+
+	//Destroys the sampler, view, image, and frees the memory. Make sure the GPU is done with it first
+	pub fn destroy(&self, device: &ash::Device, allocator: &mut GpuAllocator) {
+		unsafe {
+			device.destroy_sampler(self.sampler, None);
+			device.destroy_image_view(self.image_view, None);
+			device.destroy_image(self.image, None);
+		}
+		allocator.free(device, &self.image_allocation);
+	}
+}
+
+//Makes a sampler from the settings. Anisotropy only gets turned on if the device supports it
+fn create_sampler(device: &ash::Device, settings: &SamplerSettings, mip_levels: u32, device_max_anisotropy: f32) -> vk::Sampler {
+	let max_anisotropy = settings.max_anisotropy.unwrap_or(0.0).min(device_max_anisotropy);
+
+	let sampler_info = vk::SamplerCreateInfo {
+		s_type: vk::StructureType::SAMPLER_CREATE_INFO,
+		p_next: ptr::null(),
+		flags: vk::SamplerCreateFlags::empty(),
+		mag_filter: settings.mag_filter,
+		min_filter: settings.min_filter,
+		mipmap_mode: settings.mipmap_mode,
+		address_mode_u: settings.address_mode,
+		address_mode_v: settings.address_mode,
+		address_mode_w: settings.address_mode,
+		mip_lod_bias: 0.0,
+		anisotropy_enable: (max_anisotropy >= 1.0) as vk::Bool32,
+		max_anisotropy: max_anisotropy.max(1.0),
+		compare_enable: vk::FALSE, //Only for shadow maps
+		compare_op: vk::CompareOp::ALWAYS,
+		min_lod: 0.0,
+		max_lod: mip_levels as f32, //Use the whole mip chain
+		border_color: vk::BorderColor::INT_OPAQUE_BLACK, //Only matters for clamp to border
+		unnormalized_coordinates: vk::FALSE, //Uvs are 0..1
+		..Default::default()
+	};
+
+	unsafe { device.create_sampler(&sampler_info, None).expect("Failed to create sampler") }
+}
+
+//Records a layout transition for some of the mips of a color image
+fn image_barrier(device: &ash::Device, command_buffer: vk::CommandBuffer, image: vk::Image, base_mip_level: u32, level_count: u32, old_layout: vk::ImageLayout, new_layout: vk::ImageLayout, src_access_mask: vk::AccessFlags, dst_access_mask: vk::AccessFlags, src_stage_mask: vk::PipelineStageFlags, dst_stage_mask: vk::PipelineStageFlags) {
+	let barrier = vk::ImageMemoryBarrier {
+		s_type: vk::StructureType::IMAGE_MEMORY_BARRIER,
+		p_next: ptr::null(),
+		src_access_mask,
+		dst_access_mask,
+		old_layout,
+		new_layout,
+		src_queue_family_index: vk::QUEUE_FAMILY_IGNORED, //Not transferring queue family ownership
+		dst_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
+		image,
+		subresource_range: vk::ImageSubresourceRange {
+			aspect_mask: vk::ImageAspectFlags::COLOR,
+			base_mip_level,
+			level_count,
+			base_array_layer: 0,
+			layer_count: 1,
+		},
+		..Default::default()
+	};
+
+	unsafe { device.cmd_pipeline_barrier(command_buffer, src_stage_mask, dst_stage_mask, vk::DependencyFlags::empty(), &[], &[], &[barrier]) };
+}
@@ -0,0 +1,475 @@
+use crate::constants::STAGING_RING_SIZE;
+use crate::render::memory::{GpuAllocator, Allocation, create_buffer};
+
+use ash::vk;
+use std::collections::VecDeque;
+use std::ptr;
+
+//Where staging data starts in the ring. Covers the texel size of any format + the optimal copy offset alignment on pretty much everything
+const STAGING_ALIGNMENT: vk::DeviceSize = 16;
+
+//Something that got uploaded and needs to be acquired by the graphics queue before it's used
+//When the transfer queue is its own family, it "releases" ownership after copying and the graphics queue has to "acquire" it with a matching barrier
+//When it's the same family, the acquire is just a normal barrier that makes the copy visible
+#[derive(Debug, Clone, Copy)]
+pub enum Acquire {
+	Buffer {
+		buffer: vk::Buffer,
+		dst_access_mask: vk::AccessFlags, //How the graphics queue uses it (vertex attribute read, index read, etc)
+		dst_stage_mask: vk::PipelineStageFlags,
+	},
+	//Images are acquired with every mip in the transfer dst layout, ready for mips to be generated (which needs the graphics queue)
+	Image {
+		image: vk::Image,
+		mip_levels: u32,
+	},
+}
+
+//Batch of uploads the transfer queue is done with
+pub struct FinishedBatch {
+	pub serial: u64, //Resources that were uploaded in this batch have this as their "upload_batch"
+	pub acquires: Vec<Acquire>,
+}
+
+//A bunch of copies recorded into one command buffer and submitted together
+struct UploadBatch {
+	serial: u64,
+	command_buffer: vk::CommandBuffer,
+	fence: vk::Fence, //Signaled when the transfer queue is done with it
+	staging_end: vk::DeviceSize, //Where the ring's head was after this batch - everything before it in the ring is free once the batch finishes
+	oversized_staging: Vec<(vk::Buffer, Allocation)>, //One off staging buffers for uploads too big for the ring
+	acquires: Vec<Acquire>,
+}
+
+//Asynchronous uploader - copies data into device local buffers/images on the transfer queue without blocking rendering
+//Data gets written into a host visible staging ring, copies get recorded into a batch, and the batch gets submitted with a fence
+//Nothing waits on the fence - "collect_finished" checks which batches are done every frame, and the renderer acquires those resources + starts using them
+//Uses a dedicated transfer queue family if the device has one (usually DMA hardware that runs alongside the graphics queue), otherwise the graphics family
+pub struct Uploader {
+	transfer_queue: vk::Queue,
+	transfer_family: u32,
+	graphics_family: u32,
+	command_pool: vk::CommandPool, //On the transfer family
+
+	staging_buffer: vk::Buffer, //The staging ring
+	staging_allocation: Allocation,
+	staging_head: vk::DeviceSize, //Where the next staging write goes
+	staging_tail: vk::DeviceSize, //Start of the oldest staging data a batch is still using
+
+	open_batch: Option<UploadBatch>, //Batch being recorded into - gets submitted by "submit"
+	in_flight: VecDeque<UploadBatch>, //Submitted batches, oldest first. They finish in order since it's all one queue
+	finished: Vec<FinishedBatch>, //Batches that are done but haven't been collected yet
+	next_serial: u64,
+	collected_serial: u64, //Last batch handed out by "collect_finished" - everything up to it has been acquired
+}
+
+impl Uploader {
+	//The staging ring + the transfer command pool, for giving them debug names
+	pub fn staging_buffer(&self) -> vk::Buffer {
+		self.staging_buffer
+	}
+	pub fn command_pool(&self) -> vk::CommandPool {
+		self.command_pool
+	}
+
+	pub fn new(device: &ash::Device, allocator: &mut GpuAllocator, transfer_queue: vk::Queue, transfer_family: u32, graphics_family: u32) -> Uploader {
+		//Command buffers are one per batch, and get freed individually once their batch is done
+		let command_pool_info = vk::CommandPoolCreateInfo {
+			s_type: vk::StructureType::COMMAND_POOL_CREATE_INFO,
+			p_next: ptr::null(),
+			flags: vk::CommandPoolCreateFlags::TRANSIENT | vk::CommandPoolCreateFlags::RESET_COMMAND_BUFFER,
+			queue_family_index: transfer_family,
+			..Default::default()
+		};
+		let command_pool = unsafe { device.create_command_pool(&command_pool_info, None).expect("Failed to create upload command pool") };
+
+		let (staging_buffer, staging_allocation) = create_buffer(device, allocator, STAGING_RING_SIZE, vk::BufferUsageFlags::TRANSFER_SRC, vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT);
+
+		Uploader {
+			transfer_queue,
+			transfer_family,
+			graphics_family,
+			command_pool,
+
+			staging_buffer,
+			staging_allocation,
+			staging_head: 0,
+			staging_tail: 0,
+
+			open_batch: None,
+			in_flight: VecDeque::new(),
+			finished: vec![],
+			next_serial: 1,
+			collected_serial: 0,
+		}
+	}
+
+	//Queues up a copy of "data" into the start of a device local buffer. The buffer needs transfer dst usage
+	//"dst_access_mask"/"dst_stage_mask" are how the graphics queue is going to use it
+	//Returns the batch it's in - the buffer is usable once that batch has been collected
+	pub fn upload_buffer<T: Copy>(&mut self, device: &ash::Device, allocator: &mut GpuAllocator, data: &[T], dst_buffer: vk::Buffer, dst_access_mask: vk::AccessFlags, dst_stage_mask: vk::PipelineStageFlags) -> u64 {
+		let size = core::mem::size_of_val(data) as vk::DeviceSize;
+		let (src_buffer, src_offset) = self.stage(device, allocator, data);
+		let (transfer_family, graphics_family) = (self.transfer_family, self.graphics_family);
+		let batch = self.open_batch(device);
+
+		let copy_regions = [vk::BufferCopy {
+			src_offset,
+			dst_offset: 0,
+			size,
+		}];
+		unsafe { device.cmd_copy_buffer(batch.command_buffer, src_buffer, dst_buffer, &copy_regions) };
+
+		//Hand the buffer over to the graphics family (if it's a different one)
+		if transfer_family != graphics_family {
+			let release_barrier = vk::BufferMemoryBarrier {
+				s_type: vk::StructureType::BUFFER_MEMORY_BARRIER,
+				p_next: ptr::null(),
+				src_access_mask: vk::AccessFlags::TRANSFER_WRITE,
+				dst_access_mask: vk::AccessFlags::empty(), //Ignored for a release
+				src_queue_family_index: transfer_family,
+				dst_queue_family_index: graphics_family,
+				buffer: dst_buffer,
+				offset: 0,
+				size: vk::WHOLE_SIZE,
+				..Default::default()
+			};
+			unsafe { device.cmd_pipeline_barrier(batch.command_buffer, vk::PipelineStageFlags::TRANSFER, vk::PipelineStageFlags::BOTTOM_OF_PIPE, vk::DependencyFlags::empty(), &[], &[release_barrier], &[]) };
+		}
+
+		batch.acquires.push(Acquire::Buffer {buffer: dst_buffer, dst_access_mask, dst_stage_mask});
+		batch.serial
+	}
+
+	//Queues up a copy of tightly packed pixels into mip 0 of a 2d color image. The image needs transfer dst usage
+	//Every mip gets put in the transfer dst layout. The graphics queue takes it from there (generating mips + transitioning for sampling)
+	//Returns the batch it's in, same as "upload_buffer"
+	pub fn upload_image(&mut self, device: &ash::Device, allocator: &mut GpuAllocator, pixels: &[u8], dst_image: vk::Image, extent: vk::Extent2D, mip_levels: u32) -> u64 {
+		let (src_buffer, src_offset) = self.stage(device, allocator, pixels);
+		let (transfer_family, graphics_family) = (self.transfer_family, self.graphics_family);
+		let batch = self.open_batch(device);
+
+		let subresource_range = vk::ImageSubresourceRange {
+			aspect_mask: vk::ImageAspectFlags::COLOR,
+			base_mip_level: 0,
+			level_count: mip_levels,
+			base_array_layer: 0,
+			layer_count: 1,
+		};
+
+		//Every mip starts out undefined - get them all ready to be written to
+		let to_transfer_barrier = vk::ImageMemoryBarrier {
+			s_type: vk::StructureType::IMAGE_MEMORY_BARRIER,
+			p_next: ptr::null(),
+			src_access_mask: vk::AccessFlags::empty(),
+			dst_access_mask: vk::AccessFlags::TRANSFER_WRITE,
+			old_layout: vk::ImageLayout::UNDEFINED,
+			new_layout: vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+			src_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
+			dst_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
+			image: dst_image,
+			subresource_range,
+			..Default::default()
+		};
+		unsafe { device.cmd_pipeline_barrier(batch.command_buffer, vk::PipelineStageFlags::TOP_OF_PIPE, vk::PipelineStageFlags::TRANSFER, vk::DependencyFlags::empty(), &[], &[], &[to_transfer_barrier]) };
+
+		//Copy the pixels into mip 0. Row length/image height of 0 means tightly packed
+		let copy_regions = [vk::BufferImageCopy {
+			buffer_offset: src_offset,
+			buffer_row_length: 0,
+			buffer_image_height: 0,
+			image_subresource: vk::ImageSubresourceLayers {
+				aspect_mask: vk::ImageAspectFlags::COLOR,
+				mip_level: 0,
+				base_array_layer: 0,
+				layer_count: 1,
+			},
+			image_offset: vk::Offset3D {x: 0, y: 0, z: 0},
+			image_extent: vk::Extent3D {width: extent.width, height: extent.height, depth: 1},
+		}];
+		unsafe { device.cmd_copy_buffer_to_image(batch.command_buffer, src_buffer, dst_image, vk::ImageLayout::TRANSFER_DST_OPTIMAL, &copy_regions) };
+
+		//Hand the image over to the graphics family, layout stays the same
+		if transfer_family != graphics_family {
+			let release_barrier = vk::ImageMemoryBarrier {
+				s_type: vk::StructureType::IMAGE_MEMORY_BARRIER,
+				p_next: ptr::null(),
+				src_access_mask: vk::AccessFlags::TRANSFER_WRITE,
+				dst_access_mask: vk::AccessFlags::empty(), //Ignored for a release
+				old_layout: vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+				new_layout: vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+				src_queue_family_index: transfer_family,
+				dst_queue_family_index: graphics_family,
+				image: dst_image,
+				subresource_range,
+				..Default::default()
+			};
+			unsafe { device.cmd_pipeline_barrier(batch.command_buffer, vk::PipelineStageFlags::TRANSFER, vk::PipelineStageFlags::BOTTOM_OF_PIPE, vk::DependencyFlags::empty(), &[], &[], &[release_barrier]) };
+		}
+
+		batch.acquires.push(Acquire::Image {image: dst_image, mip_levels});
+		batch.serial
+	}
+
+	//Submits whatever's been queued up since the last submit. Doesn't wait for it
+	pub fn submit(&mut self, device: &ash::Device) {
+		let Some(mut batch) = self.open_batch.take() else { return };
+		batch.staging_end = self.staging_head;
+
+		unsafe { device.end_command_buffer(batch.command_buffer).expect("Failed to record upload command buffer") };
+
+		let submit_infos = [vk::SubmitInfo {
+			s_type: vk::StructureType::SUBMIT_INFO,
+			p_next: ptr::null(),
+			wait_semaphore_count: 0,
+			p_wait_semaphores: ptr::null(),
+			p_wait_dst_stage_mask: ptr::null(),
+			command_buffer_count: 1,
+			p_command_buffers: &batch.command_buffer,
+			signal_semaphore_count: 0,
+			p_signal_semaphores: ptr::null(),
+			..Default::default()
+		}];
+		unsafe { device.queue_submit(self.transfer_queue, &submit_infos, batch.fence).expect("Failed to submit upload command buffer") };
+
+		self.in_flight.push_back(batch);
+	}
+
+	//Hands back every batch that's finished since the last call, oldest first. Never blocks
+	//The caller has to record the acquires (see "cmd_acquire") on the graphics queue before using anything in them
+	pub fn collect_finished(&mut self, device: &ash::Device, allocator: &mut GpuAllocator) -> Vec<FinishedBatch> {
+		while let Some(batch) = self.in_flight.front() {
+			let is_done = unsafe { device.get_fence_status(batch.fence).expect("Failed to get upload fence status") };
+			if !is_done {
+				break
+			}
+			self.retire_oldest(device, allocator);
+		}
+
+		let finished = std::mem::take(&mut self.finished);
+		if let Some(last) = finished.last() {
+			self.collected_serial = last.serial;
+		}
+		finished
+	}
+
+	//Records the graphics queue's half of the ownership transfer for some uploads (or just a barrier, if it's all one family)
+	//Goes in a graphics command buffer before anything uses the resources
+	pub fn cmd_acquire(&self, device: &ash::Device, command_buffer: vk::CommandBuffer, acquires: &[Acquire]) {
+		//Same family - no ownership to transfer, but the copies still have to be made visible
+		let (src_queue_family_index, dst_queue_family_index, src_access_mask) = match self.transfer_family == self.graphics_family {
+			true => (vk::QUEUE_FAMILY_IGNORED, vk::QUEUE_FAMILY_IGNORED, vk::AccessFlags::TRANSFER_WRITE),
+			false => (self.transfer_family, self.graphics_family, vk::AccessFlags::empty()), //Src access is ignored for an acquire
+		};
+
+		for acquire in acquires {
+			match *acquire {
+				Acquire::Buffer {buffer, dst_access_mask, dst_stage_mask} => {
+					let acquire_barrier = vk::BufferMemoryBarrier {
+						s_type: vk::StructureType::BUFFER_MEMORY_BARRIER,
+						p_next: ptr::null(),
+						src_access_mask,
+						dst_access_mask,
+						src_queue_family_index,
+						dst_queue_family_index,
+						buffer,
+						offset: 0,
+						size: vk::WHOLE_SIZE,
+						..Default::default()
+					};
+					unsafe { device.cmd_pipeline_barrier(command_buffer, vk::PipelineStageFlags::TRANSFER, dst_stage_mask, vk::DependencyFlags::empty(), &[], &[acquire_barrier], &[]) };
+				},
+				Acquire::Image {image, mip_levels} => {
+					let acquire_barrier = vk::ImageMemoryBarrier {
+						s_type: vk::StructureType::IMAGE_MEMORY_BARRIER,
+						p_next: ptr::null(),
+						src_access_mask,
+						dst_access_mask: vk::AccessFlags::TRANSFER_READ | vk::AccessFlags::TRANSFER_WRITE, //Mip generation reads + writes it next
+						old_layout: vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+						new_layout: vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+						src_queue_family_index,
+						dst_queue_family_index,
+						image,
+						subresource_range: vk::ImageSubresourceRange {
+							aspect_mask: vk::ImageAspectFlags::COLOR,
+							base_mip_level: 0,
+							level_count: mip_levels,
+							base_array_layer: 0,
+							layer_count: 1,
+						},
+						..Default::default()
+					};
+					unsafe { device.cmd_pipeline_barrier(command_buffer, vk::PipelineStageFlags::TRANSFER, vk::PipelineStageFlags::TRANSFER, vk::DependencyFlags::empty(), &[], &[], &[acquire_barrier]) };
+				},
+			}
+		}
+	}
+
+	//Whether everything from a batch has been collected (and so acquired) yet
+	pub fn is_collected(&self, serial: u64) -> bool {
+		serial <= self.collected_serial
+	}
+
+	//Last batch handed out by "collect_finished"
+	pub fn collected_serial(&self) -> u64 {
+		self.collected_serial
+	}
+
+	//Submits anything queued and blocks until the transfer queue is done with all of it
+	//For loading screens/startup, where there's nothing to draw until it's all there anyway. Still needs collecting after
+	pub fn wait_idle(&mut self, device: &ash::Device, allocator: &mut GpuAllocator) {
+		self.submit(device);
+		while !self.in_flight.is_empty() {
+			self.wait_for_oldest(device, allocator);
+		}
+	}
+
+	//Waits for everything, then destroys the staging ring + command pool
+	pub fn destroy(&mut self, device: &ash::Device, allocator: &mut GpuAllocator) {
+		self.wait_idle(device, allocator);
+		unsafe {
+			device.destroy_buffer(self.staging_buffer, None);
+			device.destroy_command_pool(self.command_pool, None);
+		}
+		allocator.free(device, &self.staging_allocation);
+	}
+
+	//Batch being recorded into, starting a new one if there isn't one
+	fn open_batch(&mut self, device: &ash::Device) -> &mut UploadBatch {
+		if self.open_batch.is_none() {
+			let command_buffer_info = vk::CommandBufferAllocateInfo {
+				s_type: vk::StructureType::COMMAND_BUFFER_ALLOCATE_INFO,
+				p_next: ptr::null(),
+				command_pool: self.command_pool,
+				level: vk::CommandBufferLevel::PRIMARY,
+				command_buffer_count: 1,
+				..Default::default()
+			};
+			let command_buffer = unsafe { device.allocate_command_buffers(&command_buffer_info).expect("Failed to allocate upload command buffer")[0] };
+
+			let command_buffer_begin_info = vk::CommandBufferBeginInfo {
+				s_type: vk::StructureType::COMMAND_BUFFER_BEGIN_INFO,
+				p_next: ptr::null(),
+				flags: vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT,
+				p_inheritance_info: ptr::null(),
+				..Default::default()
+			};
+			unsafe { device.begin_command_buffer(command_buffer, &command_buffer_begin_info).expect("Failed to begin recording upload command buffer") };
+
+			let fence_info = vk::FenceCreateInfo {
+				s_type: vk::StructureType::FENCE_CREATE_INFO,
+				p_next: ptr::null(),
+				flags: vk::FenceCreateFlags::empty(),
+				..Default::default()
+			};
+			let fence = unsafe { device.create_fence(&fence_info, None).expect("Failed to create upload fence") };
+
+			self.open_batch = Some(UploadBatch {
+				serial: self.next_serial,
+				command_buffer,
+				fence,
+				staging_end: 0, //Filled in on submit
+				oversized_staging: vec![],
+				acquires: vec![],
+			});
+			self.next_serial += 1;
+		}
+
+		self.open_batch.as_mut().unwrap()
+	}
+
+	//Copies data somewhere the transfer queue can copy it from - returns the buffer + offset it's at
+	//Normally that's the staging ring. If the ring's full, older batches get waited on until there's room
+	//Anything bigger than the whole ring gets its own staging buffer, freed along with its batch
+	fn stage<T: Copy>(&mut self, device: &ash::Device, allocator: &mut GpuAllocator, data: &[T]) -> (vk::Buffer, vk::DeviceSize) {
+		let size = core::mem::size_of_val(data) as vk::DeviceSize;
+
+		let offset = loop {
+			if let Some(offset) = self.reserve_staging(size) {
+				break Some(offset)
+			}
+			//Out of room - the open batch's staging data can't be freed until it's submitted, so submit it, then wait for the oldest batch
+			self.submit(device);
+			if self.in_flight.is_empty() {
+				break None //Ring is completely empty and it still doesn't fit
+			}
+			self.wait_for_oldest(device, allocator);
+		};
+
+		match offset {
+			Some(offset) => {
+				unsafe {
+					let p_destination = self.staging_allocation.mapped_pointer::<u8>().add(offset as usize) as *mut T;
+					ptr::copy_nonoverlapping(data.as_ptr(), p_destination, data.len());
+				}
+				(self.staging_buffer, offset)
+			},
+			None => {
+				let (buffer, allocation) = create_buffer(device, allocator, size, vk::BufferUsageFlags::TRANSFER_SRC, vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT);
+				unsafe { ptr::copy_nonoverlapping(data.as_ptr(), allocation.mapped_pointer::<T>(), data.len()) };
+				self.open_batch(device).oversized_staging.push((buffer, allocation));
+				(buffer, 0)
+			},
+		}
+	}
+
+	//Takes a chunk of the staging ring, if there's room
+	//Free space is from the head around to the tail. The head never catches up to the tail from behind, so head == tail always means empty
+	fn reserve_staging(&mut self, size: vk::DeviceSize) -> Option<vk::DeviceSize> {
+		let start = self.staging_head.next_multiple_of(STAGING_ALIGNMENT);
+
+		let offset = if self.staging_head >= self.staging_tail {
+			//Free space is the end of the ring + the start of the ring up to the tail
+			if start + size <= STAGING_RING_SIZE {
+				start
+			} else if size < self.staging_tail {
+				0 //Wrap around
+			} else {
+				return None
+			}
+		} else {
+			//Free space is just between the head and the tail
+			if start + size < self.staging_tail {
+				start
+			} else {
+				return None
+			}
+		};
+
+		self.staging_head = offset + size;
+		Some(offset)
+	}
+
+	//Blocks until the oldest submitted batch is done, then retires it
+	fn wait_for_oldest(&mut self, device: &ash::Device, allocator: &mut GpuAllocator) {
+		let fence = self.in_flight.front().expect("No upload batches in flight").fence;
+		unsafe { device.wait_for_fences(&[fence], true, u64::MAX).expect("Failed to wait for upload fence") };
+		self.retire_oldest(device, allocator);
+	}
+
+	//Cleans up the oldest batch (has to be done already) - frees its staging space, command buffer, and fence, and moves its acquires to "finished"
+	fn retire_oldest(&mut self, device: &ash::Device, allocator: &mut GpuAllocator) {
+		let batch = self.in_flight.pop_front().expect("No upload batches in flight");
+
+		self.staging_tail = batch.staging_end;
+		//Nothing left using the ring - start back at the beginning so uploads don't get split around the end for no reason
+		if self.in_flight.is_empty() && self.open_batch.is_none() {
+			self.staging_head = 0;
+			self.staging_tail = 0;
+		}
+
+		unsafe {
+			device.free_command_buffers(self.command_pool, &[batch.command_buffer]);
+			device.destroy_fence(batch.fence, None);
+		}
+		for (buffer, allocation) in &batch.oversized_staging {
+			unsafe { device.destroy_buffer(*buffer, None) };
+			allocator.free(device, allocation);
+		}
+
+		self.finished.push(FinishedBatch {
+			serial: batch.serial,
+			acquires: batch.acquires,
+		});
+	}
+}
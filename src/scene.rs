@@ -1,62 +1,303 @@
+pub mod bounds;
 pub mod camera;
+pub mod debug_draw;
+pub mod hud;
 pub mod entities;
+pub mod transform;
 
+use crate::scene::bounds::{Aabb, Frustum};
 use crate::scene::camera::Camera;
+use crate::scene::debug_draw::{DebugDraw, YELLOW, WHITE};
+use crate::scene::hud::Hud;
 use crate::scene::entities::Player;
-use crate::render::Vertex;
+use crate::scene::transform::Transform;
+use crate::render::{Vertex, InstanceData, FrameUniforms};
+use crate::render::mesh::MeshData;
+use crate::render::texture::TextureData;
+use crate::utility::{read, gltf};
+use crate::constants::{FPS, DEBUG_VELOCITY_ARROW_SCALE};
 
-use glam::f32::{vec3, vec4};
+use glam::f32::{vec3, vec4, Mat4, Vec2, Vec3, Vec4};
+use std::collections::{BTreeMap, HashMap};
 
 //Make the vertices for a test triangle
+//No normals, so they don't get shaded
 pub const TEST_TRIANGLE_VERTICES: [Vertex; 9] = [
-	Vertex {pos: vec4(  20.0,100.0,  0.0,  1.0), color: vec3(1.0, 0.0, 0.0)},
-	Vertex {pos: vec4(  20.0, -5.0, -5.0,  1.0), color: vec3(0.0, 1.0, 0.0)},
-	Vertex {pos: vec4(  20.0, -5.0,  13.0,  1.0), color: vec3(0.0, 0.0, 1.0)},
+	Vertex {pos: vec4(  20.0,100.0,  0.0,  1.0), color: vec3(1.0, 0.0, 0.0), normal: Vec3::ZERO, uv: Vec2::ZERO},
+	Vertex {pos: vec4(  20.0, -5.0, -5.0,  1.0), color: vec3(0.0, 1.0, 0.0), normal: Vec3::ZERO, uv: Vec2::ZERO},
+	Vertex {pos: vec4(  20.0, -5.0,  13.0,  1.0), color: vec3(0.0, 0.0, 1.0), normal: Vec3::ZERO, uv: Vec2::ZERO},
 
-	Vertex {pos: vec4(  20.0,100.0,  0.0,  1.0), color: vec3(1.0, 0.0, 0.0)},
-	Vertex {pos: vec4(  20.0, -5.0,  13.0, 1.0), color: vec3(0.0, 1.0, 0.0)},
-	Vertex {pos: vec4(  20.0, -5.0, -5.0,  1.0), color: vec3(0.0, 0.0, 1.0)},
+	Vertex {pos: vec4(  20.0,100.0,  0.0,  1.0), color: vec3(1.0, 0.0, 0.0), normal: Vec3::ZERO, uv: Vec2::ZERO},
+	Vertex {pos: vec4(  20.0, -5.0,  13.0, 1.0), color: vec3(0.0, 1.0, 0.0), normal: Vec3::ZERO, uv: Vec2::ZERO},
+	Vertex {pos: vec4(  20.0, -5.0, -5.0,  1.0), color: vec3(0.0, 0.0, 1.0), normal: Vec3::ZERO, uv: Vec2::ZERO},
 
-	Vertex {pos: vec4(  20.0, -5.0,  10.0,  1.0), color: vec3(1.0, 1.0, 1.0)},
-	Vertex {pos: vec4(  20.0,-10.0, -5.0,  1.0), color: vec3(0.0, 0.0, 0.0)},
-	Vertex {pos: vec4(  20.0,-10.0,  5.0,  1.0), color: vec3(0.0, 0.0, 0.0)},
+	Vertex {pos: vec4(  20.0, -5.0,  10.0,  1.0), color: vec3(1.0, 1.0, 1.0), normal: Vec3::ZERO, uv: Vec2::ZERO},
+	Vertex {pos: vec4(  20.0,-10.0, -5.0,  1.0), color: vec3(0.0, 0.0, 0.0), normal: Vec3::ZERO, uv: Vec2::ZERO},
+	Vertex {pos: vec4(  20.0,-10.0,  5.0,  1.0), color: vec3(0.0, 0.0, 0.0), normal: Vec3::ZERO, uv: Vec2::ZERO},
 ];
 
+//Something in the world that gets drawn - a mesh with a transform
+pub struct SceneObject {
+	pub name: String,
+	pub transform: Transform, //Model to world - change this to move the object around
+	pub mesh: usize, //Index into the scene's mesh list
+	pub texture: usize, //Index into the scene's texture list - WHITE_TEXTURE if it's untextured
+	pub tint: Vec4, //Multiplied into the object's color - white (the default) leaves it alone
+}
+
+//Something in the world whose geometry changes every frame (particles, animated props, etc)
+//Instead of being uploaded once like a mesh, the geometry gets streamed to the GPU every frame - just change "mesh_data" and the next frame shows it
+pub struct DynamicObject {
+	pub name: String,
+	pub transform: Transform,
+	pub mesh_data: MeshData,
+	pub texture: usize,
+	pub tint: Vec4,
+}
+
+//All the objects sharing a mesh + texture, drawn together in one instanced draw
+pub struct InstanceBatch {
+	pub mesh: usize,
+	pub texture: usize,
+	pub instances: Vec<InstanceData>, //One for each object in the batch
+}
+
+//How many objects made it past frustum culling in a frame, for debugging
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CullStats {
+	pub drawn: usize,
+	pub culled: usize,
+}
+
+impl std::fmt::Display for CullStats {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		write!(f, "{} drawn, {} culled ({} total)", self.drawn, self.culled, self.drawn + self.culled)
+	}
+}
+
+//Every scene starts with a plain white texture, for objects that don't have one
+pub const WHITE_TEXTURE: usize = 0;
+
 //Scene with all the stuff in it
 pub struct Scene {
 	pub camera: Camera,
 	pub player: Player,
+
+	pub light_dir: Vec3, //Direction the sun comes from - doesn't need to be normalized
+
+	pub meshes: Vec<MeshData>, //Geometry for the objects. Only ever gets added to, so indices stay valid (the renderer uploads new ones with "upload_scene_assets")
+	pub mesh_bounds: Vec<Aabb>, //Model space bounding box of each mesh, worked out when it's added. Same indices as "meshes"
+	pub textures: Vec<TextureData>, //Textures for the objects. Same deal as meshes - only added to, uploaded with "upload_scene_assets"
+	pub objects: Vec<SceneObject>, //Everything with static geometry that gets drawn
+	pub dynamic_objects: Vec<DynamicObject>, //Everything with geometry that changes frame to frame
+
+	pub debug_draw: DebugDraw, //Debug lines/shapes drawn on top of everything. Add to it from anywhere
+	pub show_debug_shapes: bool, //Draws the player's velocity + every object's bounding box each update
+	pub hud: Hud, //Text drawn over the screen - stats and messages
 }
 
 impl Scene {
 	//Right now, doesn't really do much
 	pub fn init_scene() -> Scene {
+		//Start empty, then load the level
+		let mut scene = Scene::empty();
+		scene.load_gltf(&read::sample_scene_asset());
+		//Keep the test triangles around too
+		scene.add_test_triangles();
+
+		//Return the initialized scene
+		scene
+	}
+
+	//Scene with nothing in it but the white texture, with the camera + player at the spawn point
+	pub fn empty() -> Scene {
 		//Create the camera by passing in the camera pos and target
 		let camera = Camera::new(vec3(0.0, 0.0, 10.0), vec3(20.0, 100.0, 0.0));
 		//Create the player by passing in pos
 		let player = Player::new(vec3(0.0, 0.0, 10.0));
-		
-		//Return the initialized scene
+
 		Scene {
 			camera,
 			player,
+
+			light_dir: vec3(0.4, 1.0, 0.6),
+
+			meshes: vec![],
+			mesh_bounds: vec![],
+			textures: vec![TextureData::white()], //WHITE_TEXTURE
+			objects: vec![],
+			dynamic_objects: vec![],
+
+			debug_draw: DebugDraw::new(),
+			show_debug_shapes: false,
+			hud: Hud::new(),
+		}
+	}
+
+	//Adds the test triangles as one object. They're already in world space
+	pub fn add_test_triangles(&mut self) {
+		let test_triangles_mesh = self.add_mesh(MeshData {
+			vertices: TEST_TRIANGLE_VERTICES.to_vec(),
+			indices: (0..TEST_TRIANGLE_VERTICES.len() as u32).collect(),
+		});
+		self.add_object("test_triangles", Transform::IDENTITY, test_triangles_mesh, WHITE_TEXTURE);
+	}
+
+	//Adds a mesh to the scene, returns its index for scene objects to use
+	pub fn add_mesh(&mut self, mesh_data: MeshData) -> usize {
+		self.mesh_bounds.push(mesh_data.bounds());
+		self.meshes.push(mesh_data);
+		self.meshes.len() - 1
+	}
+
+	//Adds a texture to the scene, returns its index for scene objects to use
+	pub fn add_texture(&mut self, texture_data: TextureData) -> usize {
+		self.textures.push(texture_data);
+		self.textures.len() - 1
+	}
+
+	//Places a mesh in the world with a texture on it. The same mesh can be placed any number of times
+	pub fn add_object(&mut self, name: &str, transform: Transform, mesh: usize, texture: usize) {
+		self.objects.push(SceneObject {
+			name: String::from(name),
+			transform,
+			mesh,
+			texture,
+			tint: Vec4::ONE,
+		});
+	}
+
+	//Adds an object with dynamic geometry, returns its index in "dynamic_objects" so its geometry can be changed later
+	pub fn add_dynamic_object(&mut self, name: &str, transform: Transform, mesh_data: MeshData, texture: usize) -> usize {
+		self.dynamic_objects.push(DynamicObject {
+			name: String::from(name),
+			transform,
+			mesh_data,
+			texture,
+			tint: Vec4::ONE,
+		});
+		self.dynamic_objects.len() - 1
+	}
+
+	//Adds everything in a glb file to the scene - one mesh + object for each primitive (each material gets its own)
+	//The material's base color becomes the vertex color, and the vertex shader shades it with the normals
+	//Nodes that place the same glTF mesh share the scene meshes too, so all the copies get drawn instanced
+	pub fn load_gltf(&mut self, glb_bytes: &[u8]) {
+		let mut loaded_meshes: HashMap<usize, Vec<usize>> = HashMap::new(); //glTF mesh index -> scene mesh for each of its primitives
+		for gltf_object in gltf::load_glb(glb_bytes) {
+			let transform = Transform::from_matrix(gltf_object.transform);
+
+			let meshes = loaded_meshes.entry(gltf_object.mesh_index).or_insert_with(|| {
+				gltf_object.primitives.iter().map(|primitive| {
+					let vertices = primitive.positions
+						.iter()
+						.zip(&primitive.normals)
+						.zip(&primitive.uvs)
+						.map(|((&pos, &normal), &uv)| Vertex::new(pos.extend(1.0), primitive.base_color.truncate(), normal, uv))
+						.collect();
+
+					self.add_mesh(MeshData {
+						vertices,
+						indices: primitive.indices.clone(),
+					})
+				}).collect()
+			}).clone();
+
+			for (i, &mesh) in meshes.iter().enumerate() {
+				//Multiple primitives in one mesh get numbered
+				let name = match i {
+					0 => gltf_object.name.clone(),
+					_ => format!("{}.{}", gltf_object.name, i),
+				};
+				self.add_object(&name, transform, mesh, WHITE_TEXTURE);
+			}
 		}
 	}
 
-	//Get the result from the camera's "render_matrix" fn
-	pub fn get_render_matrix_bytes(&self) -> [u8; 64] {
-		let render_matrix = self.camera.get_render_matrix();
+	//Uniforms for the frame - the camera's "render_matrix" + lighting
+	pub fn get_frame_uniforms(&self) -> FrameUniforms {
+		FrameUniforms {
+			view_projection: self.camera.get_render_matrix(),
+			light_dir: self.light_dir.extend(0.0),
+		}
+	}
 
-		//Transmute is a little evil but I don't care
-		unsafe { std::mem::transmute::<glam::f32::Mat4, [u8; 64]>(render_matrix) }
+	//Instance data for drawing an object (static or dynamic) - the object's model matrix + tint
+	pub fn get_instance_data(&self, transform: Transform, tint: Vec4) -> InstanceData {
+		InstanceData {
+			model: transform.to_matrix(),
+			tint,
+		}
+	}
+
+	//Groups the static objects the camera can see by mesh + texture, so every copy of a mesh can go out in one draw
+	//Anything fully outside the camera's frustum gets left out and counted in "cull_stats"
+	//Batches come out sorted by mesh then texture, so draw order stays the same from frame to frame
+	pub fn get_instance_batches(&self, frustum: &Frustum, cull_stats: &mut CullStats) -> Vec<InstanceBatch> {
+		let mut batches: BTreeMap<(usize, usize), Vec<InstanceData>> = BTreeMap::new();
+		for object in &self.objects {
+			let model = object.transform.to_matrix();
+			if !frustum.intersects_aabb(&self.mesh_bounds[object.mesh].transformed(model)) {
+				cull_stats.culled += 1;
+				continue
+			}
+			cull_stats.drawn += 1;
+
+			batches.entry((object.mesh, object.texture)).or_default().push(self.get_instance_data(object.transform, object.tint));
+		}
+
+		batches.into_iter().map(|((mesh, texture), instances)| InstanceBatch {mesh, texture, instances}).collect()
+	}
+
+	//Dynamic objects the camera can see. Their geometry can change every frame, so the bounds get worked out fresh each time
+	pub fn get_visible_dynamic_objects(&self, frustum: &Frustum, cull_stats: &mut CullStats) -> Vec<&DynamicObject> {
+		let (visible, culled): (Vec<&DynamicObject>, Vec<&DynamicObject>) = self.dynamic_objects
+			.iter()
+			.partition(|object| frustum.intersects_aabb(&object.mesh_data.bounds().transformed(object.transform.to_matrix())));
+
+		cull_stats.drawn += visible.len();
+		cull_stats.culled += culled.len();
+		visible
 	}
 
 	//Update scene physics. This will be called per timestep (with some timestep/frame jank involved)
 	pub fn update(&mut self) {
+		//Last timestep's debug lines are done, unless they were given a lifetime
+		self.debug_draw.tick(1.0 / FPS);
+		self.hud.tick(1.0 / FPS);
+
 		//Update the player
 		self.player.update();
 		//Glue the camera to the player
 		let player_pos = self.player.get_pos();
 		self.camera.set_pos(player_pos.x, player_pos.y, player_pos.z);
+
+		if self.show_debug_shapes {
+			self.draw_debug_shapes();
+		}
+	}
+
+	//Every line of HUD text to draw this frame, with its color
+	pub fn get_hud_lines(&self) -> Vec<(String, Vec4)> {
+		self.hud.lines(self.player.get_pos(), self.player.get_vel())
+	}
+
+	//Queues up the built in debug shapes for this timestep - the player's velocity, and the bounding boxes culling uses (the closest thing to collision shapes right now)
+	fn draw_debug_shapes(&mut self) {
+		//The player's at the camera, so start the arrow a bit below it to be able to see it
+		let arrow_start = self.player.get_pos() - Vec3::Y;
+		self.debug_draw.arrow(arrow_start, arrow_start + self.player.get_vel() * DEBUG_VELOCITY_ARROW_SCALE, YELLOW, None);
+
+		for object in &self.objects {
+			let bounds = self.mesh_bounds[object.mesh].transformed(object.transform.to_matrix());
+			self.debug_draw.aabb(&bounds, WHITE, None);
+		}
+		for object in &self.dynamic_objects {
+			let bounds = object.mesh_data.bounds().transformed(object.transform.to_matrix());
+			self.debug_draw.aabb(&bounds, YELLOW, None);
+		}
+
+		//Which way the world axes go
+		self.debug_draw.axes(Mat4::IDENTITY, 1.0, None);
 	}
 }
\ No newline at end of file
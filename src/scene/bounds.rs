@@ -0,0 +1,197 @@
+use glam::f32::{Mat4, Vec3, Vec4};
+
+//Axis aligned bounding box - the smallest box lined up with the axes that fits around some geometry
+//Used for frustum culling. Meshes get one in model space when they're added, which gets moved into world space by the object's transform
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Aabb {
+	pub min: Vec3,
+	pub max: Vec3,
+}
+
+impl Aabb {
+	//Box around a bunch of points. No points gives an "empty" box (min > max) that never touches anything
+	pub fn from_points(points: impl IntoIterator<Item = Vec3>) -> Aabb {
+		let mut aabb = Aabb {
+			min: Vec3::INFINITY,
+			max: Vec3::NEG_INFINITY,
+		};
+		for point in points {
+			aabb.min = aabb.min.min(point);
+			aabb.max = aabb.max.max(point);
+		}
+		aabb
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.min.cmpgt(self.max).any()
+	}
+
+	pub fn center(&self) -> Vec3 {
+		(self.min + self.max) * 0.5
+	}
+
+	//Half the size on each axis
+	pub fn half_extents(&self) -> Vec3 {
+		(self.max - self.min) * 0.5
+	}
+
+	//Box around this box after it's been moved by a matrix (usually a model matrix, to get a world space box)
+	//Rotated boxes come out a bit bigger than they need to be, since the new box has to line up with the axes again
+	pub fn transformed(&self, matrix: Mat4) -> Aabb {
+		if self.is_empty() {
+			return *self
+		}
+
+		//Move the center like a point, then work out how far the rotated/scaled corners can reach on each axis (abs of the matrix * half extents)
+		let center = matrix.transform_point3(self.center());
+		let half_extents = self.half_extents();
+		let reach = matrix.x_axis.truncate().abs() * half_extents.x + matrix.y_axis.truncate().abs() * half_extents.y + matrix.z_axis.truncate().abs() * half_extents.z;
+		Aabb {
+			min: center - reach,
+			max: center + reach,
+		}
+	}
+}
+
+//The 6 planes around what a camera can see, pointing inwards
+//Each plane is (normal xyz, distance w), so a point is on the inside when dot(normal, point) + distance >= 0
+#[derive(Debug, Clone, Copy)]
+pub struct Frustum {
+	pub planes: [Vec4; 6], //Left, right, bottom, top, near, far
+}
+
+impl Frustum {
+	//Pulls the planes straight out of a view projection matrix (the camera's render matrix) - gives world space planes
+	//A point is visible when -w <= x <= w, -w <= y <= w, and 0 <= z <= w in clip space (vulkan depth goes 0 to 1), and each of those is a plane made out of the matrix's rows
+	pub fn from_matrix(view_projection: Mat4) -> Frustum {
+		let row = |i: usize| view_projection.row(i);
+		let planes = [
+			row(3) + row(0), //Left
+			row(3) - row(0), //Right
+			row(3) + row(1), //Bottom
+			row(3) - row(1), //Top
+			row(2), //Near
+			row(3) - row(2), //Far
+		];
+
+		//Normalize so the w is an actual distance
+		Frustum {
+			planes: planes.map(|plane| plane / plane.truncate().length()),
+		}
+	}
+
+	//True if any of the box might be visible. Only false if the box is fully outside at least one plane
+	//Can let through some boxes that are just outside near the corners, but never culls something that's visible
+	pub fn intersects_aabb(&self, aabb: &Aabb) -> bool {
+		if aabb.is_empty() {
+			return false
+		}
+
+		self.planes.iter().all(|plane| {
+			//Corner of the box furthest along the plane's normal - if even that's behind the plane, the whole box is
+			let normal = plane.truncate();
+			let furthest_corner = Vec3::select(normal.cmpge(Vec3::ZERO), aabb.max, aabb.min);
+			normal.dot(furthest_corner) + plane.w >= 0.0
+		})
+	}
+}
+
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::scene::camera::Camera;
+	use glam::f32::{vec3, vec4, Quat};
+
+	//Camera at the origin looking down -z, 90 degree fov, square, near plane at 1 and far plane at 100 (0 to 1 depth)
+	//The side planes are at 45 degrees, so at z = -10 the frustum goes from -10 to 10 on x and y
+	fn test_frustum() -> Frustum {
+		let view = Mat4::look_at_rh(Vec3::ZERO, Vec3::NEG_Z, Vec3::Y);
+		let projection = Mat4::perspective_rh(std::f32::consts::FRAC_PI_2, 1.0, 1.0, 100.0);
+		Frustum::from_matrix(projection * view)
+	}
+
+	//Box of the given size around a point
+	fn cube(center: Vec3, half_size: f32) -> Aabb {
+		Aabb {
+			min: center - Vec3::splat(half_size),
+			max: center + Vec3::splat(half_size),
+		}
+	}
+
+	#[test]
+	fn box_inside() {
+		let frustum = test_frustum();
+		assert!(frustum.intersects_aabb(&cube(vec3(0.0, 0.0, -10.0), 1.0)));
+		assert!(frustum.intersects_aabb(&cube(vec3(5.0, -5.0, -50.0), 1.0)));
+	}
+
+	#[test]
+	fn box_outside() {
+		let frustum = test_frustum();
+		//Behind the camera, past the far plane, and off to each side
+		assert!(!frustum.intersects_aabb(&cube(vec3(0.0, 0.0, 10.0), 1.0)));
+		assert!(!frustum.intersects_aabb(&cube(vec3(0.0, 0.0, -200.0), 1.0)));
+		assert!(!frustum.intersects_aabb(&cube(vec3(-20.0, 0.0, -10.0), 1.0)));
+		assert!(!frustum.intersects_aabb(&cube(vec3(20.0, 0.0, -10.0), 1.0)));
+		assert!(!frustum.intersects_aabb(&cube(vec3(0.0, -20.0, -10.0), 1.0)));
+		assert!(!frustum.intersects_aabb(&cube(vec3(0.0, 20.0, -10.0), 1.0)));
+		//Empty boxes never show up
+		assert!(!frustum.intersects_aabb(&Aabb::from_points([])));
+	}
+
+	#[test]
+	fn box_straddling() {
+		let frustum = test_frustum();
+		//Half in on the left, half in past the far plane, and one the camera's inside of
+		assert!(frustum.intersects_aabb(&cube(vec3(-10.0, 0.0, -10.0), 1.0)));
+		assert!(frustum.intersects_aabb(&cube(vec3(0.0, 0.0, -100.0), 1.0)));
+		assert!(frustum.intersects_aabb(&cube(Vec3::ZERO, 5.0)));
+	}
+
+	//Near plane is at z = 0 in clip space (vulkan depth), not -w like opengl's
+	#[test]
+	fn near_plane() {
+		let frustum = test_frustum();
+		let [.., near, far] = frustum.planes;
+
+		//Planes are normalized, so w is the actual distance from the camera - near at 1, far at 100
+		assert!(near.abs_diff_eq(vec4(0.0, 0.0, -1.0, -1.0), 1e-5));
+		assert!(far.abs_diff_eq(vec4(0.0, 0.0, 1.0, 100.0), 1e-3));
+
+		//Between the camera and the near plane isn't visible, crossing the near plane is
+		assert!(!frustum.intersects_aabb(&Aabb {min: vec3(-0.1, -0.1, -0.9), max: vec3(0.1, 0.1, -0.1)}));
+		assert!(frustum.intersects_aabb(&Aabb {min: vec3(-0.1, -0.1, -1.5), max: vec3(0.1, 0.1, -0.5)}));
+	}
+
+	//Same as the others, but with the camera's own matrices - looking down +x from the origin
+	#[test]
+	fn camera_frustum() {
+		let frustum = Camera::new(Vec3::ZERO, vec3(1.0, 0.0, 0.0)).get_frustum();
+		assert!(frustum.intersects_aabb(&cube(vec3(10.0, 0.0, 0.0), 1.0)));
+		assert!(!frustum.intersects_aabb(&cube(vec3(-10.0, 0.0, 0.0), 1.0)));
+		assert!(!frustum.intersects_aabb(&cube(vec3(0.5, 0.0, 0.0), 0.1)));
+	}
+
+	//Rotated boxes get a new axis aligned box that fits around the rotated corners
+	#[test]
+	fn rotated_aabb() {
+		let aabb = cube(Vec3::ZERO, 1.0);
+
+		//45 degrees about y - corners reach out to sqrt(2) on x and z, y doesn't change
+		let rotation = Mat4::from_quat(Quat::from_rotation_y(std::f32::consts::FRAC_PI_4));
+		let rotated = aabb.transformed(Mat4::from_translation(vec3(5.0, 0.0, 0.0)) * rotation);
+		let reach = 2.0f32.sqrt();
+		assert!(rotated.min.abs_diff_eq(vec3(5.0 - reach, -1.0, -reach), 1e-5));
+		assert!(rotated.max.abs_diff_eq(vec3(5.0 + reach, 1.0, reach), 1e-5));
+
+		//Box that isn't centered on the origin, scaled then rotated 90 degrees about z - x and y swap
+		let aabb = Aabb {min: vec3(1.0, 2.0, 3.0), max: vec3(3.0, 4.0, 5.0)};
+		let transformed = aabb.transformed(Mat4::from_rotation_z(std::f32::consts::FRAC_PI_2) * Mat4::from_scale(vec3(2.0, 1.0, 1.0)));
+		assert!(transformed.min.abs_diff_eq(vec3(-4.0, 2.0, 3.0), 1e-5));
+		assert!(transformed.max.abs_diff_eq(vec3(-2.0, 6.0, 5.0), 1e-5));
+
+		//Empty stays empty
+		assert!(Aabb::from_points([]).transformed(rotation).is_empty());
+	}
+}
@@ -1,4 +1,5 @@
 use crate::constants::{WINDOW_WIDTH, WINDOW_HEIGHT, SENSITIVITY};
+use crate::scene::bounds::Frustum;
 
 use std::f32::consts::PI;
 use glam::f32::{vec3, Vec3, vec4, Mat4};
@@ -55,11 +56,7 @@ impl Camera {
 	//This will need to be called if any of the camera's fields are adjusted manually, rather than through Camera's methods
 	fn calc_matrices(&mut self) {
 		//Clamp pitch - keep it between -90 and 90 so neck won't break
-		if self.dir.x < -PI / 2.0 {
-			self.dir.x = -PI / 2.0;
-		} else if self.dir.x >= PI / 2.0 {
-			self.dir.x = PI / 2.0;
-		}
+		self.dir.x = self.dir.x.clamp(-PI / 2.0, PI / 2.0);
 
 		//Clamp yaw, keeping remainder - keep between 0 to 360 (full circle)
 		if self.dir.y < 0.0 {
@@ -69,11 +66,7 @@ impl Camera {
 		}
 
 		//Clamp roll - keep it between -90 and 90 so neck won't break
-		if self.dir.z < -PI / 2.0 {
-			self.dir.z = -PI / 2.0;
-		} else if self.dir.z >= PI / 2.0 {
-			self.dir.z = PI / 2.0;
-		}
+		self.dir.z = self.dir.z.clamp(-PI / 2.0, PI / 2.0);
 
 		//Calculate matrices
 		self.view_matrix = self.calc_view_matrix();
@@ -133,6 +126,11 @@ impl Camera {
 		self.render_matrix
 	}
 
+	//The planes around everything the camera can see (world space), from the render matrix
+	pub fn get_frustum(&self) -> Frustum {
+		Frustum::from_matrix(self.render_matrix)
+	}
+
 	//Get the "forward" direction in world space
 	pub fn get_forward_dir(&self) -> Vec3 {
 		let dir_normalized = self.dir;
@@ -179,10 +177,12 @@ impl Camera {
 
 	//Modifies the camera's aspect ratio based on new window dimensions
 	pub fn camera_window_resize(&mut self, window: &Window) {
-		let width = window.inner_size().width as f32;
-		let height = window.inner_size().height as f32;
+		self.camera_resize(window.inner_size().width, window.inner_size().height);
+	}
 
-		let aspect_ratio = width / height;
+	//Modifies the camera's aspect ratio based on the dimensions of whatever is being rendered to (window or offscreen image)
+	pub fn camera_resize(&mut self, width: u32, height: u32) {
+		let aspect_ratio = width as f32 / height as f32;
 		self.aspect_ratio = aspect_ratio;
 		self.calc_matrices();
 	}
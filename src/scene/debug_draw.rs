@@ -0,0 +1,150 @@
+use crate::render::DebugVertex;
+use crate::scene::bounds::Aabb;
+
+use glam::f32::{vec3, Mat4, Vec3};
+use std::f32::consts::PI;
+
+//Segments in each circle of a debug sphere
+const SPHERE_SEGMENTS: usize = 24;
+//Arrow heads are this fraction of the arrow's length
+const ARROW_HEAD_SIZE: f32 = 0.2;
+
+//Common colors for debug shapes
+pub const RED: Vec3 = vec3(1.0, 0.0, 0.0);
+pub const GREEN: Vec3 = vec3(0.0, 1.0, 0.0);
+pub const BLUE: Vec3 = vec3(0.0, 0.0, 1.0);
+pub const YELLOW: Vec3 = vec3(1.0, 1.0, 0.0);
+pub const WHITE: Vec3 = vec3(1.0, 1.0, 1.0);
+
+//One line segment, and how much longer it sticks around
+struct DebugLine {
+	start: Vec3,
+	end: Vec3,
+	color: Vec3,
+	lifetime: f32, //Seconds left. Zero or less means it goes away on the next tick
+}
+
+//Immediate mode debug drawing - queue up lines/shapes from anywhere (scene update, controls, etc) and they get drawn on top of the scene
+//Everything's in world space, and gets turned into lines right away
+//Lifetime is in seconds. None means just until the next timestep, so calling it every update keeps it up
+pub struct DebugDraw {
+	lines: Vec<DebugLine>,
+}
+
+impl DebugDraw {
+	pub fn new() -> DebugDraw {
+		DebugDraw {
+			lines: vec![],
+		}
+	}
+
+	//A line between two points
+	pub fn line(&mut self, start: Vec3, end: Vec3, color: Vec3, lifetime: Option<f32>) {
+		self.lines.push(DebugLine {
+			start,
+			end,
+			color,
+			lifetime: lifetime.unwrap_or(0.0),
+		});
+	}
+
+	//The 12 edges of a box
+	pub fn aabb(&mut self, aabb: &Aabb, color: Vec3, lifetime: Option<f32>) {
+		if aabb.is_empty() {
+			return
+		}
+		let corner = |x: bool, y: bool, z: bool| vec3(
+			if x {aabb.max.x} else {aabb.min.x},
+			if y {aabb.max.y} else {aabb.min.y},
+			if z {aabb.max.z} else {aabb.min.z},
+		);
+
+		//4 edges along each axis
+		for a in [false, true] {
+			for b in [false, true] {
+				self.line(corner(false, a, b), corner(true, a, b), color, lifetime);
+				self.line(corner(a, false, b), corner(a, true, b), color, lifetime);
+				self.line(corner(a, b, false), corner(a, b, true), color, lifetime);
+			}
+		}
+	}
+
+	//A sphere as 3 circles, one around each axis
+	pub fn sphere(&mut self, center: Vec3, radius: f32, color: Vec3, lifetime: Option<f32>) {
+		let axes = [(Vec3::X, Vec3::Y), (Vec3::Y, Vec3::Z), (Vec3::Z, Vec3::X)];
+		for (u, v) in axes {
+			let point = |i: usize| {
+				let angle = i as f32 / SPHERE_SEGMENTS as f32 * 2.0 * PI;
+				center + (u * angle.cos() + v * angle.sin()) * radius
+			};
+			for i in 0..SPHERE_SEGMENTS {
+				self.line(point(i), point(i + 1), color, lifetime);
+			}
+		}
+	}
+
+	//A line with an arrow head at the end - good for velocities, directions, etc
+	pub fn arrow(&mut self, start: Vec3, end: Vec3, color: Vec3, lifetime: Option<f32>) {
+		self.line(start, end, color, lifetime);
+
+		let direction = end - start;
+		let length = direction.length();
+		if length == 0.0 {
+			return
+		}
+
+		//4 lines back from the tip, spread out sideways
+		let (side_a, side_b) = (direction / length).any_orthonormal_pair();
+		let head_base = end - direction * ARROW_HEAD_SIZE;
+		let head_width = length * ARROW_HEAD_SIZE * 0.5;
+		for side in [side_a, -side_a, side_b, -side_b] {
+			self.line(end, head_base + side * head_width, color, lifetime);
+		}
+	}
+
+	//Red/green/blue lines along a transform's x/y/z axes, "size" long
+	pub fn axes(&mut self, transform: Mat4, size: f32, lifetime: Option<f32>) {
+		let origin = transform.transform_point3(Vec3::ZERO);
+		for (axis, color) in [(Vec3::X, RED), (Vec3::Y, GREEN), (Vec3::Z, BLUE)] {
+			self.line(origin, transform.transform_point3(axis * size), color, lifetime);
+		}
+	}
+
+	//Outline of what a camera can see, from its view projection matrix (the camera's render matrix)
+	pub fn frustum(&mut self, view_projection: Mat4, color: Vec3, lifetime: Option<f32>) {
+		//Corners of clip space (vulkan depth goes 0 to 1) back out into world space
+		let inverse = view_projection.inverse();
+		let corner = |x: f32, y: f32, z: f32| inverse.project_point3(vec3(x, y, z));
+
+		for z in [0.0, 1.0] {
+			self.line(corner(-1.0, -1.0, z), corner(1.0, -1.0, z), color, lifetime);
+			self.line(corner(1.0, -1.0, z), corner(1.0, 1.0, z), color, lifetime);
+			self.line(corner(1.0, 1.0, z), corner(-1.0, 1.0, z), color, lifetime);
+			self.line(corner(-1.0, 1.0, z), corner(-1.0, -1.0, z), color, lifetime);
+		}
+		for (x, y) in [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)] {
+			self.line(corner(x, y, 0.0), corner(x, y, 1.0), color, lifetime);
+		}
+	}
+
+	//Called once per timestep - gets rid of anything whose time is up, then counts down the rest
+	pub fn tick(&mut self, timestep: f32) {
+		self.lines.retain(|line| line.lifetime > 0.0);
+		for line in &mut self.lines {
+			line.lifetime -= timestep;
+		}
+	}
+
+	//Gets rid of everything, lifetime or not
+	pub fn clear(&mut self) {
+		self.lines.clear();
+	}
+
+	//Every line as a pair of vertices, for a line list
+	pub fn vertices(&self) -> Vec<DebugVertex> {
+		self.lines
+			.iter()
+			.flat_map(|line| [DebugVertex {pos: line.start, color: line.color}, DebugVertex {pos: line.end, color: line.color}])
+			.collect()
+	}
+}
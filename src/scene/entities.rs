@@ -57,6 +57,11 @@ impl Player {
 		self.pos
 	}
 
+	//Gets player velocity
+	pub fn get_vel(&self) -> Vec3 {
+		self.vel
+	}
+
 	//Adds a given speed in a given direction
 	pub fn move_grounded(&mut self, dir: Vec3) {
 		//Normalize the direction
@@ -0,0 +1,117 @@
+use crate::constants::{HUD_FRAME_SAMPLES, HUD_MESSAGE_LIFETIME, HUD_MAX_MESSAGES};
+
+use glam::f32::{vec4, Vec3, Vec4};
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+//Text colors for the HUD
+pub const HUD_STATS_COLOR: Vec4 = vec4(1.0, 1.0, 1.0, 1.0);
+pub const HUD_MESSAGE_COLOR: Vec4 = vec4(1.0, 0.9, 0.4, 1.0);
+
+//A message that shows up on the HUD for a while
+struct HudMessage {
+	text: String,
+	repeats: u32, //How many times in a row the same message came in. Shows up as "(x3)" instead of filling the screen with copies
+	lifetime: f32, //Seconds left before it goes away
+}
+
+//What gets shown on top of the screen - frame timing, player info, and messages from anywhere in the engine
+//The text gets put together here, the renderer just draws whatever "lines" gives it
+pub struct Hud {
+	pub visible: bool,
+	pub gpu_timings: Vec<(&'static str, f64)>, //Average GPU time (ms) of each part of the frame, set from the renderer every frame
+
+	messages: VecDeque<HudMessage>, //Oldest first
+	frame_samples: VecDeque<(Duration, Duration)>, //Last few frames' (time since the frame before, time spent working), for averaging
+	last_frame: Option<Instant>,
+}
+
+impl Hud {
+	pub fn new() -> Hud {
+		Hud {
+			visible: true,
+			gpu_timings: vec![],
+
+			messages: VecDeque::new(),
+			frame_samples: VecDeque::new(),
+			last_frame: None,
+		}
+	}
+
+	//Shows a message for a few seconds. Also goes to stdout, so there's still a log of it
+	pub fn message(&mut self, text: String) {
+		println!("{}", text);
+
+		//Same as the newest one - just count it and keep it up longer
+		if let Some(newest) = self.messages.back_mut().filter(|newest| newest.text == text) {
+			newest.repeats += 1;
+			newest.lifetime = HUD_MESSAGE_LIFETIME;
+			return
+		}
+
+		self.messages.push_back(HudMessage {
+			text,
+			repeats: 1,
+			lifetime: HUD_MESSAGE_LIFETIME,
+		});
+		if self.messages.len() > HUD_MAX_MESSAGES {
+			self.messages.pop_front();
+		}
+	}
+
+	//Called once per frame from the game loop with how long the frame's work took (before sleeping until the next one)
+	//Time between calls gives the actual frame rate
+	pub fn record_frame(&mut self, work_time: Duration) {
+		let now = Instant::now();
+		if let Some(last_frame) = self.last_frame {
+			self.frame_samples.push_back((now - last_frame, work_time));
+			if self.frame_samples.len() > HUD_FRAME_SAMPLES {
+				self.frame_samples.pop_front();
+			}
+		}
+		self.last_frame = Some(now);
+	}
+
+	//Called once per timestep - counts down the messages and gets rid of the expired ones
+	pub fn tick(&mut self, timestep: f32) {
+		for message in &mut self.messages {
+			message.lifetime -= timestep;
+		}
+		self.messages.retain(|message| message.lifetime > 0.0);
+	}
+
+	//Every line of text to draw, top to bottom, with its color
+	pub fn lines(&self, player_pos: Vec3, player_vel: Vec3) -> Vec<(String, Vec4)> {
+		let mut lines = vec![];
+
+		//Averaged over the last few frames, so the numbers are readable
+		let frame_stats = match self.frame_samples.len() {
+			0 => String::from("FPS: --"),
+			sample_count => {
+				let total_interval: Duration = self.frame_samples.iter().map(|(interval, _)| *interval).sum();
+				let total_work: Duration = self.frame_samples.iter().map(|(_, work_time)| *work_time).sum();
+				format!("FPS: {:.0}  Frame: {:.2} ms", sample_count as f64 / total_interval.as_secs_f64(), total_work.as_secs_f64() * 1000.0 / sample_count as f64)
+			},
+		};
+		lines.push((frame_stats, HUD_STATS_COLOR));
+		lines.push((format!("Pos: {:.2} {:.2} {:.2}", player_pos.x, player_pos.y, player_pos.z), HUD_STATS_COLOR));
+		lines.push((format!("Vel: {:.3} {:.3} {:.3}", player_vel.x, player_vel.y, player_vel.z), HUD_STATS_COLOR));
+		for (scope_name, milliseconds) in &self.gpu_timings {
+			lines.push((format!("GPU {}: {:.2} ms", scope_name, milliseconds), HUD_STATS_COLOR));
+		}
+
+		//Blank line between the stats and the messages
+		if !self.messages.is_empty() {
+			lines.push((String::new(), HUD_MESSAGE_COLOR));
+		}
+		for message in &self.messages {
+			let text = match message.repeats {
+				1 => message.text.clone(),
+				repeats => format!("{} (x{})", message.text, repeats),
+			};
+			lines.push((text, HUD_MESSAGE_COLOR));
+		}
+
+		lines
+	}
+}
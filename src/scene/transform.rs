@@ -0,0 +1,52 @@
+use glam::f32::{Mat4, Quat, Vec3};
+
+//Where something is in the world - translation, rotation, and scale, applied scale first, then rotation, then translation
+//Kept split up instead of as a matrix so it's easy to move/rotate things around. Gets turned into a model matrix when drawing
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Transform {
+	pub translation: Vec3,
+	pub rotation: Quat,
+	pub scale: Vec3,
+}
+
+impl Transform {
+	//Doesn't move anything
+	pub const IDENTITY: Transform = Transform {
+		translation: Vec3::ZERO,
+		rotation: Quat::IDENTITY,
+		scale: Vec3::ONE,
+	};
+
+	//Makes a transform from a translation, rotation, and scale
+	pub fn new(translation: Vec3, rotation: Quat, scale: Vec3) -> Transform {
+		Transform {
+			translation,
+			rotation,
+			scale,
+		}
+	}
+
+	//Just a translation
+	pub fn from_translation(translation: Vec3) -> Transform {
+		Transform {
+			translation,
+			..Transform::IDENTITY
+		}
+	}
+
+	//Splits a model matrix back up into a transform
+	//Matrices with shear (non-uniform scale on a parent, then a rotation on a child) can't be split exactly, so the shear gets lost
+	pub fn from_matrix(matrix: Mat4) -> Transform {
+		let (scale, rotation, translation) = matrix.to_scale_rotation_translation();
+		Transform {
+			translation,
+			rotation,
+			scale,
+		}
+	}
+
+	//Model matrix (model space -> world space) to hand to the vertex shader
+	pub fn to_matrix(self) -> Mat4 {
+		Mat4::from_scale_rotation_translation(self.scale, self.rotation, self.translation)
+	}
+}
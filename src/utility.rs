@@ -1,2 +1,5 @@
 pub mod read;
-pub mod debug;
\ No newline at end of file
+pub mod debug;
+pub mod image;
+pub mod capture;
+pub mod gltf;
\ No newline at end of file
@@ -0,0 +1,139 @@
+use crate::constants::{SCREENSHOT_DIR, CAPTURE_DIR, FPS};
+use crate::utility::image::write_png;
+
+use ash::vk;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+//Saves a frame copied back from the GPU ("VulkanApp::take_swapchain_readback") as a png in the screenshot folder
+//Named with a timestamp (milliseconds since the unix epoch) so they sort in order and never overwrite each other
+pub fn save_screenshot(extent: vk::Extent2D, pixels: &[u8]) {
+	let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).expect("System clock is before 1970").as_millis();
+	let path = Path::new(SCREENSHOT_DIR).join(format!("screenshot_{timestamp}.png"));
+
+	write_png(&path, extent.width, extent.height, pixels);
+	println!("Saved screenshot to {:?}", path);
+}
+
+
+//Formats a frame capture can be written in
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CaptureFormat {
+	PngSequence, //Every frame as its own numbered png in the capture folder
+	Y4m, //One uncompressed yuv 4:4:4 stream - most video tools (ffmpeg, etc) can read it directly
+}
+
+//Dumps every rendered frame to disk while it's running
+//Also keeps the virtual clock for the capture - every captured frame is exactly one timestep of game time (1/FPS), no matter how long writing it took
+pub struct FrameCapture {
+	format: CaptureFormat,
+	folder: PathBuf, //Where all the output goes for this capture
+	frame_count: u64, //Number of frames written so far
+	virtual_time: Duration, //Game time covered by the capture so far. Only counts timesteps that actually got written
+	frame_missed: bool, //Set when the last drawn frame never came back from the GPU (minimized, out of date swapchain). The game loop redraws that timestep instead of stepping
+
+	y4m_writer: Option<BufWriter<File>>, //Only used for y4m, opened on the first frame (need the size for the header)
+	frame_extent: Option<(u32, u32)>, //Size of the first frame. Y4m can't change size mid stream, so frames with a different size get dropped
+}
+
+impl FrameCapture {
+	//Starts a new capture in its own timestamped folder inside the capture folder
+	pub fn start(format: CaptureFormat) -> FrameCapture {
+		let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).expect("System clock is before 1970").as_millis();
+		let folder = Path::new(CAPTURE_DIR).join(format!("capture_{timestamp}"));
+		std::fs::create_dir_all(&folder).expect("Failed to create capture directory");
+
+		println!("Started {:?} capture in {:?}", format, folder);
+
+		FrameCapture {
+			format,
+			folder,
+			frame_count: 0,
+			virtual_time: Duration::ZERO,
+			frame_missed: false,
+
+			y4m_writer: None,
+			frame_extent: None,
+		}
+	}
+
+	//Writes out a frame copied back from the GPU ("VulkanApp::take_swapchain_readback"), then steps the virtual clock forward one timestep
+	//Frames all have to be the size of the first one (y4m can't change size mid stream). If the window got resized, nothing gets written and the capture should be stopped
+	pub fn capture_frame(&mut self, extent: vk::Extent2D, pixels: &[u8]) -> Result<(), String> {
+		let frame_extent = *self.frame_extent.get_or_insert((extent.width, extent.height));
+		if frame_extent != (extent.width, extent.height) {
+			return Err(format!("Stopped capture - window resized from {:?} to {:?}", frame_extent, (extent.width, extent.height)))
+		}
+		self.frame_missed = false;
+
+		match self.format {
+			CaptureFormat::PngSequence => {
+				let path = self.folder.join(format!("frame_{:06}.png", self.frame_count));
+				write_png(&path, extent.width, extent.height, pixels);
+			},
+			CaptureFormat::Y4m => {
+				self.write_y4m_frame(extent.width, extent.height, pixels);
+			},
+		}
+
+		self.frame_count += 1;
+		self.virtual_time += Duration::from_secs_f32(1.0 / FPS);
+		Ok(())
+	}
+
+	//Notes that the frame for this timestep didn't come back, so it has to be drawn again before the game moves on
+	pub fn miss_frame(&mut self) {
+		self.frame_missed = true;
+	}
+
+	//Whether the game can step forward - false until the missed timestep has been drawn + written
+	pub fn ready_for_timestep(&self) -> bool {
+		!self.frame_missed
+	}
+
+	//Appends one frame to the y4m stream, writing the stream header first if this is the first frame
+	fn write_y4m_frame(&mut self, width: u32, height: u32, rgba: &[u8]) {
+		let folder = &self.folder;
+		let writer = self.y4m_writer.get_or_insert_with(|| {
+			let file = File::create(folder.join("capture.y4m")).expect("Failed to create y4m file");
+			let mut writer = BufWriter::new(file);
+			//Frame rate is the game's timestep, progressive, square pixels, full res chroma (4:4:4)
+			writeln!(writer, "YUV4MPEG2 W{width} H{height} F{}:1 Ip A1:1 C444", FPS as u32).expect("Failed to write y4m header");
+			writer
+		});
+
+		//Y4m frames are planar - all the Y, then all the U, then all the V
+		let pixel_count = (width * height) as usize;
+		let mut planes = vec![0u8; pixel_count * 3];
+		for (i, pixel) in rgba.chunks_exact(4).enumerate() {
+			let (y, u, v) = rgb_to_yuv(pixel[0], pixel[1], pixel[2]);
+			planes[i] = y;
+			planes[pixel_count + i] = u;
+			planes[2 * pixel_count + i] = v;
+		}
+
+		writer.write_all(b"FRAME\n").expect("Failed to write y4m frame");
+		writer.write_all(&planes).expect("Failed to write y4m frame");
+	}
+
+	//Ends the capture - flushes anything that's still buffered and prints a summary
+	pub fn finish(mut self) {
+		if let Some(writer) = &mut self.y4m_writer {
+			writer.flush().expect("Failed to flush y4m file");
+		}
+		println!("Finished capture: {:?} frames, {:.2}s of game time, in {:?}", self.frame_count, self.virtual_time.as_secs_f32(), self.folder);
+	}
+}
+
+//Converts an srgb pixel to limited range BT.601 yuv (what y4m players assume by default)
+fn rgb_to_yuv(r: u8, g: u8, b: u8) -> (u8, u8, u8) {
+	let (r, g, b) = (r as f32, g as f32, b as f32);
+
+	let y = 16.0 + (65.481 * r + 128.553 * g + 24.966 * b) / 255.0;
+	let u = 128.0 + (-37.797 * r - 74.203 * g + 112.0 * b) / 255.0;
+	let v = 128.0 + (112.0 * r - 93.786 * g - 18.214 * b) / 255.0;
+
+	(y.round() as u8, u.round() as u8, v.round() as u8)
+}
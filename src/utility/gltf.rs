@@ -0,0 +1,522 @@
+use glam::f32::{vec2, vec3, vec4, Mat4, Quat, Vec2, Vec3, Vec4};
+use serde::Deserialize;
+
+//Loader for glTF 2.0 binary files (.glb) - this is how levels get out of blender and into the engine
+//Only pulls out what the engine uses right now: node transforms, triangle meshes (indices, positions, normals, uvs), and material base colors
+//Textures, animations, skins, cameras, lights, etc are all ignored
+//Spec is here: https://registry.khronos.org/glTF/specs/2.0/glTF-2.0.html
+
+//Magic numbers for the glb container
+const GLB_MAGIC: u32 = 0x46546C67; //"glTF" in ascii
+const GLB_CHUNK_JSON: u32 = 0x4E4F534A; //"JSON"
+const GLB_CHUNK_BIN: u32 = 0x004E4942; //"BIN\0"
+
+//Accessor component types (same values as the GL enums)
+const COMPONENT_UNSIGNED_BYTE: u32 = 5121;
+const COMPONENT_UNSIGNED_SHORT: u32 = 5123;
+const COMPONENT_UNSIGNED_INT: u32 = 5125;
+const COMPONENT_FLOAT: u32 = 5126;
+
+//Primitive topology for triangle lists - the only mode the engine draws
+const MODE_TRIANGLES: u32 = 4;
+
+
+//------------------------------------------ JSON CHUNK LAYOUT ------------------------------------------------
+//Just the parts of the glTF json that get used. Serde skips over everything else
+
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GltfJson {
+	scene: Option<usize>,
+	#[serde(default)]
+	scenes: Vec<GltfJsonScene>,
+	#[serde(default)]
+	nodes: Vec<GltfJsonNode>,
+	#[serde(default)]
+	meshes: Vec<GltfJsonMesh>,
+	#[serde(default)]
+	materials: Vec<GltfJsonMaterial>,
+	#[serde(default)]
+	accessors: Vec<GltfJsonAccessor>,
+	#[serde(default)]
+	buffer_views: Vec<GltfJsonBufferView>,
+}
+
+#[derive(Deserialize)]
+struct GltfJsonScene {
+	#[serde(default)]
+	nodes: Vec<usize>,
+}
+
+#[derive(Deserialize)]
+struct GltfJsonNode {
+	name: Option<String>,
+	mesh: Option<usize>,
+	#[serde(default)]
+	children: Vec<usize>,
+	//Transform is either a full column major matrix, or translation/rotation/scale (TRS). Missing parts are identity
+	matrix: Option<[f32; 16]>,
+	translation: Option<[f32; 3]>,
+	rotation: Option<[f32; 4]>, //Quaternion, xyzw
+	scale: Option<[f32; 3]>,
+}
+
+#[derive(Deserialize)]
+struct GltfJsonMesh {
+	name: Option<String>,
+	primitives: Vec<GltfJsonPrimitive>,
+}
+
+#[derive(Deserialize)]
+struct GltfJsonPrimitive {
+	attributes: std::collections::HashMap<String, usize>, //Attribute name (POSITION, NORMAL, ...) -> accessor index
+	indices: Option<usize>,
+	material: Option<usize>,
+	mode: Option<u32>, //Defaults to triangles
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GltfJsonMaterial {
+	pbr_metallic_roughness: Option<GltfJsonPbr>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GltfJsonPbr {
+	base_color_factor: Option<[f32; 4]>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GltfJsonAccessor {
+	buffer_view: Option<usize>, //No buffer view means all zeroes (sparse accessors aren't supported)
+	#[serde(default)]
+	byte_offset: usize,
+	component_type: u32,
+	#[serde(default)]
+	normalized: bool,
+	count: usize,
+	#[serde(rename = "type")]
+	accessor_type: String, //SCALAR, VEC2, VEC3, ...
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GltfJsonBufferView {
+	buffer: usize,
+	#[serde(default)]
+	byte_offset: usize,
+	byte_length: usize,
+	byte_stride: Option<usize>, //Only for vertex attributes. Tightly packed if it's missing
+}
+
+
+//------------------------------------------ LOADED SCENE ------------------------------------------------
+
+
+//One draw's worth of geometry with a single material
+//Attributes are all the same length (one per vertex). Missing normals/uvs get filled with zeroes
+pub struct GltfPrimitive {
+	pub positions: Vec<Vec3>,
+	pub normals: Vec<Vec3>,
+	pub uvs: Vec<Vec2>,
+	pub indices: Vec<u32>, //Triangle list. Made up (0, 1, 2, ...) if the primitive isn't indexed
+	pub base_color: Vec4, //From the material - white if there's no material, per the spec
+}
+
+//A mesh placed in the world by a node
+//Nodes without meshes are only used for their transforms, so they don't show up here
+pub struct GltfObject {
+	pub name: String,
+	pub transform: Mat4, //Model to world, with all the parent node transforms already multiplied in
+	pub mesh_index: usize, //Which of the file's meshes this is - nodes placing the same mesh share it, so the geometry only has to be kept once
+	pub primitives: Vec<GltfPrimitive>,
+}
+
+//Parses a .glb file and returns every mesh in its default scene, transformed into world space by the node hierarchy
+//Panics if the file is malformed or uses something the loader can't handle - assets are baked into the exe, so a bad one should fail loudly
+pub fn load_glb(glb_bytes: &[u8]) -> Vec<GltfObject> {
+	let (json, bin) = split_glb_chunks(glb_bytes);
+	let gltf: GltfJson = serde_json::from_slice(json).expect("Failed to parse glTF json chunk");
+
+	//Default scene, or the first scene if one isn't specified. Some files have no scenes at all - just use every root node then
+	let root_nodes: Vec<usize> = match gltf.scenes.get(gltf.scene.unwrap_or(0)) {
+		Some(scene) => scene.nodes.clone(),
+		None => {
+			let child_nodes: Vec<usize> = gltf.nodes.iter().flat_map(|node| node.children.clone()).collect();
+			(0..gltf.nodes.len()).filter(|i| !child_nodes.contains(i)).collect()
+		},
+	};
+
+	//Walk the node tree, accumulating transforms on the way down
+	//Nodes get pushed on the stack backwards so objects come out in the same order as the file
+	let mut objects = vec![];
+	let mut node_stack: Vec<(usize, Mat4)> = root_nodes.iter().rev().map(|&node_index| (node_index, Mat4::IDENTITY)).collect();
+	while let Some((node_index, parent_transform)) = node_stack.pop() {
+		let node = gltf.nodes.get(node_index).expect("glTF node index out of range");
+		let transform = parent_transform * node_local_transform(node);
+
+		if let Some(mesh_index) = node.mesh {
+			let mesh = gltf.meshes.get(mesh_index).expect("glTF mesh index out of range");
+			let primitives = mesh.primitives
+				.iter()
+				.filter(|primitive| primitive.mode.unwrap_or(MODE_TRIANGLES) == MODE_TRIANGLES) //Skip points/lines/strips
+				.map(|primitive| load_primitive(&gltf, bin, primitive))
+				.collect();
+
+			//Name the object after the node, fall back on the mesh name
+			let name = node.name.clone().or(mesh.name.clone()).unwrap_or(format!("node_{node_index}"));
+			objects.push(GltfObject {
+				name,
+				transform,
+				mesh_index,
+				primitives,
+			});
+		}
+
+		for &child_index in node.children.iter().rev() {
+			node_stack.push((child_index, transform));
+		}
+	}
+
+	objects
+}
+
+//Splits a glb into its json chunk and binary chunk
+//Layout is a 12 byte header (magic, version, total length), then chunks of (length, type, data)
+fn split_glb_chunks(glb_bytes: &[u8]) -> (&[u8], &[u8]) {
+	let read_u32 = |offset: usize| -> u32 {
+		let bytes = glb_bytes.get(offset..offset + 4).expect("Unexpected end of glb file");
+		u32::from_le_bytes(bytes.try_into().unwrap())
+	};
+
+	assert!(read_u32(0) == GLB_MAGIC, "Not a glb file");
+	assert!(read_u32(4) == 2, "Only glTF 2.0 glb files are supported");
+	let total_length = (read_u32(8) as usize).min(glb_bytes.len());
+
+	let mut json = None;
+	let mut bin: &[u8] = &[];
+	let mut offset = 12;
+	while offset + 8 <= total_length {
+		let chunk_length = read_u32(offset) as usize;
+		let chunk_type = read_u32(offset + 4);
+		let chunk_data = glb_bytes.get(offset + 8..offset + 8 + chunk_length).expect("glb chunk runs past the end of the file");
+
+		match chunk_type {
+			GLB_CHUNK_JSON => json = Some(chunk_data),
+			GLB_CHUNK_BIN => bin = chunk_data,
+			_ => (), //Unknown chunks are allowed by the spec and should be skipped
+		}
+
+		offset += 8 + chunk_length;
+	}
+
+	(json.expect("glb file has no json chunk"), bin)
+}
+
+//Local transform of a node (relative to its parent)
+fn node_local_transform(node: &GltfJsonNode) -> Mat4 {
+	if let Some(matrix) = node.matrix {
+		return Mat4::from_cols_array(&matrix) //glTF matrices are column major, same as glam
+	}
+
+	let translation = node.translation.map_or(Vec3::ZERO, Vec3::from_array);
+	let rotation = node.rotation.map_or(Quat::IDENTITY, Quat::from_array);
+	let scale = node.scale.map_or(Vec3::ONE, Vec3::from_array);
+	Mat4::from_scale_rotation_translation(scale, rotation, translation)
+}
+
+//Reads all the attributes/indices for one primitive out of the binary chunk
+fn load_primitive(gltf: &GltfJson, bin: &[u8], primitive: &GltfJsonPrimitive) -> GltfPrimitive {
+	let position_accessor = *primitive.attributes.get("POSITION").expect("glTF primitive has no positions");
+	let positions: Vec<Vec3> = read_accessor(gltf, bin, position_accessor).iter().map(|v| vec3(v[0], v[1], v[2])).collect();
+	let vertex_count = positions.len();
+	//An empty mesh would end up as zero sized vulkan buffers, which aren't allowed
+	assert!(vertex_count > 0, "glTF primitive has no vertices");
+
+	let normals: Vec<Vec3> = match primitive.attributes.get("NORMAL") {
+		Some(&accessor_index) => read_accessor(gltf, bin, accessor_index).iter().map(|v| vec3(v[0], v[1], v[2])).collect(),
+		None => vec![Vec3::ZERO; vertex_count],
+	};
+	let uvs: Vec<Vec2> = match primitive.attributes.get("TEXCOORD_0") {
+		Some(&accessor_index) => read_accessor(gltf, bin, accessor_index).iter().map(|v| vec2(v[0], v[1])).collect(),
+		None => vec![Vec2::ZERO; vertex_count],
+	};
+	//The spec says every attribute has the same count. Vertices get built by zipping them together, so a short one would silently drop vertices the indices still point at
+	assert!(normals.len() == vertex_count && uvs.len() == vertex_count, "glTF primitive attributes have different counts ({} positions, {} normals, {} uvs)", vertex_count, normals.len(), uvs.len());
+
+	let indices: Vec<u32> = match primitive.indices {
+		Some(accessor_index) => read_indices(gltf, bin, accessor_index),
+		None => (0..vertex_count as u32).collect(),
+	};
+	assert!(!indices.is_empty(), "glTF primitive has no indices");
+	assert!(indices.iter().all(|&index| (index as usize) < vertex_count), "glTF index out of range of the vertex attributes");
+
+	let base_color = primitive.material
+		.and_then(|material_index| gltf.materials.get(material_index))
+		.and_then(|material| material.pbr_metallic_roughness.as_ref())
+		.and_then(|pbr| pbr.base_color_factor)
+		.map_or(vec4(1.0, 1.0, 1.0, 1.0), Vec4::from_array);
+
+	GltfPrimitive {
+		positions,
+		normals,
+		uvs,
+		indices,
+		base_color,
+	}
+}
+
+//Reads a float accessor (or a normalized integer one) out of the binary chunk as a list of elements, each up to 4 floats (unused components are 0)
+fn read_accessor(gltf: &GltfJson, bin: &[u8], accessor_index: usize) -> Vec<[f32; 4]> {
+	let (accessor, elements) = accessor_elements(gltf, bin, accessor_index);
+	let component_size = component_size(accessor.component_type);
+
+	elements
+		.iter()
+		.map(|element_bytes| {
+			let mut element = [0.0; 4];
+			for (component, component_bytes) in element.iter_mut().zip(element_bytes.chunks_exact(component_size)) {
+				*component = match accessor.component_type {
+					COMPONENT_FLOAT => f32::from_le_bytes(component_bytes.try_into().unwrap()),
+					COMPONENT_UNSIGNED_BYTE if accessor.normalized => component_bytes[0] as f32 / 255.0,
+					COMPONENT_UNSIGNED_SHORT if accessor.normalized => u16::from_le_bytes(component_bytes.try_into().unwrap()) as f32 / 65535.0,
+					other => panic!("glTF attribute has component type {:?}, expected float or normalized integer", other),
+				};
+			}
+			element
+		})
+		.collect()
+}
+
+//Reads an index accessor (unsigned byte/short/int scalars) out of the binary chunk
+fn read_indices(gltf: &GltfJson, bin: &[u8], accessor_index: usize) -> Vec<u32> {
+	let (accessor, elements) = accessor_elements(gltf, bin, accessor_index);
+
+	elements
+		.iter()
+		.map(|element_bytes| match accessor.component_type {
+			COMPONENT_UNSIGNED_BYTE => element_bytes[0] as u32,
+			COMPONENT_UNSIGNED_SHORT => u16::from_le_bytes(element_bytes[..2].try_into().unwrap()) as u32,
+			COMPONENT_UNSIGNED_INT => u32::from_le_bytes(element_bytes[..4].try_into().unwrap()),
+			other => panic!("glTF indices have component type {:?}, expected an unsigned integer", other),
+		})
+		.collect()
+}
+
+//Size in bytes of one component of an accessor
+fn component_size(component_type: u32) -> usize {
+	match component_type {
+		COMPONENT_UNSIGNED_BYTE => 1,
+		COMPONENT_UNSIGNED_SHORT => 2,
+		COMPONENT_UNSIGNED_INT | COMPONENT_FLOAT => 4,
+		other => panic!("Unsupported glTF component type {:?}", other),
+	}
+}
+
+//Finds the bytes for every element of an accessor, following the buffer view's stride
+//An accessor without a buffer view is all zeroes according to the spec (sparse accessors aren't supported)
+fn accessor_elements<'a>(gltf: &'a GltfJson, bin: &'a [u8], accessor_index: usize) -> (&'a GltfJsonAccessor, Vec<&'a [u8]>) {
+	const ZEROES: [u8; 16] = [0; 16];
+
+	let accessor = gltf.accessors.get(accessor_index).expect("glTF accessor index out of range");
+	let component_count = match accessor.accessor_type.as_str() {
+		"SCALAR" => 1,
+		"VEC2" => 2,
+		"VEC3" => 3,
+		"VEC4" => 4,
+		other => panic!("Unsupported glTF accessor type {:?}", other),
+	};
+	let element_size = component_count * component_size(accessor.component_type);
+
+	let Some(buffer_view_index) = accessor.buffer_view else {
+		return (accessor, vec![&ZEROES[..element_size]; accessor.count])
+	};
+	let buffer_view = gltf.buffer_views.get(buffer_view_index).expect("glTF buffer view index out of range");
+	assert!(buffer_view.buffer == 0, "Only the glb binary chunk is supported as a glTF buffer");
+
+	let view_bytes = bin.get(buffer_view.byte_offset..buffer_view.byte_offset + buffer_view.byte_length).expect("glTF buffer view runs past the end of the binary chunk");
+	let stride = buffer_view.byte_stride.unwrap_or(element_size); //Tightly packed unless it says otherwise
+
+	let elements = (0..accessor.count)
+		.map(|element_index| {
+			let element_offset = accessor.byte_offset + element_index * stride;
+			view_bytes.get(element_offset..element_offset + element_size).expect("glTF accessor runs past the end of its buffer view")
+		})
+		.collect();
+
+	(accessor, elements)
+}
+
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::utility::read;
+
+	//Packs a json string + binary chunk into a glb, padding both chunks out to 4 bytes like the spec wants
+	fn build_glb(json: &str, bin: &[u8]) -> Vec<u8> {
+		let mut json_chunk = json.as_bytes().to_vec();
+		json_chunk.resize(json_chunk.len().next_multiple_of(4), b' ');
+		let mut bin_chunk = bin.to_vec();
+		bin_chunk.resize(bin_chunk.len().next_multiple_of(4), 0);
+
+		let total_length = 12 + 8 + json_chunk.len() + 8 + bin_chunk.len();
+		let mut glb = vec![];
+		for word in [GLB_MAGIC, 2, total_length as u32, json_chunk.len() as u32, GLB_CHUNK_JSON] {
+			glb.extend_from_slice(&word.to_le_bytes());
+		}
+		glb.extend_from_slice(&json_chunk);
+		for word in [bin_chunk.len() as u32, GLB_CHUNK_BIN] {
+			glb.extend_from_slice(&word.to_le_bytes());
+		}
+		glb.extend_from_slice(&bin_chunk);
+		glb
+	}
+
+	//Every node in the sample level has a mesh, so they all come out, in file order
+	#[test]
+	fn sample_scene_objects() {
+		let objects = load_glb(&read::sample_scene_asset());
+
+		let names: Vec<&str> = objects.iter().map(|object| object.name.as_str()).collect();
+		assert_eq!(names, ["Cube", "Plane", "Cube.001", "Plane.001"]);
+		assert_eq!(objects.iter().map(|object| object.mesh_index).collect::<Vec<_>>(), [0, 1, 2, 3]);
+
+		//Cubes are 24 vertices (4 per face so the normals are flat) and 12 triangles, planes are 4 vertices and 2 triangles
+		for (object, (vertex_count, index_count)) in objects.iter().zip([(24, 36), (4, 6), (24, 36), (4, 6)]) {
+			assert_eq!(object.primitives.len(), 1);
+			let primitive = &object.primitives[0];
+			assert_eq!(primitive.positions.len(), vertex_count, "{}", object.name);
+			assert_eq!(primitive.normals.len(), vertex_count, "{}", object.name);
+			assert_eq!(primitive.uvs.len(), vertex_count, "{}", object.name);
+			assert_eq!(primitive.indices.len(), index_count, "{}", object.name);
+			assert!(primitive.indices.iter().all(|&index| (index as usize) < vertex_count), "{}", object.name);
+			//Every vertex gets used by some triangle
+			assert_eq!(*primitive.indices.iter().max().unwrap() as usize, vertex_count - 1, "{}", object.name);
+		}
+
+		//Cubes have a material, planes don't (so they're white)
+		assert!(objects[0].primitives[0].base_color.abs_diff_eq(vec4(0.8, 0.8, 0.8, 1.0), 1e-6));
+		assert_eq!(objects[1].primitives[0].base_color, Vec4::ONE);
+
+		//Positions stay in model space, inside the accessor's min/max
+		for position in &objects[0].primitives[0].positions {
+			assert!(position.abs().max_element() <= 1.0);
+		}
+	}
+
+	//Node translation/rotation/scale end up in the object transforms
+	#[test]
+	fn sample_scene_transforms() {
+		let objects = load_glb(&read::sample_scene_asset());
+
+		//Translation only
+		let cube_translation = vec3(-4.501229, 1.018902, -4.777886);
+		assert!(objects[0].transform.abs_diff_eq(Mat4::from_translation(cube_translation), 1e-5));
+
+		//Scale only
+		assert!(objects[1].transform.abs_diff_eq(Mat4::from_scale(Vec3::splat(6.986528)), 1e-5));
+
+		//Full TRS - scale, then rotate about y, then translate
+		let transform = objects[2].transform;
+		let translation = vec3(-4.501229, 1.493003, -1.052568);
+		assert!(transform.transform_point3(Vec3::ZERO).abs_diff_eq(translation, 1e-5));
+		let rotation = Quat::from_xyzw(0.0, 0.354153, 0.0, 0.935187);
+		let expected_corner = translation + rotation * vec3(1.0, 1.483306, 0.424853);
+		assert!(transform.transform_point3(Vec3::ONE).abs_diff_eq(expected_corner, 1e-4));
+	}
+
+	//Attributes sharing a buffer view with a stride (position + normal interleaved), and a child node inheriting its parent's transform
+	#[test]
+	fn interleaved_attributes_and_child_nodes() {
+		let mut bin = vec![];
+		let vertices = [
+			([0.0f32, 0.0, 0.0], [0.0f32, 0.0, 1.0]),
+			([1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+			([0.0, 1.0, 0.0], [1.0, 0.0, 0.0]),
+		];
+		for (position, normal) in vertices {
+			for component in position.iter().chain(&normal) {
+				bin.extend_from_slice(&component.to_le_bytes());
+			}
+		}
+
+		let json = r#"{
+			"scene": 0,
+			"scenes": [{"nodes": [0]}],
+			"nodes": [
+				{"name": "parent", "translation": [10, 0, 0], "children": [1]},
+				{"name": "child", "mesh": 0, "scale": [2, 2, 2]}
+			],
+			"meshes": [{"primitives": [{"attributes": {"POSITION": 0, "NORMAL": 1}}]}],
+			"accessors": [
+				{"bufferView": 0, "byteOffset": 0, "componentType": 5126, "count": 3, "type": "VEC3"},
+				{"bufferView": 0, "byteOffset": 12, "componentType": 5126, "count": 3, "type": "VEC3"}
+			],
+			"bufferViews": [{"buffer": 0, "byteOffset": 0, "byteLength": 72, "byteStride": 24}]
+		}"#;
+		let objects = load_glb(&build_glb(json, &bin));
+
+		//Only the node with a mesh comes out
+		assert_eq!(objects.len(), 1);
+		let object = &objects[0];
+		assert_eq!(object.name, "child");
+		assert!(object.transform.abs_diff_eq(Mat4::from_translation(vec3(10.0, 0.0, 0.0)) * Mat4::from_scale(Vec3::splat(2.0)), 1e-6));
+
+		let primitive = &object.primitives[0];
+		assert_eq!(primitive.positions, vertices.map(|(position, _)| Vec3::from_array(position)));
+		assert_eq!(primitive.normals, vertices.map(|(_, normal)| Vec3::from_array(normal)));
+		//No uvs or indices in the file - zeroed uvs, and indices made up in order
+		assert_eq!(primitive.uvs, [Vec2::ZERO; 3]);
+		assert_eq!(primitive.indices, [0, 1, 2]);
+	}
+
+	//A file cut off partway through the binary chunk fails loudly instead of reading garbage
+	#[test]
+	#[should_panic(expected = "glb chunk runs past the end of the file")]
+	fn truncated_chunk() {
+		let glb = read::sample_scene_asset();
+		load_glb(&glb[..glb.len() - 100]);
+	}
+
+	//A normal accessor shorter than the positions would drop vertices when they get zipped together, so it's rejected
+	#[test]
+	#[should_panic(expected = "glTF primitive attributes have different counts (3 positions, 2 normals, 3 uvs)")]
+	fn mismatched_attribute_counts() {
+		let mut bin = vec![];
+		for component in [0.0f32, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0] {
+			bin.extend_from_slice(&component.to_le_bytes());
+		}
+
+		let json = r#"{
+			"scene": 0,
+			"scenes": [{"nodes": [0]}],
+			"nodes": [{"name": "short_normals", "mesh": 0}],
+			"meshes": [{"primitives": [{"attributes": {"POSITION": 0, "NORMAL": 1}}]}],
+			"accessors": [
+				{"bufferView": 0, "byteOffset": 0, "componentType": 5126, "count": 3, "type": "VEC3"},
+				{"bufferView": 0, "byteOffset": 0, "componentType": 5126, "count": 2, "type": "VEC3"}
+			],
+			"bufferViews": [{"buffer": 0, "byteOffset": 0, "byteLength": 36}]
+		}"#;
+		load_glb(&build_glb(json, &bin));
+	}
+
+	//A primitive with no vertices would make zero sized buffers, so it's rejected
+	#[test]
+	#[should_panic(expected = "glTF primitive has no vertices")]
+	fn empty_primitive() {
+		let json = r#"{
+			"scene": 0,
+			"scenes": [{"nodes": [0]}],
+			"nodes": [{"name": "empty", "mesh": 0}],
+			"meshes": [{"primitives": [{"attributes": {"POSITION": 0}}]}],
+			"accessors": [{"bufferView": 0, "byteOffset": 0, "componentType": 5126, "count": 0, "type": "VEC3"}],
+			"bufferViews": [{"buffer": 0, "byteOffset": 0, "byteLength": 0}]
+		}"#;
+		load_glb(&build_glb(json, &[]));
+	}
+}
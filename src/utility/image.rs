@@ -0,0 +1,52 @@
+use std::fs::File;
+use std::io::{BufWriter, Read};
+use std::path::Path;
+
+//This module deals with image files on disk (screenshots, golden images for tests, etc)
+//Everything in here works with tightly packed 8 bit rgba pixels, row by row from the top left - same as "memory::read_image" gives back
+
+//Writes rgba pixels out as a png
+pub fn write_png(path: &Path, width: u32, height: u32, rgba: &[u8]) {
+	//Make sure the folder exists first
+	if let Some(parent) = path.parent() {
+		std::fs::create_dir_all(parent).expect("Failed to create image directory");
+	}
+
+	let file = File::create(path).expect("Failed to create png file");
+	let mut encoder = png::Encoder::new(BufWriter::new(file), width, height);
+	encoder.set_color(png::ColorType::Rgba);
+	encoder.set_depth(png::BitDepth::Eight);
+
+	let mut writer = encoder.write_header().expect("Failed to write png header");
+	writer.write_image_data(rgba).expect("Failed to write png data");
+}
+
+//Reads a png back in as rgba pixels. Returns (width, height, pixels)
+//Any png color type gets expanded to 8 bit rgba
+pub fn read_png(path: &Path) -> (u32, u32, Vec<u8>) {
+	let file = File::open(path).expect("Failed to open png file");
+	decode_png(file)
+}
+
+//Decodes png data (from a file, or bytes already in memory) into rgba pixels. Returns (width, height, pixels)
+pub fn decode_png<R: Read>(png_data: R) -> (u32, u32, Vec<u8>) {
+	let mut decoder = png::Decoder::new(png_data);
+	//Expand palettes/low bit depths, strip 16 bit down to 8
+	decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+
+	let mut reader = decoder.read_info().expect("Failed to read png header");
+	let mut buffer = vec![0; reader.output_buffer_size()];
+	let frame_info = reader.next_frame(&mut buffer).expect("Failed to read png data");
+	buffer.truncate(frame_info.buffer_size());
+
+	//Fill in whatever channels are missing so it's always rgba
+	let rgba = match frame_info.color_type {
+		png::ColorType::Rgba => buffer,
+		png::ColorType::Rgb => buffer.chunks_exact(3).flat_map(|p| [p[0], p[1], p[2], 255]).collect(),
+		png::ColorType::GrayscaleAlpha => buffer.chunks_exact(2).flat_map(|p| [p[0], p[0], p[0], p[1]]).collect(),
+		png::ColorType::Grayscale => buffer.iter().flat_map(|&p| [p, p, p, 255]).collect(),
+		png::ColorType::Indexed => panic!("Indexed png should have been expanded"),
+	};
+
+	(frame_info.width, frame_info.height, rgba)
+}
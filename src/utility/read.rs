@@ -12,6 +12,27 @@ pub fn fragment_shader() -> Vec<u8> {
 	include_bytes!("../render/shaders/fragment.spv").to_vec()
 }
 
+//Reads the debug render modes' fragment shader spirv code
+pub fn debug_fragment_shader() -> Vec<u8> {
+	include_bytes!("../render/shaders/debug.spv").to_vec()
+}
+
+//Reads the debug line pipeline's shaders' spirv code
+pub fn debug_line_vertex_shader() -> Vec<u8> {
+	include_bytes!("../render/shaders/debug_line_vert.spv").to_vec()
+}
+pub fn debug_line_fragment_shader() -> Vec<u8> {
+	include_bytes!("../render/shaders/debug_line_frag.spv").to_vec()
+}
+
+//Reads the overlay pipeline's shaders' spirv code
+pub fn overlay_vertex_shader() -> Vec<u8> {
+	include_bytes!("../render/shaders/overlay_vert.spv").to_vec()
+}
+pub fn overlay_fragment_shader() -> Vec<u8> {
+	include_bytes!("../render/shaders/overlay_frag.spv").to_vec()
+}
+
 //Reads vertex shader spirv code
 pub fn vertex_shader() -> Vec<u8> {
 	include_bytes!("../render/shaders/vertex.spv").to_vec()
@@ -21,4 +42,9 @@ pub fn vertex_shader() -> Vec<u8> {
 pub fn icon_asset() -> Icon {
 	let icon_bytes = include_bytes!("../assets/icon.rgba").to_vec();
 	Icon::from_rgba(icon_bytes, 32, 32).expect("Bad icon")
+}
+
+//Returns the sample level (glb exported from blender)
+pub fn sample_scene_asset() -> Vec<u8> {
+	include_bytes!("../assets/samplescene.glb").to_vec()
 }
\ No newline at end of file